@@ -0,0 +1,120 @@
+use std::{
+    error::Error,
+    io::{ErrorKind, Read as _, Write as _},
+};
+
+use bsb::{
+    bus::{invert, BusClient, BusTiming},
+    Field,
+};
+use clap::Args;
+use tokio::{
+    io::{AsyncReadExt as _, AsyncWriteExt as _, DuplexStream},
+    runtime::{Handle, Runtime},
+};
+
+use crate::port;
+
+/// Bytes that can be in transit between the serial port and the `BusClient`
+const BRIDGE_CAPACITY: usize = 1024;
+
+/// Arguments of the subcommands that send frames on the bus
+#[derive(Debug, Args)]
+pub struct BusArgs {
+    /// Baud rate of the bus
+    #[arg(long, default_value_t = BusTiming::bsb().baud())]
+    baud: u32,
+    /// Invert all sent and received bytes for adapters that handle the signal inverted
+    #[arg(long)]
+    invert: bool,
+    /// Own address on the bus
+    #[arg(long, default_value_t = 0x42)]
+    address: u8,
+}
+
+impl BusArgs {
+    /// Access `BusArgs.address`
+    #[must_use]
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+}
+
+/// Runtime of the subcommands that use a `BusClient`
+pub fn runtime() -> std::io::Result<Runtime> {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()
+}
+
+/// Id of a field given by its name or its hex id, e.g. "water_pressure" or "0x053d19f0"
+pub fn field_id(field: &str) -> Result<u32, Box<dyn Error>> {
+    if let Some(digits) = field.strip_prefix("0x") {
+        return Ok(u32::from_str_radix(digits, 16)?);
+    }
+    Field::by_name(field)
+        .map(Field::id)
+        .ok_or_else(|| format!("unknown field {field}").into())
+}
+
+/// Open the serial `port` and connect a `BusClient` to it. Two threads copy the bytes between
+/// the blocking port and the client until either side fails. Must be called inside the `runtime`
+pub fn connect(port: &str, args: &BusArgs) -> Result<BusClient<DuplexStream>, Box<dyn Error>> {
+    let mut reader = port::open(port, args.baud)?;
+    let mut writer = reader.try_clone()?;
+    let (client, bridge) = tokio::io::duplex(BRIDGE_CAPACITY);
+    let (mut from_client, mut to_client) = tokio::io::split(bridge);
+    let invert_bytes = args.invert;
+    let handle = Handle::current();
+    std::thread::spawn(move || {
+        let mut chunk = [0; 256];
+        loop {
+            let length = match reader.read(&mut chunk) {
+                Ok(length) => length,
+                // the bus is idle
+                Err(error) if error.kind() == ErrorKind::TimedOut => continue,
+                Err(_) => break,
+            };
+            let bytes = &mut chunk[..length];
+            if invert_bytes {
+                invert(bytes);
+            }
+            if handle.block_on(to_client.write_all(bytes)).is_err() {
+                return;
+            }
+        }
+        // the client sees the end of the connection
+        let _ = handle.block_on(to_client.shutdown());
+    });
+    let handle = Handle::current();
+    std::thread::spawn(move || {
+        let mut chunk = [0; 256];
+        while let Ok(length @ 1..) = handle.block_on(from_client.read(&mut chunk)) {
+            let bytes = &mut chunk[..length];
+            if invert_bytes {
+                invert(bytes);
+            }
+            if writer
+                .write_all(bytes)
+                .and_then(|()| writer.flush())
+                .is_err()
+            {
+                return;
+            }
+        }
+    });
+    Ok(BusClient::new(client, args.address))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::field_id;
+
+    #[test]
+    fn test_field_id() {
+        assert_eq!(field_id("water_pressure").unwrap(), 0x053d_19f0);
+        assert_eq!(field_id("0x053d19f0").unwrap(), 0x053d_19f0);
+        assert!(field_id("no_such_field").is_err());
+        assert!(field_id("0xzz").is_err());
+    }
+}
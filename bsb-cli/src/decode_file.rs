@@ -0,0 +1,77 @@
+use std::{io::Write, path::PathBuf};
+
+use bsb::convert::{decode_capture, to_csv, to_json_lines};
+use clap::{Args, ValueEnum};
+
+use crate::sniff::format_event;
+
+/// Arguments of the `decode-file` subcommand
+#[derive(Debug, Args)]
+pub struct DecodeFileArgs {
+    /// Raw bytes recorded from the bus, e.g. with `cat /dev/ttyUSB0 > capture.bin`
+    capture: PathBuf,
+    /// Output format of the decoded frames
+    #[arg(long, value_enum, default_value_t = Format::Pretty)]
+    format: Format,
+}
+
+/// Output formats of the `decode-file` subcommand
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// One JSON object per frame
+    Jsonl,
+    /// One CSV row per frame with a header
+    Csv,
+    /// One human-readable line per frame like `sniff`
+    Pretty,
+}
+
+/// Decode all frames of a capture file and print them in the chosen format
+pub fn run(args: &DecodeFileArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let raw = std::fs::read(&args.capture)?;
+    let count = decode(&raw, args.format, std::io::stdout().lock())?;
+    eprintln!("decoded {count} frames");
+    Ok(())
+}
+
+/// Write the frames of the `raw` capture to `out` in `format`, returns the number of frames
+fn decode<W: Write>(raw: &[u8], format: Format, mut out: W) -> std::io::Result<usize> {
+    let count = match format {
+        Format::Jsonl => to_json_lines(raw, &mut out)?,
+        Format::Csv => to_csv(raw, &mut out)?,
+        Format::Pretty => {
+            let events = decode_capture(raw);
+            for event in &events {
+                writeln!(out, "{}", format_event(event))?;
+            }
+            events.len()
+        }
+    };
+    out.flush()?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use bsb::{Frame, PacketType};
+
+    use super::{decode, Format};
+
+    #[test]
+    fn test_decode() {
+        let frame = Frame::new(66, 0, PacketType::Ret.into(), 0x053d_19f0, vec![0, 0, 15]).unwrap();
+        // noise before the frame and a truncated frame at the end are skipped
+        let raw = [vec![0x00, 0x01], frame.serialize(), vec![0xdc, 0x80]].concat();
+        let mut out = Vec::new();
+        assert_eq!(decode(&raw, Format::Pretty, &mut out).unwrap(), 1);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "0x00 -> 0x42 Ret   water_pressure = 1.5 (0x053d19f0)\n"
+        );
+        for format in [Format::Jsonl, Format::Csv] {
+            let mut out = Vec::new();
+            assert_eq!(decode(&raw, format, &mut out).unwrap(), 1);
+            assert!(String::from_utf8(out).unwrap().contains("water_pressure"));
+        }
+    }
+}
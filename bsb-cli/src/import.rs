@@ -0,0 +1,27 @@
+use std::path::PathBuf;
+
+use bsb::import::BsbLanImport;
+use clap::Args;
+
+/// Arguments of the `import-bsb-lan` subcommand
+#[derive(Debug, Args)]
+pub struct ImportArgs {
+    /// BSB-LAN parameter definitions, e.g. BSB_LAN_defs.h
+    defs: PathBuf,
+}
+
+/// Print the rows of all parameters that are missing in the field database in the format of
+/// bsb-fields.csv and report parameters with unsupported value types on stderr
+pub fn run(args: &ImportArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let defs = std::fs::read_to_string(&args.defs)?;
+    let import = BsbLanImport::parse(&defs);
+    let count = import.write_csv(std::io::stdout().lock())?;
+    for (id, value_type) in import.unmapped() {
+        eprintln!("skipped 0x{id:08x}: unsupported value type {value_type}");
+    }
+    eprintln!(
+        "imported {count} new fields, skipped {} parameters",
+        import.unmapped().len()
+    );
+    Ok(())
+}
@@ -0,0 +1,98 @@
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+
+mod bus;
+mod decode_file;
+mod import;
+mod monitor;
+mod port;
+mod scan;
+mod set;
+mod sniff;
+mod telegraf;
+
+/// Command line tools for the BSB protocol
+#[derive(Debug, Parser)]
+#[command(name = "bsb-cli", version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Decode and print all frames on the bus
+    Sniff(sniff::SniffArgs),
+    /// Decode the frames of a raw capture file
+    DecodeFile(decode_file::DecodeFileArgs),
+    /// Convert BSB-LAN parameter definitions into rows of the field database
+    ImportBsbLan(import::ImportArgs),
+    /// Show a live table of the values on the bus and get or set fields interactively
+    Monitor(monitor::MonitorArgs),
+    /// Report the devices on the bus or the fields a device answers
+    Scan(scan::ScanArgs),
+    /// Write a value to a field of a device
+    Set(set::SetArgs),
+    /// Print the values on the bus as line protocol for Telegraf's `execd` input
+    Telegraf(telegraf::TelegrafArgs),
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = match cli.command {
+        Command::Sniff(args) => sniff::run(&args),
+        Command::DecodeFile(args) => decode_file::run(&args),
+        Command::ImportBsbLan(args) => import::run(&args),
+        Command::Monitor(args) => monitor::run(&args),
+        Command::Scan(args) => scan::run(&args),
+        Command::Set(args) => set::run(&args),
+        Command::Telegraf(args) => telegraf::run(&args),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("bsb-cli: {}", report(error.as_ref()));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// The `error` followed by its sources, e.g.
+/// "Parse failed for field 0x053d19f0: invalid float literal"
+fn report(error: &dyn std::error::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(error) = source {
+        let text = error.to_string();
+        // some errors already include the text of their source
+        if !message.ends_with(&text) {
+            message = format!("{message}: {text}");
+        }
+        source = error.source();
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use bsb::{BsbError, ErrorContext, Operation};
+
+    use super::report;
+
+    #[test]
+    fn test_report() {
+        let error = BsbError::InvalidFieldValue
+            .with_context(ErrorContext::new(Operation::Parse, 0x053d_19f0));
+        assert_eq!(
+            report(&error),
+            "Parse failed for field 0x053d19f0: cannot parse FieldValue string"
+        );
+        // a message that already contains its source is not repeated
+        let error = bsb::bus::ClientError::Request(error);
+        assert_eq!(
+            report(&error),
+            "cannot build the request: Parse failed for field 0x053d19f0: cannot parse FieldValue string"
+        );
+    }
+}
@@ -0,0 +1,273 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    io::{BufRead as _, Write as _},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use bsb::{
+    bus::{BusClient, ClientError},
+    CapabilityCache, DecodedEvent, FieldValue, Frame,
+};
+use clap::Args;
+use tokio::{io::DuplexStream, sync::broadcast::error::RecvError};
+
+use crate::{
+    bus::{self, BusArgs},
+    sniff::format_event,
+};
+
+/// Commands of the monitor
+const HELP: &str = "get <field>          request a field, e.g. get water_pressure
+set <field> <value>  write a value, e.g. set heating_circuit_1_mode Automatic
+show                 redraw the table
+quit                 leave the monitor";
+
+/// Clear the terminal and move the cursor to the top left
+const CLEAR: &str = "\x1b[2J\x1b[H";
+
+/// Arguments of the `monitor` subcommand
+#[derive(Debug, Args)]
+pub struct MonitorArgs {
+    /// Serial port of the bus adapter, e.g. /dev/ttyUSB0
+    port: String,
+    /// Address of the device for get and set
+    #[arg(long, default_value_t = 0)]
+    destination: u8,
+    /// Seconds between two redraws of the table when values changed
+    #[arg(long, default_value_t = 1)]
+    refresh: u64,
+    #[command(flatten)]
+    bus: BusArgs,
+}
+
+/// Latest value of every field seen on the bus by path, what devices answered and the output
+/// of the last command
+#[derive(Debug, Default)]
+struct Monitor {
+    values: BTreeMap<&'static str, FieldValue>,
+    capabilities: CapabilityCache,
+    output: String,
+    changed: bool,
+}
+
+impl Monitor {
+    /// Record a received `frame`, frames without a value of a known field only update the
+    /// capabilities
+    fn observe(&mut self, frame: &Frame) {
+        self.capabilities.record(frame);
+        if let Ok(field_value) = FieldValue::from_frame(frame) {
+            self.insert(field_value);
+        }
+    }
+
+    /// Show the `field_value` in the table
+    fn insert(&mut self, field_value: FieldValue) {
+        self.values.insert(field_value.path(), field_value);
+        self.changed = true;
+    }
+
+    /// One line per path with the value and its unit
+    fn table(&self) -> String {
+        let width = self.values.keys().map(|path| path.len()).max().unwrap_or(0);
+        self.values
+            .iter()
+            .map(|(path, field_value)| {
+                let unit = field_value
+                    .field()
+                    .unit()
+                    .map(|unit| format!(" {unit}"))
+                    .unwrap_or_default();
+                format!("{path:<width$}  {}{unit}\n", field_value.value_str())
+            })
+            .collect()
+    }
+
+    /// The whole terminal content: the table, the output of the last command and the prompt
+    fn screen(&mut self) -> String {
+        self.changed = false;
+        format!("{CLEAR}{}\n{}\n> ", self.table(), self.output)
+    }
+}
+
+/// A command typed into the monitor
+#[derive(Debug, PartialEq)]
+enum Action {
+    Get(u32),
+    Set(u32, String),
+    Show,
+    Help,
+    Quit,
+}
+
+impl Action {
+    /// Parse a line of input, fields are given by name or hex id
+    fn parse(line: &str) -> Result<Action, Box<dyn Error>> {
+        let line = line.trim();
+        let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let rest = rest.trim();
+        match command {
+            "get" => Ok(Action::Get(bus::field_id(rest)?)),
+            "set" => {
+                let (field, value) = rest.split_once(' ').ok_or("usage: set <field> <value>")?;
+                Ok(Action::Set(bus::field_id(field)?, value.trim().to_string()))
+            }
+            "show" | "" => Ok(Action::Show),
+            "help" => Ok(Action::Help),
+            "quit" | "exit" => Ok(Action::Quit),
+            _ => Err(format!("unknown command {command}, try help").into()),
+        }
+    }
+}
+
+/// Show a live table of the values on the bus and get or set fields until the input ends
+pub fn run(args: &MonitorArgs) -> Result<(), Box<dyn Error>> {
+    bus::runtime()?.block_on(async {
+        let client = bus::connect(&args.port, &args.bus)?;
+        let monitor = Arc::new(Mutex::new(Monitor {
+            output: HELP.to_string(),
+            changed: true,
+            ..Monitor::default()
+        }));
+        let mut frames = client.subscribe();
+        let observed = Arc::clone(&monitor);
+        tokio::spawn(async move {
+            loop {
+                match frames.recv().await {
+                    Ok(frame) => lock(&observed).observe(&frame),
+                    Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => return,
+                }
+            }
+        });
+        let redrawn = Arc::clone(&monitor);
+        let refresh = Duration::from_secs(args.refresh);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(refresh);
+            loop {
+                interval.tick().await;
+                let screen = {
+                    let mut monitor = lock(&redrawn);
+                    monitor.changed.then(|| monitor.screen())
+                };
+                if let Some(screen) = screen {
+                    print_screen(&screen);
+                }
+            }
+        });
+        // stdin blocks, so it is read on its own thread
+        let (sender, mut lines) = tokio::sync::mpsc::channel(1);
+        std::thread::spawn(move || {
+            for line in std::io::stdin().lock().lines().map_while(Result::ok) {
+                if sender.blocking_send(line).is_err() {
+                    return;
+                }
+            }
+        });
+        while let Some(line) = lines.recv().await {
+            let output = match Action::parse(&line) {
+                Ok(Action::Quit) => break,
+                Ok(action) => execute(&client, args.destination, &monitor, action).await,
+                Err(error) => Err(error),
+            };
+            let screen = {
+                let mut monitor = lock(&monitor);
+                monitor.output = output.unwrap_or_else(|error| format!("error: {error}"));
+                monitor.screen()
+            };
+            print_screen(&screen);
+        }
+        Ok(())
+    })
+}
+
+/// Run the `action` against the device at `destination`, returns the output to show
+async fn execute(
+    client: &BusClient<DuplexStream>,
+    destination: u8,
+    monitor: &Mutex<Monitor>,
+    action: Action,
+) -> Result<String, Box<dyn Error>> {
+    match action {
+        Action::Get(field_id) => {
+            if lock(monitor)
+                .capabilities
+                .is_unsupported(destination, field_id)
+            {
+                return Ok(format!(
+                    "0x{field_id:08x} is not supported by 0x{destination:02x}"
+                ));
+            }
+            let frame = match client.request(destination, field_id).await {
+                Ok(frame) | Err(ClientError::Rejected(frame)) => frame,
+                Err(error) => return Err(error.into()),
+            };
+            lock(monitor).observe(&frame);
+            Ok(format_event(&DecodedEvent::from_frame(&frame)))
+        }
+        Action::Set(field_id, value) => {
+            let field_value = FieldValue::from_value_str(&value, field_id)?;
+            let ack = client.set(destination, &field_value).await?;
+            lock(monitor).insert(field_value);
+            Ok(format_event(&DecodedEvent::from_frame(&ack)))
+        }
+        Action::Show => Ok(String::new()),
+        Action::Help | Action::Quit => Ok(HELP.to_string()),
+    }
+}
+
+fn print_screen(screen: &str) {
+    let mut out = std::io::stdout().lock();
+    // a closed terminal ends the monitor with the input
+    let _ = out.write_all(screen.as_bytes()).and_then(|()| out.flush());
+}
+
+fn lock(monitor: &Mutex<Monitor>) -> MutexGuard<'_, Monitor> {
+    monitor.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use bsb::{Frame, PacketType};
+
+    use super::{Action, Monitor};
+
+    #[test]
+    fn test_monitor_observe() {
+        let mut monitor = Monitor::default();
+        let ret = Frame::new(66, 0, PacketType::Ret.into(), 0x053d_19f0, vec![0, 0, 15]).unwrap();
+        let error = Frame::new(66, 0, PacketType::Error.into(), 0x2d3d_0574, vec![]).unwrap();
+        monitor.observe(&ret);
+        monitor.observe(&error);
+        assert!(monitor.changed);
+        assert_eq!(monitor.table(), "system/water_pressure  1.5 bar\n");
+        assert!(monitor.capabilities.is_unsupported(0, 0x2d3d_0574));
+        assert!(monitor
+            .screen()
+            .ends_with("system/water_pressure  1.5 bar\n\n\n> "));
+        assert!(!monitor.changed);
+    }
+
+    #[test]
+    fn test_action_parse() {
+        // (<line>, <action>)
+        let testcases = vec![
+            ("get water_pressure", Action::Get(0x053d_19f0)),
+            (" get  0x053d19f0 ", Action::Get(0x053d_19f0)),
+            (
+                "set heating_circuit_1_mode Automatic",
+                Action::Set(0x2d3d_0574, "Automatic".to_string()),
+            ),
+            ("", Action::Show),
+            ("help", Action::Help),
+            ("quit", Action::Quit),
+        ];
+        for (line, want) in testcases {
+            assert_eq!(Action::parse(line).unwrap(), want);
+        }
+        for line in ["get", "set water_pressure", "get no_such_field", "reboot"] {
+            assert!(Action::parse(line).is_err());
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use std::{error::Error, io::ErrorKind, time::Duration};
+
+use bsb::{
+    bus::{invert, BusTiming, Parity},
+    Frame, FrameStream,
+};
+use serialport::SerialPort;
+
+/// Open the serial `port` of the bus adapter with the framing of the BSB and `baud`.
+/// Reads time out after 1 second so an idle bus does not block forever
+pub fn open(port: &str, baud: u32) -> Result<Box<dyn SerialPort>, Box<dyn Error>> {
+    let timing = BusTiming::bsb().with_baud(baud);
+    let framing = timing.framing();
+    let port = serialport::new(port, timing.baud())
+        .data_bits(data_bits(framing.data_bits())?)
+        .parity(parity(framing.parity()))
+        .stop_bits(stop_bits(framing.stop_bits())?)
+        .timeout(Duration::from_secs(1))
+        .open()?;
+    Ok(port)
+}
+
+/// Read from the `port` and pass the frames of every received chunk to `handle` until the port
+/// or `handle` fails. With `invert_bytes` all received bytes are inverted first
+pub fn read_frames<F>(
+    port: &mut dyn SerialPort,
+    invert_bytes: bool,
+    mut handle: F,
+) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut(Vec<Frame>) -> Result<(), Box<dyn Error>>,
+{
+    let mut stream = FrameStream::new();
+    let mut chunk = [0; 256];
+    loop {
+        let length = match port.read(&mut chunk) {
+            Ok(length) => length,
+            // the bus is idle
+            Err(error) if error.kind() == ErrorKind::TimedOut => continue,
+            Err(error) => return Err(error.into()),
+        };
+        let bytes = &mut chunk[..length];
+        if invert_bytes {
+            invert(bytes);
+        }
+        stream.push_bytes(bytes);
+        handle(stream.by_ref().collect())?;
+    }
+}
+
+fn data_bits(data_bits: u8) -> Result<serialport::DataBits, String> {
+    match data_bits {
+        5 => Ok(serialport::DataBits::Five),
+        6 => Ok(serialport::DataBits::Six),
+        7 => Ok(serialport::DataBits::Seven),
+        8 => Ok(serialport::DataBits::Eight),
+        _ => Err(format!("unsupported number of data bits: {data_bits}")),
+    }
+}
+
+fn parity(parity: Parity) -> serialport::Parity {
+    match parity {
+        Parity::None => serialport::Parity::None,
+        Parity::Odd => serialport::Parity::Odd,
+        Parity::Even => serialport::Parity::Even,
+    }
+}
+
+fn stop_bits(stop_bits: u8) -> Result<serialport::StopBits, String> {
+    match stop_bits {
+        1 => Ok(serialport::StopBits::One),
+        2 => Ok(serialport::StopBits::Two),
+        _ => Err(format!("unsupported number of stop bits: {stop_bits}")),
+    }
+}
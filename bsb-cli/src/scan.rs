@@ -0,0 +1,231 @@
+use std::{
+    io::Write,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use bsb::{
+    bus::{AddressSelection, AddressSelector, Backoff, BusClient, ClientError, RetryPolicy},
+    Capability, CapabilityCache, Field,
+};
+use clap::{ArgGroup, Args, ValueEnum};
+use serde::Serialize;
+use tokio::{io::DuplexStream, sync::broadcast::error::RecvError};
+
+use crate::bus::{self, BusArgs};
+
+/// Arguments of the `scan` subcommand
+#[derive(Debug, Args)]
+#[command(group(ArgGroup::new("target").required(true).args(["addresses", "fields"])))]
+pub struct ScanArgs {
+    /// Serial port of the bus adapter, e.g. /dev/ttyUSB0
+    port: String,
+    /// Listen to the bus and report the addresses of all devices that send
+    #[arg(long)]
+    addresses: bool,
+    /// Request every field of the database and report how the device answers
+    #[arg(long)]
+    fields: bool,
+    /// Seconds to listen with `--addresses`
+    #[arg(long, default_value_t = 30)]
+    duration: u64,
+    /// Address of the device to scan with `--fields`
+    #[arg(long, default_value_t = 0)]
+    destination: u8,
+    /// JSON file with the capabilities of earlier scans, probed fields are skipped and new
+    /// results are added
+    #[arg(long)]
+    cache: Option<PathBuf>,
+    /// Format of the report
+    #[arg(long, value_enum, default_value_t = Format::Json)]
+    format: Format,
+    #[command(flatten)]
+    bus: BusArgs,
+}
+
+/// Formats of the scan report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// A JSON array with one object per row
+    Json,
+    /// CSV with a header
+    Csv,
+}
+
+/// Row of the `--addresses` report
+#[derive(Debug, Serialize)]
+struct AddressRow {
+    address: u8,
+}
+
+/// Row of the `--fields` report
+#[derive(Debug, PartialEq, Serialize)]
+struct FieldRow {
+    address: u8,
+    field_id: String,
+    name: Option<&'static str>,
+    status: &'static str,
+    payload_length: Option<usize>,
+    datatype: Option<String>,
+}
+
+/// Scan the bus for devices or the fields of a device and print the report
+pub fn run(args: &ScanArgs) -> Result<(), Box<dyn std::error::Error>> {
+    bus::runtime()?.block_on(async {
+        // a field that is not answered at once is not supported
+        let client = bus::connect(&args.port, &args.bus)?
+            .with_retry_policy(RetryPolicy::new(Backoff::Fixed(Duration::ZERO), 1));
+        let out = std::io::stdout().lock();
+        if args.addresses {
+            let selector = scan_addresses(&client, Duration::from_secs(args.duration)).await;
+            let rows: Vec<_> = selector
+                .active_addresses()
+                .iter()
+                .map(|address| AddressRow { address: *address })
+                .collect();
+            write_report(&rows, args.format, out)?;
+            match selector.select(client.address()) {
+                Some(AddressSelection::Preferred(address)) => {
+                    eprintln!("the own address 0x{address:02x} is unused");
+                }
+                Some(AddressSelection::Conflict { preferred, address }) => eprintln!(
+                    "the own address 0x{preferred:02x} is in use, use --address 0x{address:02x}"
+                ),
+                None => eprintln!("all client addresses are in use"),
+            }
+            return Ok(());
+        }
+        let mut cache = match &args.cache {
+            Some(path) if path.exists() => serde_json::from_slice(&std::fs::read(path)?)?,
+            _ => CapabilityCache::new(),
+        };
+        let mut field_ids: Vec<u32> = Field::iter().map(|(id, _)| *id).collect();
+        field_ids.sort_unstable();
+        let unanswered = scan_fields(&client, args.destination, &field_ids, &mut cache).await?;
+        if let Some(path) = &args.cache {
+            std::fs::write(path, serde_json::to_vec_pretty(&cache)?)?;
+        }
+        write_report(
+            &field_rows(&cache, args.destination, &field_ids),
+            args.format,
+            out,
+        )?;
+        eprintln!("{unanswered} fields were not answered");
+        Ok(())
+    })
+}
+
+/// Observe the source addresses of all frames on the bus for `duration`
+async fn scan_addresses(client: &BusClient<DuplexStream>, duration: Duration) -> AddressSelector {
+    let mut frames = client.subscribe();
+    let mut selector = AddressSelector::new(Instant::now(), duration);
+    let deadline = tokio::time::Instant::now() + duration;
+    loop {
+        match tokio::time::timeout_at(deadline, frames.recv()).await {
+            Ok(Ok(frame)) => selector.observe(&frame),
+            Ok(Err(RecvError::Lagged(_))) => {}
+            Ok(Err(RecvError::Closed)) | Err(_) => return selector,
+        }
+    }
+}
+
+/// Request all `field_ids` that were not probed yet from the device at `destination` and
+/// record the answers in the `cache`. Returns the number of fields without an answer
+async fn scan_fields(
+    client: &BusClient<DuplexStream>,
+    destination: u8,
+    field_ids: &[u32],
+    cache: &mut CapabilityCache,
+) -> Result<usize, ClientError> {
+    let mut unanswered = 0;
+    for field_id in field_ids {
+        if !cache.needs_probe(destination, *field_id) {
+            continue;
+        }
+        match client.request(destination, *field_id).await {
+            Ok(frame) | Err(ClientError::Rejected(frame)) => {
+                cache.record(&frame);
+            }
+            Err(ClientError::Timeout { .. }) => unanswered += 1,
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(unanswered)
+}
+
+/// Rows of the known capabilities of `field_ids` on the device at `address`
+fn field_rows(cache: &CapabilityCache, address: u8, field_ids: &[u32]) -> Vec<FieldRow> {
+    field_ids
+        .iter()
+        .filter_map(|field_id| {
+            let (status, payload_length, datatype) = match cache.get(address, *field_id)? {
+                Capability::Supported {
+                    payload_length,
+                    datatype,
+                } => (
+                    "supported",
+                    Some(payload_length),
+                    datatype.map(|datatype| datatype.to_string()),
+                ),
+                Capability::Unsupported => ("unsupported", None, None),
+            };
+            Some(FieldRow {
+                address,
+                field_id: format!("0x{field_id:08x}"),
+                name: Field::by_id(*field_id).map(Field::name),
+                status,
+                payload_length,
+                datatype,
+            })
+        })
+        .collect()
+}
+
+/// Write the `rows` to `out` in `format`
+fn write_report<T: Serialize, W: Write>(
+    rows: &[T],
+    format: Format,
+    mut out: W,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match format {
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut out, rows)?;
+            writeln!(out)?;
+        }
+        Format::Csv => {
+            let mut writer = csv::Writer::from_writer(out);
+            for row in rows {
+                writer.serialize(row)?;
+            }
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use bsb::{CapabilityCache, Frame, PacketType};
+
+    use super::{field_rows, write_report, Format};
+
+    #[test]
+    fn test_field_rows() {
+        let mut cache = CapabilityCache::new();
+        let ret = Frame::new(66, 0, PacketType::Ret.into(), 0x053d_19f0, vec![0, 0, 15]).unwrap();
+        let error = Frame::new(66, 0, PacketType::Error.into(), 0x2d3d_0574, vec![]).unwrap();
+        cache.record(&ret);
+        cache.record(&error);
+        // the third field was not answered
+        let rows = field_rows(&cache, 0, &[0x053d_19f0, 0x2d3d_0574, 0x313d_052f]);
+        assert_eq!(rows.len(), 2);
+        let mut out = Vec::new();
+        write_report(&rows, Format::Csv, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "address,field_id,name,status,payload_length,datatype\n\
+             0,0x053d19f0,water_pressure,supported,3,Float(10)\n\
+             0,0x2d3d0574,heating_circuit_1_mode,unsupported,,\n"
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use std::fmt::Write as _;
+
+use bsb::{bus::Confirmation, BsbError, DecodedEvent, FieldValue};
+use clap::{Args, ValueEnum};
+
+use crate::{
+    bus::{self, BusArgs},
+    sniff::format_event,
+};
+
+/// Arguments of the `set` subcommand
+#[derive(Debug, Args)]
+pub struct SetArgs {
+    /// Name or hex id of the field, e.g. heating_circuit_1_mode or 0x2d3d0574
+    field: String,
+    /// Value to write, e.g. "21.5", "Automatic" or "---" to unset it
+    value: String,
+    /// Print the bytes of the Set frame without sending it
+    #[arg(long)]
+    dry_run: bool,
+    /// Serial port of the bus adapter, e.g. /dev/ttyUSB0
+    #[arg(long, required_unless_present = "dry_run")]
+    port: Option<String>,
+    /// Address of the device to write to
+    #[arg(long, default_value_t = 0)]
+    destination: u8,
+    /// How the device confirms the write
+    #[arg(long, value_enum, default_value_t = Confirm::Ack)]
+    confirm: Confirm,
+    #[command(flatten)]
+    bus: BusArgs,
+}
+
+/// Command line names of `Confirmation`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Confirm {
+    /// Wait for the Ack frame
+    Ack,
+    /// Succeed if no Nack arrives within the response timeout
+    NoNack,
+    /// Like no-nack, then read the field back and compare it
+    ReadBack,
+}
+
+impl From<Confirm> for Confirmation {
+    fn from(confirm: Confirm) -> Self {
+        match confirm {
+            Confirm::Ack => Confirmation::Ack,
+            Confirm::NoNack => Confirmation::NoNack,
+            Confirm::ReadBack => Confirmation::ReadBack,
+        }
+    }
+}
+
+/// Write a value to a field, or only print the `Set` frame with `--dry-run`
+pub fn run(args: &SetArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let field_value = FieldValue::from_value_str(&args.value, bus::field_id(&args.field)?)?;
+    if args.dry_run {
+        print!(
+            "{}",
+            dry_run(&field_value, args.destination, args.bus.address())?
+        );
+        return Ok(());
+    }
+    let port = args.port.as_deref().ok_or("--port is required")?;
+    bus::runtime()?.block_on(async {
+        let client = bus::connect(port, &args.bus)?;
+        let response = client
+            .set_with(args.destination, &field_value, args.confirm.into())
+            .await?;
+        match response {
+            Some(frame) => println!("{}", format_event(&DecodedEvent::from_frame(&frame))),
+            None => println!("no Nack within the response timeout"),
+        }
+        Ok(())
+    })
+}
+
+/// The serialized `Set` frame of the `field_value` followed by one line per segment
+fn dry_run(field_value: &FieldValue, destination: u8, source: u8) -> Result<String, BsbError> {
+    let (bytes, segments) = field_value.dry_run_set(destination, source)?;
+    let mut lines = bytes
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<Vec<_>>()
+        .join(" ");
+    lines.push('\n');
+    for segment in segments {
+        // writing to a String cannot fail
+        let _ = writeln!(lines, "{segment}");
+    }
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use bsb::{BsbError, FieldValue};
+
+    use super::dry_run;
+
+    #[test]
+    fn test_dry_run() {
+        let field_value = FieldValue::from_value_str("Automatic", 0x2d3d_0574).unwrap();
+        let testcase = dry_run(&field_value, 0, 0x42).unwrap();
+        let mut lines = testcase.lines();
+        assert_eq!(lines.next().unwrap().split(' ').count(), 13);
+        assert_eq!(lines.next(), Some("sof: [dc]"));
+        assert_eq!(lines.next(), Some("source_address: [c2]"));
+        // a `Set` swaps the first two bytes of the field id
+        assert!(testcase.contains("field_id: [3d 2d 05 74]\npayload: [01 01]\n"));
+        // read-only fields are not written
+        let read_only = FieldValue::from_value_str("50", 0x313d_052f).unwrap();
+        assert_eq!(dry_run(&read_only, 0, 0x42), Err(BsbError::ReadOnlyField));
+    }
+}
@@ -0,0 +1,83 @@
+use std::io::Write;
+
+use bsb::{bus::BusTiming, DecodedEvent};
+use clap::Args;
+
+use crate::port;
+
+/// Arguments of the `sniff` subcommand
+#[derive(Debug, Args)]
+pub struct SniffArgs {
+    /// Serial port of the bus adapter, e.g. /dev/ttyUSB0
+    port: String,
+    /// Baud rate of the bus
+    #[arg(long, default_value_t = BusTiming::bsb().baud())]
+    baud: u32,
+    /// Print one JSON object per frame instead of a human-readable line
+    #[arg(long)]
+    json: bool,
+    /// Invert all received bytes for adapters that deliver the signal inverted
+    #[arg(long)]
+    invert: bool,
+}
+
+/// Open the serial port and print every received frame until the port fails
+pub fn run(args: &SniffArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let mut port = port::open(&args.port, args.baud)?;
+    let mut out = std::io::stdout().lock();
+    port::read_frames(&mut *port, args.invert, |frames| {
+        for frame in frames {
+            let event = DecodedEvent::from_frame(&frame);
+            if args.json {
+                writeln!(out, "{}", serde_json::to_string(&event)?)?;
+            } else {
+                writeln!(out, "{}", format_event(&event))?;
+            }
+        }
+        out.flush()?;
+        Ok(())
+    })
+}
+
+/// Human-readable line of a received frame, e.g.
+/// "0x00 -> 0x42 Ret   water_pressure = 1.5 (0x053d19f0)"
+pub fn format_event(event: &DecodedEvent) -> String {
+    let description = match (event.name(), event.value()) {
+        (Some(name), Some(value)) => format!("{name} = {value}"),
+        (Some(name), None) => name.to_string(),
+        _ => format!("payload [{}]", event.payload()),
+    };
+    format!(
+        "0x{:02x} -> 0x{:02x} {:<5} {description} ({})",
+        event.source_address(),
+        event.destination_address(),
+        event.packet_type(),
+        event.field_id(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use bsb::{DecodedEvent, Frame, PacketType};
+
+    use super::format_event;
+
+    #[test]
+    fn test_format_event() {
+        // (<frame>, <line>)
+        let testcases = vec![
+            (
+                Frame::new(66, 0, PacketType::Ret.into(), 0x053d_19f0, vec![0, 0, 15]).unwrap(),
+                "0x00 -> 0x42 Ret   water_pressure = 1.5 (0x053d19f0)",
+            ),
+            (
+                Frame::new(66, 0, PacketType::Ret.into(), 0x1234_5678, vec![0, 1]).unwrap(),
+                "0x00 -> 0x42 Ret   payload [0001] (0x12345678)",
+            ),
+        ];
+        for (frame, want) in testcases {
+            let testcase = format_event(&DecodedEvent::from_frame(&frame));
+            assert_eq!(testcase, want);
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use std::{
+    collections::BTreeMap,
+    io::{BufRead, Write},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use bsb::{bus::BusTiming, convert::line_protocol, FieldValue};
+use clap::Args;
+
+use crate::port;
+
+/// Arguments of the `telegraf` subcommand
+#[derive(Debug, Args)]
+pub struct TelegrafArgs {
+    /// Serial port of the bus adapter, e.g. /dev/ttyUSB0
+    port: String,
+    /// Baud rate of the bus
+    #[arg(long, default_value_t = BusTiming::bsb().baud())]
+    baud: u32,
+    /// Invert all received bytes for adapters that deliver the signal inverted
+    #[arg(long)]
+    invert: bool,
+}
+
+/// Latest value of every field received since the last batch, by field id
+type Latest = Mutex<BTreeMap<u32, FieldValue>>;
+
+/// Collect the values on the bus and print them as line protocol for Telegraf's `execd` input
+/// until stdin is closed or the process is terminated
+pub fn run(args: &TelegrafArgs) -> Result<(), Box<dyn std::error::Error>> {
+    #[cfg(unix)]
+    exit_on_signal()?;
+    let mut port = port::open(&args.port, args.baud)?;
+    let latest = Arc::new(Latest::default());
+    let received = Arc::clone(&latest);
+    let invert = args.invert;
+    std::thread::spawn(move || {
+        let result = port::read_frames(&mut *port, invert, |frames| {
+            let mut latest = lock(&received);
+            // requests and unknown fields carry no value
+            for field_value in frames
+                .iter()
+                .filter_map(|frame| FieldValue::from_frame(frame).ok())
+            {
+                latest.insert(field_value.field_id(), field_value);
+            }
+            Ok(())
+        });
+        if let Err(error) = result {
+            eprintln!("bsb-cli: {error}");
+            std::process::exit(1);
+        }
+    });
+    serve(std::io::stdin().lock(), std::io::stdout(), &latest)?;
+    Ok(())
+}
+
+/// Write one batch of line protocol with the `latest` values for every line on `input` like
+/// `signal = "STDIN"` of the `execd` input expects, until `input` is closed
+fn serve<R: BufRead, W: Write>(input: R, mut out: W, latest: &Latest) -> std::io::Result<()> {
+    for line in input.lines() {
+        line?;
+        let batch = std::mem::take(&mut *lock(latest));
+        let lines: String = batch
+            .values()
+            .map(|field_value| line_protocol(field_value, None) + "\n")
+            .collect();
+        // a single write keeps the batch whole when the process is terminated
+        out.write_all(lines.as_bytes())?;
+        out.flush()?;
+    }
+    Ok(())
+}
+
+fn lock(latest: &Latest) -> MutexGuard<'_, BTreeMap<u32, FieldValue>> {
+    latest.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Exit with success on SIGTERM or SIGINT once the batch that is being written is complete
+#[cfg(unix)]
+fn exit_on_signal() -> nix::Result<()> {
+    use nix::sys::signal::{SigSet, Signal};
+
+    let mut signals = SigSet::empty();
+    signals.add(Signal::SIGTERM);
+    signals.add(Signal::SIGINT);
+    // threads spawned afterwards inherit the mask, so only `wait` receives the signals
+    signals.thread_block()?;
+    std::thread::spawn(move || {
+        if signals.wait().is_ok() {
+            // the lock is held while a batch is written
+            let _ = std::io::stdout().lock().flush();
+            std::process::exit(0);
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use bsb::FieldValue;
+
+    use super::{serve, Latest};
+
+    #[test]
+    fn test_serve() {
+        let latest = Latest::default();
+        let field_value = FieldValue::from_value_str("1.5", 0x053d_19f0).unwrap();
+        latest
+            .lock()
+            .unwrap()
+            .insert(field_value.field_id(), field_value);
+        let mut out = Vec::new();
+        serve(Cursor::new("\n\n"), &mut out, &latest).unwrap();
+        // the second gather has no new values
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "bsb,name=water_pressure,path=system/water_pressure,unit=bar value=1.5\n"
+        );
+        assert!(latest.lock().unwrap().is_empty());
+    }
+}
@@ -9,40 +9,87 @@ use std::path::Path;
 struct Field {
     id: u32,
     name: String,
-    prognr: usize,
+    /// program number, optionally with a sub number, e.g. "8700.1"
+    prognr: String,
     data_type: String,
     path: String,
+    unit: Option<String>,
+    min: Option<f64>,
+    max: Option<f64>,
+    writable: bool,
+    /// labels of `Setting` values, e.g. "0=Off;1=On"
+    labels: Option<String>,
+}
+
+/// Convert a `limit` into the raw integer of the payload of `data_type`
+fn raw_limit(limit: Option<f64>, data_type: &str) -> String {
+    let factor = data_type
+        .strip_prefix("Float(")
+        .and_then(|factor| factor.strip_suffix(')'))
+        .map_or(1.0, |factor| {
+            factor.parse().expect("factor is not a number")
+        });
+    limit.map_or("None".to_string(), |limit| {
+        format!("Some({})", (limit * factor).round())
+    })
+}
+
+/// Convert the `labels` into a rust slice of `(<setting>, <label>)`
+fn labels(labels: Option<&str>) -> String {
+    let labels = labels
+        .into_iter()
+        .flat_map(|labels| labels.split(';'))
+        .map(|label| {
+            let (setting, label) = label
+                .split_once('=')
+                .expect("label is not <setting>=<label>");
+            let setting: u8 = setting.parse().expect("setting of label is not a number");
+            format!("({setting}, \"{label}\")")
+        })
+        .collect::<Vec<_>>();
+    format!("&[{}]", labels.join(", "))
 }
 
 /// location of the bsb field definition field
-const FIELD_DB_CSV: &'static str = "bsb-fields.csv";
+const FIELD_DB_CSV: &str = "bsb-fields.csv";
 /// location of the generated rust file
-const FIELD_DB_RS: &'static str = "field_db.rs";
+const FIELD_DB_RS: &str = "field_db.rs";
 
 fn main() {
     // Use the csv crate to parse the field definition database.
     let mut rdr = csv::Reader::from_path(FIELD_DB_CSV)
-        .expect(&format!("Failed to read CSV file {FIELD_DB_CSV}"));
+        .unwrap_or_else(|_| panic!("Failed to read CSV file {FIELD_DB_CSV}"));
 
     // Use phf to create a static map for the fields defined in `FIELD_DB_CSV`
     let mut builder = phf_codegen::Map::new();
     for field in rdr.deserialize() {
         let field: Field = field.expect("field in database could not be deserialized");
 
+        let (prognr, sub_prognr) = field.prognr.split_once('.').unwrap_or((&field.prognr, "0"));
+        let prognr: usize = prognr.parse().expect("prognr is not a number");
+        let sub_prognr: u8 = sub_prognr.parse().expect("sub prognr is not a number");
+        let min = raw_limit(field.min, &field.data_type);
+        let max = raw_limit(field.max, &field.data_type);
+        let labels = labels(field.labels.as_deref());
+        let unit = field
+            .unit
+            .map_or("None".to_string(), |unit| format!("Some(Unit::{unit})"));
         builder.entry(
             field.id,
             &format!(
-                "Field {{id: 0x{:08X}, name: \"{}\", prognr: {}, datatype: Datatype::{}, path: \"{}\"}}",
-                field.id, field.name, field.prognr, field.data_type, field.path
+                "Field {{id: 0x{:08X}, name: \"{}\", prognr: {}, sub_prognr: {}, datatype: Datatype::{}, path: \"{}\", unit: {}, min: {}, max: {}, writable: {}, labels: {}}}",
+                field.id, field.name, prognr, sub_prognr, field.data_type, field.path, unit, min, max, field.writable, labels
             ),
         );
     }
     // Write the generated code to $OUT_DIR/<FIELD_DB_RS>
     let out_dir = env::var("OUT_DIR").expect("OUT_DIR not defined");
     let dest_path = Path::new(&out_dir).join(FIELD_DB_RS);
-    let mut file = File::create(&dest_path).expect(&format!("Failed to create {}", FIELD_DB_RS));
+    let mut file =
+        File::create(&dest_path).unwrap_or_else(|_| panic!("Failed to create {FIELD_DB_RS}"));
     writeln!(file, "use crate::field;").unwrap();
     writeln!(file, "/// static field database").unwrap();
+    writeln!(file, "#[allow(clippy::unreadable_literal)]").unwrap();
     writeln!(
         file,
         "static FIELDS: phf::Map<u32, field::Field> = {};",
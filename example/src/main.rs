@@ -13,7 +13,7 @@ fn main() {
         factor: 10,
     };
     let field_value = FieldValue::new(field_id, value.clone()).unwrap();
-    let frame = Frame::new(66, 0, PacketType::Ret as u8, field_id, field_value.encode());
+    let frame = Frame::new(66, 0, PacketType::Ret as u8, field_id, field_value.encode()).unwrap();
     let encoded = frame.serialize();
     // the serialized form is identical to the above data
     assert_eq!(data.to_vec(), encoded);
@@ -0,0 +1,18 @@
+#![no_main]
+
+use bsb::{Frame, ParseResult};
+use libfuzzer_sys::fuzz_target;
+
+// arbitrary bytes from the serial line must never panic the parser
+fuzz_target!(|data: &[u8]| {
+    let mut input = data;
+    while let ParseResult::Ok { rest, frame } = Frame::parse(input) {
+        // every parsed frame has to survive a serialize/parse round trip
+        let bytes = frame.serialize();
+        let ParseResult::Ok { frame: reparsed, .. } = Frame::parse(&bytes) else {
+            panic!("serialized frame cannot be parsed");
+        };
+        assert_eq!(reparsed, frame);
+        input = rest;
+    }
+});
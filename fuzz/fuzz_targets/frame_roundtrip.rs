@@ -0,0 +1,13 @@
+#![no_main]
+
+use bsb::{Frame, ParseResult};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|frame: Frame| {
+    let bytes = frame.serialize();
+    let ParseResult::Ok { rest, frame: parsed } = Frame::parse(&bytes) else {
+        panic!("serialized frame cannot be parsed");
+    };
+    assert!(rest.is_empty());
+    assert_eq!(parsed, frame);
+});
@@ -0,0 +1,17 @@
+#![no_main]
+
+use bsb::{FrameStream, Quirks};
+use libfuzzer_sys::fuzz_target;
+
+// bytes from the serial line arrive in arbitrary chunks and with any quirks of the device,
+// the stream must never panic and every yielded frame has to be serializable
+fuzz_target!(|input: (u8, Vec<Vec<u8>>)| {
+    let (quirks, chunks) = input;
+    let mut stream = FrameStream::new().with_quirks(Quirks::from_bits_truncate(quirks));
+    for chunk in chunks {
+        stream.push_bytes(&chunk);
+        for frame in &mut stream {
+            let _ = frame.serialize_with(Quirks::from_bits_truncate(quirks));
+        }
+    }
+});
@@ -0,0 +1,11 @@
+#![no_main]
+
+use bsb::{Datatype, Value};
+use libfuzzer_sys::fuzz_target;
+
+// schedules are the only variable length payloads
+fuzz_target!(|payload: &[u8]| {
+    if let Ok(value) = Value::decode(payload, Datatype::Schedule) {
+        assert_eq!(Value::decode(&value.encode(), Datatype::Schedule), Ok(value));
+    }
+});
@@ -0,0 +1,13 @@
+#![no_main]
+
+use bsb::{Datatype, Value};
+use libfuzzer_sys::fuzz_target;
+
+// decode arbitrary payloads for every datatype, a successfully decoded value has to be stable
+fuzz_target!(|input: (Datatype, Vec<u8>)| {
+    let (datatype, payload) = input;
+    if let Ok(value) = Value::decode(&payload, datatype) {
+        let encoded = value.encode();
+        assert_eq!(Value::decode(&encoded, datatype), Ok(value));
+    }
+});
@@ -0,0 +1,304 @@
+//! Compact binary capture format with timestamps for long recordings.
+//!
+//! The file starts with the magic `BSBLOG` and a version byte, followed by blocks. Each block
+//! starts with an index header (record count, first and last timestamp, length in bytes) and
+//! contains the timestamped frames. A reader skips all blocks outside of the requested time range
+//! without reading their frames. All numbers are big endian, timestamps are microseconds since
+//! the unix epoch.
+//!
+//! A `Replayer` plays the frames of a log with their original timing.
+
+mod replay;
+
+pub use replay::{Replayer, Speed};
+
+use std::io::{Error, ErrorKind, Read, Seek, SeekFrom, Write};
+
+use chrono::{DateTime, Utc};
+
+use crate::{Frame, ParseResult};
+
+/// Magic bytes at the beginning of each binary log
+const MAGIC: &[u8; 6] = b"BSBLOG";
+
+/// Version of the format
+const VERSION: u8 = 1;
+
+/// Length of the file header
+const HEADER_LENGTH: u64 = 7;
+
+/// Marker byte of a block
+const BLOCK_MARKER: u8 = b'B';
+
+/// Length of a block header: marker, count, first and last timestamp, length
+const BLOCK_HEADER_LENGTH: usize = 1 + 2 + 8 + 8 + 4;
+
+/// Writes timestamped frames in the binary log format. Frames have to be appended in the order of
+/// their timestamps. Frames are buffered until a block is complete or `flush` is called
+#[derive(Debug)]
+pub struct BinlogWriter<W: Write> {
+    writer: W,
+    block_size: usize,
+    block: Vec<(i64, Vec<u8>)>,
+}
+
+impl<W: Write> BinlogWriter<W> {
+    /// Create a new `BinlogWriter` with `block_size` frames per block and write the file header
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to `writer` fails
+    pub fn new(mut writer: W, block_size: usize) -> std::io::Result<BinlogWriter<W>> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        Ok(BinlogWriter {
+            writer,
+            block_size: block_size.clamp(1, usize::from(u16::MAX)),
+            block: Vec::new(),
+        })
+    }
+
+    /// Append a `frame` received at `timestamp`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing a complete block fails
+    pub fn append(&mut self, timestamp: DateTime<Utc>, frame: &Frame) -> std::io::Result<()> {
+        self.block
+            .push((timestamp.timestamp_micros(), frame.serialize()));
+        if self.block.len() >= self.block_size {
+            self.write_block()?;
+        }
+        Ok(())
+    }
+
+    /// Write all buffered frames as a block and flush the writer
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.write_block()?;
+        self.writer.flush()
+    }
+
+    /// Flush all buffered frames and return the writer
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails
+    pub fn finish(mut self) -> std::io::Result<W> {
+        self.flush()?;
+        Ok(self.writer)
+    }
+
+    /// Write the buffered frames as one block
+    fn write_block(&mut self) -> std::io::Result<()> {
+        let (Some(&(first, _)), Some(&(last, _))) = (self.block.first(), self.block.last()) else {
+            return Ok(());
+        };
+        let length: usize = self
+            .block
+            .iter()
+            .map(|(_, bytes)| 8 + 1 + bytes.len())
+            .sum();
+        let invalid = |_| Error::new(ErrorKind::InvalidInput, "block too large");
+        let mut buffer = Vec::with_capacity(BLOCK_HEADER_LENGTH + length);
+        buffer.push(BLOCK_MARKER);
+        buffer.extend_from_slice(
+            &u16::try_from(self.block.len())
+                .map_err(invalid)?
+                .to_be_bytes(),
+        );
+        buffer.extend_from_slice(&first.to_be_bytes());
+        buffer.extend_from_slice(&last.to_be_bytes());
+        buffer.extend_from_slice(&u32::try_from(length).map_err(invalid)?.to_be_bytes());
+        for (timestamp, bytes) in self.block.drain(..) {
+            buffer.extend_from_slice(&timestamp.to_be_bytes());
+            // a serialized frame never exceeds `MAX_FRAME_LENGTH`
+            buffer.push(u8::try_from(bytes.len()).map_err(invalid)?);
+            buffer.extend_from_slice(&bytes);
+        }
+        self.writer.write_all(&buffer)
+    }
+}
+
+/// Header of a block in the binary log
+struct BlockHeader {
+    count: u16,
+    first: i64,
+    last: i64,
+    length: u32,
+}
+
+/// Reads timestamped frames from a binary log
+#[derive(Debug)]
+pub struct BinlogReader<R: Read + Seek> {
+    reader: R,
+}
+
+impl<R: Read + Seek> BinlogReader<R> {
+    /// Create a new `BinlogReader` and check the file header
+    ///
+    /// # Errors
+    ///
+    /// Returns an error with `ErrorKind::InvalidData` if `reader` is not a binary log
+    /// of a supported version or reading fails
+    pub fn new(mut reader: R) -> std::io::Result<BinlogReader<R>> {
+        let mut header = [0; MAGIC.len() + 1];
+        reader.read_exact(&mut header)?;
+        if &header[..MAGIC.len()] != MAGIC || header[MAGIC.len()] != VERSION {
+            return Err(Error::new(ErrorKind::InvalidData, "not a binary log"));
+        }
+        Ok(BinlogReader { reader })
+    }
+
+    /// Read all frames received at or after `from` and before `until`.
+    /// Blocks outside of this range are skipped without reading their frames
+    ///
+    /// # Errors
+    ///
+    /// Returns an error with `ErrorKind::InvalidData` if the log is corrupted or reading fails
+    pub fn read_range(
+        &mut self,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> std::io::Result<Vec<(DateTime<Utc>, Frame)>> {
+        let (from, until) = (from.timestamp_micros(), until.timestamp_micros());
+        self.reader.seek(SeekFrom::Start(HEADER_LENGTH))?;
+        let mut frames = Vec::new();
+        while let Some(header) = self.read_block_header()? {
+            if header.last < from || header.first >= until {
+                self.reader
+                    .seek(SeekFrom::Current(i64::from(header.length)))?;
+                continue;
+            }
+            for _ in 0..header.count {
+                let (timestamp, frame) = self.read_record()?;
+                if (from..until).contains(&timestamp) {
+                    let timestamp = DateTime::from_timestamp_micros(timestamp)
+                        .ok_or_else(|| corrupted("invalid timestamp"))?;
+                    frames.push((timestamp, frame));
+                }
+            }
+        }
+        Ok(frames)
+    }
+
+    /// Read all frames of the log
+    ///
+    /// # Errors
+    ///
+    /// Returns an error with `ErrorKind::InvalidData` if the log is corrupted or reading fails
+    pub fn read_all(&mut self) -> std::io::Result<Vec<(DateTime<Utc>, Frame)>> {
+        self.read_range(DateTime::<Utc>::MIN_UTC, DateTime::<Utc>::MAX_UTC)
+    }
+
+    /// Read the next block header or `None` at the end of the log
+    fn read_block_header(&mut self) -> std::io::Result<Option<BlockHeader>> {
+        let mut buffer = [0; BLOCK_HEADER_LENGTH];
+        match self.reader.read_exact(&mut buffer) {
+            Ok(()) => {}
+            Err(error) if error.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(error) => return Err(error),
+        }
+        if buffer[0] != BLOCK_MARKER {
+            return Err(corrupted("invalid block marker"));
+        }
+        Ok(Some(BlockHeader {
+            count: u16::from_be_bytes([buffer[1], buffer[2]]),
+            first: i64::from_be_bytes(buffer[3..11].try_into().unwrap_or_default()),
+            last: i64::from_be_bytes(buffer[11..19].try_into().unwrap_or_default()),
+            length: u32::from_be_bytes(buffer[19..23].try_into().unwrap_or_default()),
+        }))
+    }
+
+    /// Read one timestamped frame of a block
+    fn read_record(&mut self) -> std::io::Result<(i64, Frame)> {
+        let mut header = [0; 9];
+        self.reader.read_exact(&mut header)?;
+        let timestamp = i64::from_be_bytes(header[..8].try_into().unwrap_or_default());
+        let mut bytes = vec![0; usize::from(header[8])];
+        self.reader.read_exact(&mut bytes)?;
+        match Frame::parse(&bytes) {
+            ParseResult::Ok { frame, .. } => Ok((timestamp, frame)),
+            _ => Err(corrupted("invalid frame")),
+        }
+    }
+}
+
+/// Create an error for a corrupted log
+fn corrupted(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, ErrorKind};
+
+    use chrono::{DateTime, Utc};
+
+    use crate::Frame;
+
+    use super::{BinlogReader, BinlogWriter};
+
+    /// Create a timestamp `seconds` after the unix epoch
+    fn create_timestamp(seconds: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(seconds, 0).unwrap()
+    }
+
+    /// Create a log with one frame per second from 0 to 9 in blocks of 3 frames
+    fn create_log() -> Vec<u8> {
+        let mut writer = BinlogWriter::new(Vec::new(), 3).unwrap();
+        for seconds in 0..10 {
+            let frame = Frame::new(
+                66,
+                0,
+                7,
+                0x053d_19f0,
+                vec![0, 0, u8::try_from(seconds).unwrap()],
+            )
+            .unwrap();
+            writer.append(create_timestamp(seconds), &frame).unwrap();
+        }
+        writer.finish().unwrap()
+    }
+
+    #[test]
+    fn test_binlog_read_range() {
+        let mut reader = BinlogReader::new(Cursor::new(create_log())).unwrap();
+        assert_eq!(reader.read_all().unwrap().len(), 10);
+        // (<from>, <until>, <payload bytes of the result>)
+        let testcases = vec![
+            (0, 10, (0..10).collect::<Vec<_>>()),
+            (4, 7, vec![4, 5, 6]),
+            (20, 30, vec![]),
+        ];
+        for (from, until, want) in testcases {
+            let testcase = reader
+                .read_range(create_timestamp(from), create_timestamp(until))
+                .unwrap()
+                .iter()
+                .map(|(timestamp, frame)| {
+                    assert_eq!(timestamp.timestamp(), i64::from(frame.payload()[2]));
+                    frame.payload()[2]
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(testcase, want);
+        }
+    }
+
+    #[test]
+    fn test_binlog_invalid() {
+        let error = BinlogReader::new(Cursor::new(b"NOTALOG".to_vec())).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        let mut log = create_log();
+        // break the marker of the first block
+        log[7] = 0;
+        let mut reader = BinlogReader::new(Cursor::new(log)).unwrap();
+        assert_eq!(
+            reader.read_all().unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+    }
+}
@@ -0,0 +1,164 @@
+use std::{
+    io::{Read, Seek},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::Frame;
+
+use super::BinlogReader;
+
+/// Playback speed of a `Replayer`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Speed {
+    /// Replay with the original gaps divided by the factor, e.g. 1.0 or 10.0
+    Factor(f64),
+    /// Replay without any gaps
+    Max,
+}
+
+/// Replays frames of a binary log with their original timing. The `Replayer` does not sleep
+/// itself, `next_frame` returns how long to wait before the frame is fed to a parser or transport
+#[derive(Debug, Clone)]
+pub struct Replayer {
+    frames: Vec<(DateTime<Utc>, Frame)>,
+    position: usize,
+    /// Playback time of the previous frame or the seek target
+    time: Option<DateTime<Utc>>,
+    speed: Speed,
+}
+
+impl Replayer {
+    /// Create a new `Replayer` for `frames` ordered by their timestamp at realtime speed
+    #[must_use]
+    pub fn new(frames: Vec<(DateTime<Utc>, Frame)>) -> Replayer {
+        Replayer {
+            frames,
+            position: 0,
+            time: None,
+            speed: Speed::Factor(1.0),
+        }
+    }
+
+    /// Create a new `Replayer` for all frames of the log between `from` and `until`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the log is corrupted or reading fails
+    pub fn from_reader<R: Read + Seek>(
+        reader: &mut BinlogReader<R>,
+        from: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> std::io::Result<Replayer> {
+        Ok(Replayer::new(reader.read_range(from, until)?))
+    }
+
+    /// Return the `Replayer` with a different `speed`
+    #[must_use]
+    pub fn with_speed(mut self, speed: Speed) -> Replayer {
+        self.speed = speed;
+        self
+    }
+
+    /// Change the `speed` during playback
+    pub fn set_speed(&mut self, speed: Speed) {
+        self.speed = speed;
+    }
+
+    /// Continue the playback with the first frame received at or after `timestamp`
+    pub fn seek(&mut self, timestamp: DateTime<Utc>) {
+        self.position = self
+            .frames
+            .partition_point(|(frame_timestamp, _)| *frame_timestamp < timestamp);
+        self.time = Some(timestamp);
+    }
+
+    /// Timestamp of the next frame, `None` at the end
+    #[must_use]
+    pub fn position(&self) -> Option<DateTime<Utc>> {
+        self.frames
+            .get(self.position)
+            .map(|(timestamp, _)| *timestamp)
+    }
+
+    /// Take the next frame together with the time to wait since the previous frame or seek
+    pub fn next_frame(&mut self) -> Option<(Duration, DateTime<Utc>, Frame)> {
+        let (timestamp, frame) = self.frames.get(self.position)?.clone();
+        let gap = self
+            .time
+            .and_then(|time| (timestamp - time).to_std().ok())
+            .unwrap_or_default();
+        self.time = Some(timestamp);
+        let delay = match self.speed {
+            Speed::Factor(factor) if factor > 0.0 => gap.div_f64(factor),
+            Speed::Factor(_) | Speed::Max => Duration::ZERO,
+        };
+        self.position += 1;
+        Some((delay, timestamp, frame))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::{DateTime, Utc};
+
+    use crate::Frame;
+
+    use super::{Replayer, Speed};
+
+    /// Create a timestamp `seconds` after the unix epoch
+    fn create_timestamp(seconds: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(seconds, 0).unwrap()
+    }
+
+    fn create_replayer() -> Replayer {
+        let frames = [0, 10, 30, 60]
+            .into_iter()
+            .map(|seconds| {
+                (
+                    create_timestamp(seconds),
+                    Frame::new_get(0, 66, 0x053d_19f0),
+                )
+            })
+            .collect();
+        Replayer::new(frames)
+    }
+
+    #[test]
+    fn test_replayer_speed() {
+        // (<speed>, <delays in ms>)
+        let testcases = vec![
+            (Speed::Factor(1.0), vec![0, 10_000, 20_000, 30_000]),
+            (Speed::Factor(10.0), vec![0, 1000, 2000, 3000]),
+            (Speed::Max, vec![0, 0, 0, 0]),
+        ];
+        for (speed, want) in testcases {
+            let mut replayer = create_replayer().with_speed(speed);
+            let testcase = std::iter::from_fn(|| replayer.next_frame())
+                .map(|(delay, _, _)| delay)
+                .collect::<Vec<_>>();
+            let want = want
+                .into_iter()
+                .map(Duration::from_millis)
+                .collect::<Vec<_>>();
+            assert_eq!(testcase, want);
+        }
+    }
+
+    #[test]
+    fn test_replayer_seek() {
+        let mut replayer = create_replayer();
+        replayer.seek(create_timestamp(20));
+        assert_eq!(replayer.position(), Some(create_timestamp(30)));
+        // the first frame is delayed from the seek target
+        let (delay, timestamp, _) = replayer.next_frame().unwrap();
+        assert_eq!(delay, Duration::from_secs(10));
+        assert_eq!(timestamp, create_timestamp(30));
+        replayer.seek(create_timestamp(61));
+        assert_eq!(replayer.position(), None);
+        assert!(replayer.next_frame().is_none());
+    }
+}
@@ -0,0 +1,24 @@
+//! Building blocks for talking on the bus, independent of the actual transport
+mod address;
+#[cfg(feature = "tokio")]
+mod client;
+mod echo;
+mod idle;
+mod invert;
+mod mux;
+mod queue;
+mod retry;
+mod timing;
+mod transactions;
+
+pub use address::{AddressSelection, AddressSelector};
+#[cfg(feature = "tokio")]
+pub use client::{BusClient, ClientError, Confirmation};
+pub use echo::EchoFilter;
+pub use idle::IdleGate;
+pub use invert::{invert, Inverted};
+pub use mux::BusMux;
+pub use queue::{Priority, TransmitQueue};
+pub use retry::{Backoff, ErrorClass, RetryPolicy};
+pub use timing::{BusTiming, Framing, Parity};
+pub use transactions::{Transaction, TransactionId, TransactionState, TransactionTracker};
@@ -0,0 +1,126 @@
+use std::{
+    collections::BTreeSet,
+    ops::RangeInclusive,
+    time::{Duration, Instant},
+};
+
+use crate::Frame;
+
+/// Addresses that are tried for clients if the preferred address is in use.
+/// 0x7f is the broadcast address
+const CLIENT_ADDRESSES: RangeInclusive<u8> = 0x42..=0x7e;
+
+/// Result of `AddressSelector::select`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressSelection {
+    /// The preferred address is not used by any other device
+    Preferred(u8),
+    /// The preferred address is used by another device, `address` is unused
+    Conflict { preferred: u8, address: u8 },
+}
+
+impl AddressSelection {
+    /// The address the client should use
+    #[must_use]
+    pub fn address(&self) -> u8 {
+        match self {
+            AddressSelection::Preferred(address) | AddressSelection::Conflict { address, .. } => {
+                *address
+            }
+        }
+    }
+}
+
+/// Listens to the bus for a while to find an unused client address,
+/// e.g. to avoid a clash with an existing room unit
+#[derive(Debug, Clone)]
+pub struct AddressSelector {
+    started_at: Instant,
+    period: Duration,
+    active: BTreeSet<u8>,
+}
+
+impl AddressSelector {
+    /// Create a new `AddressSelector` that listens for `period` starting at `now`
+    #[must_use]
+    pub fn new(now: Instant, period: Duration) -> AddressSelector {
+        AddressSelector {
+            started_at: now,
+            period,
+            active: BTreeSet::new(),
+        }
+    }
+
+    /// Record the source address of a received `frame`
+    pub fn observe(&mut self, frame: &Frame) {
+        self.active.insert(frame.source_address());
+    }
+
+    /// Check if the listening period is over at `now`
+    #[must_use]
+    pub fn is_done(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.started_at) >= self.period
+    }
+
+    /// Access all source addresses seen so far
+    #[must_use]
+    pub fn active_addresses(&self) -> &BTreeSet<u8> {
+        &self.active
+    }
+
+    /// Select the `preferred` address or the first unused client address if it is in use.
+    /// Returns `None` if all client addresses are in use
+    #[must_use]
+    pub fn select(&self, preferred: u8) -> Option<AddressSelection> {
+        if !self.active.contains(&preferred) {
+            return Some(AddressSelection::Preferred(preferred));
+        }
+        CLIENT_ADDRESSES
+            .into_iter()
+            .find(|address| !self.active.contains(address))
+            .map(|address| AddressSelection::Conflict { preferred, address })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::Frame;
+
+    use super::{AddressSelection, AddressSelector};
+
+    #[test]
+    fn test_address_selector() {
+        let start = Instant::now();
+        let mut selector = AddressSelector::new(start, Duration::from_secs(10));
+        // a controller, a room unit and another client at 66
+        for source in [0, 6, 66] {
+            selector.observe(&Frame::new(0x7f, source, 2, 0x053d_0521, vec![0, 0, 15]).unwrap());
+        }
+        assert!(!selector.is_done(start + Duration::from_secs(5)));
+        assert!(selector.is_done(start + Duration::from_secs(10)));
+        // (<preferred address>, <selection>)
+        let testcases = vec![
+            (67, AddressSelection::Preferred(67)),
+            (
+                66,
+                AddressSelection::Conflict {
+                    preferred: 66,
+                    address: 67,
+                },
+            ),
+            (
+                6,
+                AddressSelection::Conflict {
+                    preferred: 6,
+                    address: 67,
+                },
+            ),
+        ];
+        for (preferred, want) in testcases {
+            assert_eq!(selector.select(preferred), Some(want));
+        }
+        assert_eq!(selector.select(66).unwrap().address(), 67);
+    }
+}
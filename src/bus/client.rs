@@ -0,0 +1,790 @@
+use std::{
+    collections::BTreeMap,
+    hash::{BuildHasher as _, Hasher as _, RandomState},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+    time::Instant,
+};
+
+use thiserror::Error;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt as _, AsyncWrite, AsyncWriteExt as _, WriteHalf},
+    sync::broadcast,
+    task::JoinHandle,
+};
+
+use crate::{
+    session::{ResponseFuture, Session, SessionError},
+    BsbError, BufferPool, FieldValue, Frame, FrameStream, PacketType,
+};
+
+use super::{
+    BusTiming, EchoFilter, ErrorClass, IdleGate, Priority, RetryPolicy, Transaction, TransactionId,
+    TransmitQueue,
+};
+
+/// Number of received frames a slow subscriber can lag behind before it misses frames
+const SUBSCRIBER_CAPACITY: usize = 64;
+
+/// Errors of a `BusClient`
+#[derive(Debug, Error)]
+pub enum ClientError {
+    #[error("cannot write to the bus: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("no response after {attempts} attempts")]
+    Timeout { attempts: u32 },
+    #[error("the device rejected the request")]
+    Rejected(Frame),
+    #[error("cannot build the request: {0}")]
+    Request(#[from] BsbError),
+    #[error("the connection to the bus is closed")]
+    Closed,
+    #[error("the device did not take over the written value")]
+    Mismatch(Frame),
+}
+
+/// How `BusClient::set_with` confirms that the device accepted a `Set`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Confirmation {
+    /// Wait for the `Ack` frame
+    #[default]
+    Ack,
+    /// Treat no `Nack` or `Error` within the response timeout as success, for devices that
+    /// acknowledge some `Set`s only with an `Info` broadcast or not at all
+    NoNack,
+    /// Like `Confirmation::NoNack`, then request the field and compare it with the written value
+    ReadBack,
+}
+
+/// How the response to a queued frame is tracked once it is written
+#[derive(Debug, Clone, Copy)]
+enum Tracking {
+    /// The frame expects no response, e.g. a plain `BusClient::send`
+    Untracked,
+    /// The frame starts a new request in the `Session`
+    Request,
+    /// The frame repeats the pending request `TransactionId`
+    Retry(TransactionId),
+}
+
+/// Result of writing a queued frame, `Some` if it started a new request
+type Written = Result<Option<ResponseFuture>, std::io::ErrorKind>;
+
+/// State shared between the callers of a `BusClient` and its reader task
+#[derive(Debug)]
+struct State {
+    session: Session,
+    queue: TransmitQueue,
+    idle: IdleGate,
+    echo: EchoFilter,
+    /// Queued frames of callers that still wait for them to be written
+    waiting: BTreeMap<u32, Tracking>,
+    /// Frames that were written by another caller holding the writer
+    written: BTreeMap<u32, Written>,
+    closed: bool,
+}
+
+impl State {
+    fn new(timing: &BusTiming) -> State {
+        State {
+            session: Session::new(),
+            queue: TransmitQueue::new(),
+            idle: idle_gate(timing),
+            echo: EchoFilter::new(timing.response_timeout()),
+            waiting: BTreeMap::new(),
+            written: BTreeMap::new(),
+            closed: false,
+        }
+    }
+}
+
+/// `IdleGate` that waits for the inter frame gap of the `timing`
+fn idle_gate(timing: &BusTiming) -> IdleGate {
+    let idle_characters = timing
+        .inter_frame_gap()
+        .as_nanos()
+        .checked_div(timing.character_time().as_nanos())
+        .unwrap_or_default();
+    IdleGate::new(timing, u32::try_from(idle_characters).unwrap_or(u32::MAX))
+}
+
+/// Access the shared `State`, a poisoned lock is recovered as the state is always valid
+fn lock(state: &Mutex<State>) -> MutexGuard<'_, State> {
+    state.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Current time of the tokio clock, so a paused clock also pauses the client
+fn now() -> Instant {
+    tokio::time::Instant::now().into_std()
+}
+
+/// Sample between 0.0 and 1.0 for the jitter of the `RetryPolicy`
+fn random() -> f64 {
+    let [a, b, c, d, ..] = RandomState::new().build_hasher().finish().to_be_bytes();
+    f64::from(u32::from_be_bytes([a, b, c, d])) / f64::from(u32::MAX)
+}
+
+/// A frame in the `TransmitQueue`, it is removed from the queue if the caller stops waiting
+struct Queued<'a> {
+    state: &'a Mutex<State>,
+    consumer: u32,
+}
+
+impl Drop for Queued<'_> {
+    fn drop(&mut self) {
+        let mut state = lock(self.state);
+        state.queue.cancel(self.consumer);
+        state.waiting.remove(&self.consumer);
+        state.written.remove(&self.consumer);
+    }
+}
+
+/// The echo and the request of a frame that is being written. They are undone if the
+/// write fails or is cancelled, as neither an echo nor a response will arrive
+struct Registered<'a> {
+    state: &'a Mutex<State>,
+    frame: Frame,
+    request: Option<TransactionId>,
+    kept: bool,
+}
+
+impl Registered<'_> {
+    /// Keep the registrations once the frame was written
+    fn keep(mut self) {
+        self.kept = true;
+    }
+}
+
+impl Drop for Registered<'_> {
+    fn drop(&mut self) {
+        if self.kept {
+            return;
+        }
+        let mut state = lock(self.state);
+        state.echo.unsent(&self.frame);
+        if let Some(id) = self.request {
+            state.session.expire(id, now());
+        }
+    }
+}
+
+/// Async client that owns the connection to the bus, e.g. a `tokio_serial::SerialStream`.
+/// Frames are sent through a `TransmitQueue` once the `IdleGate` allows it, requests are
+/// correlated with their responses by a `Session` and retried according to a `RetryPolicy`.
+/// A background task continuously reads and parses the received bytes, drops the echoes of
+/// sent frames, answers pending requests and forwards every received `Frame` to the
+/// subscribers. It must be created inside a tokio runtime
+#[derive(Debug)]
+pub struct BusClient<T> {
+    address: u8,
+    retry: RetryPolicy,
+    timing: BusTiming,
+    pool: Arc<BufferPool>,
+    writer: tokio::sync::Mutex<WriteHalf<T>>,
+    state: Arc<Mutex<State>>,
+    next_consumer: AtomicU32,
+    frames: broadcast::Sender<Frame>,
+    reader: JoinHandle<()>,
+}
+
+impl<T> BusClient<T>
+where
+    T: AsyncRead + AsyncWrite + Send + 'static,
+{
+    /// Create a new `BusClient` on the `port` that sends with the source `address`.
+    /// It uses `BusTiming::bsb` and the default `RetryPolicy`
+    pub fn new(port: T, address: u8) -> BusClient<T> {
+        let (reader, writer) = tokio::io::split(port);
+        let timing = BusTiming::bsb();
+        let state = Arc::new(Mutex::new(State::new(&timing)));
+        let (frames, _) = broadcast::channel(SUBSCRIBER_CAPACITY);
+        let reader = tokio::spawn(read_loop(reader, Arc::clone(&state), frames.clone()));
+        BusClient {
+            address,
+            retry: RetryPolicy::default(),
+            timing,
+            pool: Arc::new(BufferPool::default()),
+            writer: tokio::sync::Mutex::new(writer),
+            state,
+            next_consumer: AtomicU32::new(0),
+            frames,
+            reader,
+        }
+    }
+
+    /// Return the `BusClient` with the `RetryPolicy` of failed requests
+    #[must_use]
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> BusClient<T> {
+        self.retry = retry;
+        self
+    }
+
+    /// Return the `BusClient` with the `timing` of the bus, e.g. `BusTiming::lpb`.
+    /// It determines the response timeout and how long the bus has to be idle before sending
+    #[must_use]
+    pub fn with_timing(mut self, timing: BusTiming) -> BusClient<T> {
+        {
+            let mut state = lock(&self.state);
+            state.idle = idle_gate(&timing);
+            state.echo = EchoFilter::new(timing.response_timeout());
+        }
+        self.timing = timing;
+        self
+    }
+
+    /// Return the `BusClient` serializing frames into buffers of a `pool` that is shared
+    /// with other users of the bus, e.g. a `FrameStream`
+    #[must_use]
+    pub fn with_pool(mut self, pool: Arc<BufferPool>) -> BusClient<T> {
+        self.pool = pool;
+        self
+    }
+
+    /// Access `BusClient.address`
+    #[must_use]
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    /// Access `BusClient.retry`
+    #[must_use]
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry
+    }
+
+    /// Access `BusClient.timing`
+    #[must_use]
+    pub fn timing(&self) -> &BusTiming {
+        &self.timing
+    }
+
+    /// Receive every `Frame` read from the bus from now on, except the echoes of sent frames
+    #[must_use]
+    pub fn subscribe(&self) -> broadcast::Receiver<Frame> {
+        self.frames.subscribe()
+    }
+
+    /// All requests that wait for a response, oldest first, e.g. to debug stalls on a busy bus
+    #[must_use]
+    pub fn in_flight(&self) -> Vec<Transaction> {
+        lock(&self.state).session.in_flight().cloned().collect()
+    }
+
+    /// Queue the `frame` and write it to the bus without waiting for a response
+    ///
+    /// # Errors
+    ///
+    /// Returns `ClientError::Io` if writing fails or `ClientError::Closed` if the connection
+    /// was closed
+    pub async fn send(&self, frame: &Frame) -> Result<(), ClientError> {
+        self.write_queued(frame.clone(), Priority::Interactive, Tracking::Untracked)
+            .await?;
+        Ok(())
+    }
+
+    /// Request the field `field_id` from the device at `destination` and wait for
+    /// the `Ret` frame
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of `BusClient::transact`
+    pub async fn request(&self, destination: u8, field_id: u32) -> Result<Frame, ClientError> {
+        self.transact(Frame::new_get(destination, self.address, field_id))
+            .await
+    }
+
+    /// Write the `field_value` to the device at `destination` and wait for the `Ack` frame.
+    /// The `Set` frame is built with `FieldValue::to_set_frame`
+    ///
+    /// # Errors
+    ///
+    /// Returns `ClientError::Request` if the value cannot be written, e.g. a read-only field,
+    /// or an error of `BusClient::transact`
+    pub async fn set(
+        &self,
+        destination: u8,
+        field_value: &FieldValue,
+    ) -> Result<Frame, ClientError> {
+        self.transact(field_value.to_set_frame(destination, self.address)?)
+            .await
+    }
+
+    /// Write the `field_value` to the device at `destination` like `BusClient::set` and confirm it
+    /// with `confirmation`. Returns the `Ack` frame, the `Ret` frame of the read-back or `None` if
+    /// no `Nack` arrived in time
+    ///
+    /// # Errors
+    ///
+    /// Returns `ClientError::Mismatch` with the `Ret` frame if the read-back differs from the
+    /// written value or an error of `BusClient::set`
+    pub async fn set_with(
+        &self,
+        destination: u8,
+        field_value: &FieldValue,
+        confirmation: Confirmation,
+    ) -> Result<Option<Frame>, ClientError> {
+        let request = field_value.to_set_frame(destination, self.address)?;
+        let response = self
+            .transact_confirmed(request.clone(), Priority::User, confirmation)
+            .await?;
+        if confirmation != Confirmation::ReadBack {
+            return Ok(response);
+        }
+        let ret = self.request(destination, request.field_id()).await?;
+        // compare without the flag, a `Set` enables the value with its own flag
+        if ret.payload().get(1..) != request.payload().get(1..) {
+            return Err(ClientError::Mismatch(ret));
+        }
+        Ok(Some(ret))
+    }
+
+    /// Fire the command of the write-only `Datatype::Trigger` field `field_id` at `destination`,
+    /// e.g. a reset, and wait for the `Ack` frame
+    ///
+    /// # Errors
+    ///
+    /// Returns `ClientError::Request` if the field is not a trigger or an error of
+    /// `BusClient::set`
+    pub async fn trigger(&self, destination: u8, field_id: u32) -> Result<Frame, ClientError> {
+        self.set(destination, &FieldValue::trigger(field_id)?).await
+    }
+
+    /// Send the `request` (e.g. a `Get` or `Set`) and wait for its response.
+    /// `Set` requests are sent with `Priority::User`, all others with `Priority::Interactive`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of `BusClient::transact_with_priority`
+    pub async fn transact(&self, request: Frame) -> Result<Frame, ClientError> {
+        let priority = if request.is_packet_type(PacketType::Set) {
+            Priority::User
+        } else {
+            Priority::Interactive
+        };
+        self.transact_with_priority(request, priority).await
+    }
+
+    /// Send the `request` with `priority` and wait for its response.
+    /// The request is repeated as long as the `RetryPolicy` allows it
+    ///
+    /// # Errors
+    ///
+    /// Returns `ClientError::Timeout` if no attempt was answered, `ClientError::Rejected` with the
+    /// `Nack` or `Error` frame of the device, `ClientError::Io` if writing fails or
+    /// `ClientError::Closed` if the connection was closed
+    pub async fn transact_with_priority(
+        &self,
+        request: Frame,
+        priority: Priority,
+    ) -> Result<Frame, ClientError> {
+        // only requests without an `Ack` confirmation succeed without a response
+        self.transact_confirmed(request, priority, Confirmation::Ack)
+            .await?
+            .ok_or(ClientError::Closed)
+    }
+
+    /// Send the `request` with `priority` and wait for its response like
+    /// `BusClient::transact_with_priority`. Unless `confirmation` is `Confirmation::Ack` a
+    /// request without a response within the timeout succeeds with `None`
+    async fn transact_confirmed(
+        &self,
+        request: Frame,
+        priority: Priority,
+        confirmation: Confirmation,
+    ) -> Result<Option<Frame>, ClientError> {
+        let mut tracking = Tracking::Request;
+        let mut response = None;
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            if let Some(started) = self
+                .write_queued(request.clone(), priority, tracking)
+                .await?
+            {
+                response = Some(started);
+            }
+            let Some(pending) = response.as_mut() else {
+                return Err(ClientError::Closed);
+            };
+            let result = tokio::time::timeout(self.timing.response_timeout(), &mut *pending)
+                .await
+                .unwrap_or(Err(SessionError::Timeout));
+            let (class, error) = match result {
+                Ok(frame) => return Ok(Some(frame)),
+                Err(SessionError::Timeout) if confirmation != Confirmation::Ack => {
+                    lock(&self.state).session.expire(pending.id(), now());
+                    return Ok(None);
+                }
+                Err(SessionError::Timeout) => {
+                    // a repeated request keeps waiting for the same response
+                    tracking = Tracking::Retry(pending.id());
+                    (ErrorClass::Timeout, ClientError::Timeout { attempts })
+                }
+                Err(SessionError::Nack(frame) | SessionError::Rejected(frame)) => {
+                    tracking = Tracking::Request;
+                    (ErrorClass::Rejected, ClientError::Rejected(frame))
+                }
+                Err(SessionError::Closed) => return Err(ClientError::Closed),
+            };
+            let Some(delay) = self.retry.delay(class, attempts, random()) else {
+                lock(&self.state).session.expire(pending.id(), now());
+                return Err(error);
+            };
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Queue the `frame` and wait until it was written, either by this caller or by another
+    /// caller holding the writer. Returns the `ResponseFuture` of a new request
+    async fn write_queued(
+        &self,
+        frame: Frame,
+        priority: Priority,
+        tracking: Tracking,
+    ) -> Result<Option<ResponseFuture>, ClientError> {
+        let consumer = self.next_consumer.fetch_add(1, Ordering::Relaxed);
+        let _queued = {
+            let mut state = lock(&self.state);
+            if state.closed {
+                return Err(ClientError::Closed);
+            }
+            state.queue.push(consumer, priority, frame);
+            state.waiting.insert(consumer, tracking);
+            Queued {
+                state: &self.state,
+                consumer,
+            }
+        };
+        let mut writer = self.writer.lock().await;
+        loop {
+            let next = {
+                let mut state = lock(&self.state);
+                if let Some(written) = state.written.remove(&consumer) {
+                    return written.map_err(|kind| ClientError::Io(kind.into()));
+                }
+                state.queue.pop()
+            };
+            // the own frame is either queued or already written
+            let Some((owner, frame)) = next else {
+                return Err(ClientError::Closed);
+            };
+            self.wait_idle().await;
+            // register the frame before writing it, a fast transport may answer at once
+            let (tracking, started, registered) = {
+                let mut state = lock(&self.state);
+                let now = now();
+                state.echo.sent(frame.clone(), now);
+                let tracking = state.waiting.remove(&owner);
+                let started = match tracking {
+                    Some(Tracking::Request) => Some(state.session.request(
+                        frame.clone(),
+                        self.timing.response_timeout(),
+                        now,
+                    )),
+                    Some(Tracking::Retry(id)) => {
+                        // the request may have been answered in the meantime
+                        state
+                            .session
+                            .retried(id, self.timing.response_timeout(), now);
+                        None
+                    }
+                    Some(Tracking::Untracked) | None => None,
+                };
+                let request = match (tracking, &started) {
+                    (Some(Tracking::Retry(id)), _) => Some(id),
+                    (_, started) => started.as_ref().map(ResponseFuture::id),
+                };
+                let registered = Registered {
+                    state: &self.state,
+                    frame: frame.clone(),
+                    request,
+                    kept: false,
+                };
+                (tracking, started, registered)
+            };
+            let result = self.write(&mut writer, &frame).await;
+            match result {
+                Ok(()) => registered.keep(),
+                Err(_) => drop(registered),
+            }
+            let mut state = lock(&self.state);
+            let written = result.map(|()| started).map_err(|error| error.kind());
+            if owner == consumer {
+                return written.map_err(|kind| ClientError::Io(kind.into()));
+            }
+            // nobody waits for a frame whose caller stopped waiting
+            if tracking.is_some() {
+                state.written.insert(owner, written);
+            }
+        }
+    }
+
+    /// Wait until the `IdleGate` allows sending
+    async fn wait_idle(&self) {
+        loop {
+            let wait = {
+                let mut state = lock(&self.state);
+                let now = now();
+                if state.idle.try_send(now) {
+                    return;
+                }
+                state.idle.wait_time(now)
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Serialize and write the `frame` to the bus
+    async fn write(&self, writer: &mut WriteHalf<T>, frame: &Frame) -> std::io::Result<()> {
+        let mut buffer = self.pool.take();
+        frame.serialize_into(&mut buffer);
+        let result = writer.write_all(&buffer).await;
+        self.pool.give(buffer);
+        result?;
+        writer.flush().await
+    }
+}
+
+impl<T> Drop for BusClient<T> {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+/// Read and parse frames until the `reader` is closed or fails
+async fn read_loop<R: AsyncRead>(
+    reader: R,
+    state: Arc<Mutex<State>>,
+    frames: broadcast::Sender<Frame>,
+) {
+    tokio::pin!(reader);
+    let mut stream = FrameStream::new();
+    let mut chunk = [0; 256];
+    while let Ok(length @ 1..) = reader.read(&mut chunk).await {
+        lock(&state).idle.received(now());
+        stream.push_bytes(&chunk[..length]);
+        for frame in &mut stream {
+            dispatch(frame, &state, &frames);
+        }
+    }
+    let mut state = lock(&state);
+    state.closed = true;
+    // dropping the session wakes up all waiting requests
+    state.session = Session::new();
+}
+
+/// Drop the echo of a sent `frame`, answer its pending request and forward it to the subscribers
+fn dispatch(frame: Frame, state: &Mutex<State>, frames: &broadcast::Sender<Frame>) {
+    {
+        let mut state = lock(state);
+        let now = now();
+        if state.echo.is_echo(&frame, now) {
+            return;
+        }
+        state.session.receive(&frame, now);
+    }
+    // there may be no subscriber
+    let _ = frames.send(frame);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+
+    use crate::{
+        bus::{Backoff, BusTiming, ErrorClass, RetryPolicy},
+        BsbError, BufferPool, FieldValue, Frame, PacketType,
+    };
+
+    use super::{lock, BusClient, ClientError, Confirmation};
+
+    #[tokio::test]
+    async fn test_bus_client_request() {
+        let (port, mut device) = tokio::io::duplex(256);
+        let pool = Arc::new(BufferPool::new(4));
+        let client = BusClient::new(port, 66).with_pool(Arc::clone(&pool));
+        let mut frames = client.subscribe();
+        let response = async {
+            let mut request = [0; 11];
+            device.read_exact(&mut request).await.unwrap();
+            assert_eq!(request, Frame::new_get(0, 66, 0x053d_19f0).serialize()[..]);
+            let in_flight = client.in_flight();
+            assert_eq!(in_flight.len(), 1);
+            assert_eq!(in_flight[0].request().field_id(), 0x053d_19f0);
+            // the echo of the request, noise and an unrelated frame before the response
+            let unrelated =
+                Frame::new(66, 0, PacketType::Ret.into(), 0x0d3d_0519, vec![0, 0, 1]).unwrap();
+            let ret =
+                Frame::new(66, 0, PacketType::Ret.into(), 0x053d_19f0, vec![0, 0, 15]).unwrap();
+            let raw = [
+                request.to_vec(),
+                vec![0xdc, 0x00],
+                unrelated.serialize(),
+                ret.serialize(),
+            ]
+            .concat();
+            device.write_all(&raw).await.unwrap();
+            ret
+        };
+        let (testcase, want) = tokio::join!(client.request(0, 0x053d_19f0), response);
+        assert_eq!(testcase.unwrap(), want);
+        assert_eq!(frames.recv().await.unwrap().field_id(), 0x0d3d_0519);
+        assert_eq!(frames.recv().await.unwrap(), want);
+        assert!(client.in_flight().is_empty());
+        // the serialization buffer went back to the shared pool
+        assert_eq!(pool.len(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_bus_client_timeout() {
+        let (port, mut device) = tokio::io::duplex(256);
+        let client = BusClient::new(port, 66)
+            .with_timing(BusTiming::bsb().with_response_timeout(Duration::from_millis(100)))
+            .with_retry_policy(RetryPolicy::new(
+                Backoff::Fixed(Duration::from_millis(10)),
+                2,
+            ));
+        let testcase = client.request(0, 0x053d_19f0).await;
+        assert!(matches!(
+            testcase,
+            Err(ClientError::Timeout { attempts: 2 })
+        ));
+        assert!(client.in_flight().is_empty());
+        // the request was sent twice
+        let mut sent = [0; 22];
+        device.read_exact(&mut sent).await.unwrap();
+        drop(device);
+        assert!(matches!(
+            client.request(0, 0x053d_19f0).await,
+            Err(ClientError::Io(_) | ClientError::Closed)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_bus_client_write_error() {
+        // the bus stays open for reading but writing fails
+        let (reader, _device) = tokio::io::duplex(256);
+        let (writer, closed) = tokio::io::duplex(256);
+        drop(closed);
+        let client = BusClient::new(tokio::io::join(reader, writer), 66);
+        let testcase = client.request(0, 0x053d_19f0).await;
+        assert!(matches!(testcase, Err(ClientError::Io(_))));
+        // neither a response nor an echo is expected for the unwritten request
+        assert!(client.in_flight().is_empty());
+        assert_eq!(lock(&client.state).echo.pending(), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_bus_client_rejected() {
+        let (port, mut device) = tokio::io::duplex(256);
+        let client = BusClient::new(port, 66)
+            .with_retry_policy(RetryPolicy::default().with_error_class(ErrorClass::Rejected, 1));
+        let set = Frame::new_set(0, 66, 0x2d3d_058e, vec![1, 5, 0x60]).unwrap();
+        let nack = Frame::new(66, 0, PacketType::Nack.into(), 0x2d3d_058e, vec![]).unwrap();
+        let response = async {
+            let mut request = vec![0; set.serialize().len()];
+            device.read_exact(&mut request).await.unwrap();
+            assert_eq!(request, set.serialize());
+            device.write_all(&nack.serialize()).await.unwrap();
+        };
+        let (testcase, ()) = tokio::join!(client.transact(set.clone()), response);
+        assert!(matches!(testcase, Err(ClientError::Rejected(frame)) if frame == nack));
+    }
+
+    #[tokio::test]
+    async fn test_bus_client_set() {
+        let (port, mut device) = tokio::io::duplex(256);
+        let client = BusClient::new(port, 66);
+        let field_value = FieldValue::from_value_str("21.5", 0x2d3d_058e).unwrap();
+        let ack = Frame::new(66, 0, PacketType::Ack.into(), 0x2d3d_058e, vec![]).unwrap();
+        let response = async {
+            let set = Frame::new_set(0, 66, 0x2d3d_058e, vec![1, 5, 0x60]).unwrap();
+            let mut request = vec![0; set.serialize().len()];
+            device.read_exact(&mut request).await.unwrap();
+            assert_eq!(request, set.serialize());
+            device.write_all(&ack.serialize()).await.unwrap();
+        };
+        let (testcase, ()) = tokio::join!(client.set(0, &field_value), response);
+        assert_eq!(testcase.unwrap(), ack);
+        // a read-only field is not sent
+        let read_only = FieldValue::from_value_str("50", 0x313d_052f).unwrap();
+        assert!(matches!(
+            client.set(0, &read_only).await,
+            Err(ClientError::Request(error)) if error == BsbError::ReadOnlyField
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_bus_client_trigger() {
+        let (port, mut device) = tokio::io::duplex(256);
+        let client = BusClient::new(port, 66);
+        // reset_alarm_relay
+        let ack = Frame::new(66, 0, PacketType::Ack.into(), 0x053d_0b5a, vec![]).unwrap();
+        let response = async {
+            let set = Frame::new_set(0, 66, 0x053d_0b5a, vec![1, 1]).unwrap();
+            let mut request = vec![0; set.serialize().len()];
+            device.read_exact(&mut request).await.unwrap();
+            assert_eq!(request, set.serialize());
+            device.write_all(&ack.serialize()).await.unwrap();
+        };
+        let (testcase, ()) = tokio::join!(client.trigger(0, 0x053d_0b5a), response);
+        assert_eq!(testcase.unwrap(), ack);
+        // a regular setting is not a trigger
+        assert!(matches!(
+            client.trigger(0, 0x0d3d_092a).await,
+            Err(ClientError::Request(error)) if error == BsbError::InvalidDatatype
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_bus_client_set_with() {
+        let (port, mut device) = tokio::io::duplex(256);
+        let client = BusClient::new(port, 66);
+        let field_value = FieldValue::from_value_str("21.5", 0x2d3d_058e).unwrap();
+        let set = Frame::new_set(0, 66, 0x2d3d_058e, vec![1, 5, 0x60]).unwrap();
+        let get = Frame::new_get(0, 66, 0x2d3d_058e);
+        // the device does not acknowledge the `Set`
+        let testcase = client.set_with(0, &field_value, Confirmation::NoNack);
+        let (testcase, ()) = tokio::join!(testcase, async {
+            let mut request = vec![0; set.serialize().len()];
+            device.read_exact(&mut request).await.unwrap();
+            assert_eq!(request, set.serialize());
+        });
+        assert!(testcase.unwrap().is_none());
+        assert!(client.in_flight().is_empty());
+        // a `Nack` still fails
+        let nack = Frame::new(66, 0, PacketType::Nack.into(), 0x2d3d_058e, vec![]).unwrap();
+        let testcase = client.set_with(0, &field_value, Confirmation::NoNack);
+        let (testcase, ()) = tokio::join!(testcase, async {
+            for _ in 0..client.retry_policy().max_attempts(ErrorClass::Rejected) {
+                let mut request = vec![0; set.serialize().len()];
+                device.read_exact(&mut request).await.unwrap();
+                device.write_all(&nack.serialize()).await.unwrap();
+            }
+        });
+        assert!(matches!(testcase, Err(ClientError::Rejected(frame)) if frame == nack));
+        // (<read-back payload>, <matches>)
+        let testcases = vec![(vec![0, 5, 0x60], true), (vec![0, 5, 0x40], false)];
+        for (payload, matches) in testcases {
+            let ret = Frame::new(66, 0, PacketType::Ret.into(), 0x2d3d_058e, payload).unwrap();
+            let testcase = client.set_with(0, &field_value, Confirmation::ReadBack);
+            let (testcase, ()) = tokio::join!(testcase, async {
+                let mut request = vec![0; set.serialize().len()];
+                device.read_exact(&mut request).await.unwrap();
+                let mut request = vec![0; get.serialize().len()];
+                device.read_exact(&mut request).await.unwrap();
+                assert_eq!(request, get.serialize());
+                device.write_all(&ret.serialize()).await.unwrap();
+            });
+            if matches {
+                assert_eq!(testcase.unwrap(), Some(ret));
+            } else {
+                assert!(matches!(testcase, Err(ClientError::Mismatch(frame)) if frame == ret));
+            }
+        }
+    }
+}
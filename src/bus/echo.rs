@@ -0,0 +1,99 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use crate::Frame;
+
+/// Detects the echo of own frames that many adapters send back on RX,
+/// so a client does not mistake its own requests for traffic of other devices
+#[derive(Debug, Clone)]
+pub struct EchoFilter {
+    window: Duration,
+    pending: VecDeque<(Frame, Instant)>,
+}
+
+impl EchoFilter {
+    /// Create a new `EchoFilter` that expects the echo within `window` after sending
+    #[must_use]
+    pub fn new(window: Duration) -> EchoFilter {
+        EchoFilter {
+            window,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Record a `frame` that was sent at `now`
+    pub fn sent(&mut self, frame: Frame, now: Instant) {
+        self.expire(now);
+        self.pending.push_back((frame, now));
+    }
+
+    /// Check if the `frame` received at `now` is the echo of a sent frame.
+    /// Every sent frame is suppressed only once, a second identical frame is real traffic
+    pub fn is_echo(&mut self, frame: &Frame, now: Instant) -> bool {
+        self.expire(now);
+        match self.pending.iter().position(|(sent, _)| sent == frame) {
+            Some(position) => {
+                self.pending.remove(position);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Forget the last recorded `frame` because writing it failed.
+    /// Returns `false` if no echo of the `frame` is expected
+    pub fn unsent(&mut self, frame: &Frame) -> bool {
+        match self.pending.iter().rposition(|(sent, _)| sent == frame) {
+            Some(position) => {
+                self.pending.remove(position);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Number of sent frames whose echo is still expected
+    #[must_use]
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Forget sent frames whose echo did not arrive within the window
+    fn expire(&mut self, now: Instant) {
+        self.pending
+            .retain(|(_, sent_at)| now.saturating_duration_since(*sent_at) <= self.window);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::Frame;
+
+    use super::EchoFilter;
+
+    #[test]
+    fn test_echo_filter() {
+        let now = Instant::now();
+        let mut filter = EchoFilter::new(Duration::from_millis(100));
+        let request = Frame::new_get(0, 66, 0x053d_19f0);
+        filter.sent(request.clone(), now);
+        assert!(!filter.is_echo(&Frame::new_get(0, 66, 0x053d_0521), now));
+        assert!(filter.is_echo(&request, now + Duration::from_millis(30)));
+        // the echo is suppressed only once
+        assert!(!filter.is_echo(&request, now + Duration::from_millis(40)));
+        // a late frame is not an echo anymore
+        filter.sent(request.clone(), now);
+        assert_eq!(filter.pending(), 1);
+        assert!(!filter.is_echo(&request, now + Duration::from_millis(150)));
+        assert_eq!(filter.pending(), 0);
+        // a frame that could not be written has no echo
+        filter.sent(request.clone(), now);
+        assert!(filter.unsent(&request));
+        assert!(!filter.unsent(&request));
+        assert!(!filter.is_echo(&request, now));
+    }
+}
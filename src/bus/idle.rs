@@ -0,0 +1,105 @@
+use std::time::{Duration, Instant};
+
+use super::BusTiming;
+
+/// Allows sending only after the bus was silent for a number of character times,
+/// so a client does not talk into frames of other masters on a busy bus
+#[derive(Debug, Clone)]
+pub struct IdleGate {
+    idle_time: Duration,
+    last_received_at: Option<Instant>,
+    deferred_since: Option<Instant>,
+    deferred: u64,
+    deferred_time: Duration,
+}
+
+impl IdleGate {
+    /// Create a new `IdleGate` that waits `idle_characters` character times of the `timing`
+    #[must_use]
+    pub fn new(timing: &BusTiming, idle_characters: u32) -> IdleGate {
+        IdleGate {
+            idle_time: timing.character_time().saturating_mul(idle_characters),
+            last_received_at: None,
+            deferred_since: None,
+            deferred: 0,
+            deferred_time: Duration::ZERO,
+        }
+    }
+
+    /// Record that bytes were received at `now`
+    pub fn received(&mut self, now: Instant) {
+        self.last_received_at = Some(now);
+    }
+
+    /// Time the bus has to be silent before sending
+    #[must_use]
+    pub fn idle_time(&self) -> Duration {
+        self.idle_time
+    }
+
+    /// Remaining time until sending is allowed at `now`
+    #[must_use]
+    pub fn wait_time(&self, now: Instant) -> Duration {
+        self.last_received_at.map_or(Duration::ZERO, |last| {
+            self.idle_time
+                .saturating_sub(now.saturating_duration_since(last))
+        })
+    }
+
+    /// Check if sending is allowed at `now`. A refused send is counted as deferred
+    pub fn try_send(&mut self, now: Instant) -> bool {
+        if self.wait_time(now).is_zero() {
+            if let Some(since) = self.deferred_since.take() {
+                self.deferred_time += now.saturating_duration_since(since);
+            }
+            true
+        } else {
+            if self.deferred_since.is_none() {
+                self.deferred_since = Some(now);
+                self.deferred += 1;
+            }
+            false
+        }
+    }
+
+    /// Number of sends that had to wait for the bus to become idle
+    #[must_use]
+    pub fn deferred(&self) -> u64 {
+        self.deferred
+    }
+
+    /// Total time sends had to wait for the bus to become idle
+    #[must_use]
+    pub fn deferred_time(&self) -> Duration {
+        self.deferred_time
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::bus::BusTiming;
+
+    use super::IdleGate;
+
+    #[test]
+    fn test_idle_gate() {
+        let now = Instant::now();
+        let timing = BusTiming::bsb().with_baud(1100);
+        // 10 character times of 10 ms each
+        let mut gate = IdleGate::new(&timing, 10);
+        assert_eq!(gate.idle_time(), Duration::from_millis(100));
+        assert!(gate.try_send(now));
+        gate.received(now);
+        assert!(!gate.try_send(now + Duration::from_millis(40)));
+        assert!(!gate.try_send(now + Duration::from_millis(60)));
+        assert_eq!(
+            gate.wait_time(now + Duration::from_millis(60)),
+            Duration::from_millis(40)
+        );
+        assert!(gate.try_send(now + Duration::from_millis(100)));
+        assert_eq!(gate.deferred(), 1);
+        assert_eq!(gate.deferred_time(), Duration::from_millis(60));
+    }
+}
@@ -0,0 +1,85 @@
+use std::io::{Read, Write};
+
+/// Invert all bits of `bytes` in place, e.g. for adapters that deliver the signal inverted
+pub fn invert(bytes: &mut [u8]) {
+    for byte in bytes {
+        *byte = !*byte;
+    }
+}
+
+/// Wrapper around a transport that inverts all bytes on read and write,
+/// so logically inverting adapters work with `Frame::parse` and `Frame::serialize`
+#[derive(Debug)]
+pub struct Inverted<T> {
+    inner: T,
+}
+
+impl<T> Inverted<T> {
+    /// Wrap the transport `inner`
+    pub fn new(inner: T) -> Inverted<T> {
+        Inverted { inner }
+    }
+
+    /// Access the wrapped transport
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Access the wrapped transport mutable
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Return the wrapped transport
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: Read> Read for Inverted<T> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let length = self.inner.read(buf)?;
+        invert(&mut buf[..length]);
+        Ok(length)
+    }
+}
+
+impl<T: Write> Write for Inverted<T> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut inverted = buf.to_vec();
+        invert(&mut inverted);
+        self.inner.write(&inverted)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read as _, Write as _};
+
+    use crate::{Frame, ParseResult};
+
+    use super::Inverted;
+
+    #[test]
+    fn test_inverted() {
+        let frame = Frame::new_get(0, 66, 0x053d_19f0);
+        let mut transport = Inverted::new(Vec::new());
+        transport.write_all(&frame.serialize()).unwrap();
+        let raw = transport.into_inner();
+        assert_eq!(raw[0], !0xdc);
+        let mut transport = Inverted::new(raw.as_slice());
+        let mut data = Vec::new();
+        transport.read_to_end(&mut data).unwrap();
+        let ParseResult::Ok {
+            frame: testcase, ..
+        } = Frame::parse(&data)
+        else {
+            panic!("inverted frame cannot be parsed");
+        };
+        assert_eq!(testcase, frame);
+    }
+}
@@ -0,0 +1,129 @@
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+
+use crate::{session::Session, Frame};
+
+use super::{Priority, TransactionId, TransmitQueue};
+
+/// Shares one half-duplex bus between several consumers, e.g. a poller and an MQTT set handler.
+/// The `BusMux` does not own the transport: the application sends the frames returned by
+/// `poll_transmit` and passes all received frames to `receive`. Only one request is on the bus
+/// at a time and the `TransmitQueue` decides which consumer is next. Responses are matched by a
+/// `Session` like in the `BusClient`
+#[derive(Debug)]
+pub struct BusMux {
+    queue: TransmitQueue,
+    session: Session,
+    /// Consumer and request of each transaction in the `session`
+    consumers: BTreeMap<TransactionId, (u32, Frame)>,
+    response_timeout: Duration,
+}
+
+impl BusMux {
+    /// Create a new `BusMux` that waits `response_timeout` for each response,
+    /// e.g. `BusTiming::response_timeout`
+    #[must_use]
+    pub fn new(response_timeout: Duration) -> BusMux {
+        BusMux {
+            queue: TransmitQueue::new(),
+            session: Session::new(),
+            consumers: BTreeMap::new(),
+            response_timeout,
+        }
+    }
+
+    /// Queue a `request` of `consumer` with `priority`
+    pub fn submit(&mut self, consumer: u32, priority: Priority, request: Frame) {
+        self.queue.push(consumer, priority, request);
+    }
+
+    /// Take the next request to send at `now`.
+    /// Returns `None` while a request waits for its response or nothing is queued
+    pub fn poll_transmit(&mut self, now: Instant) -> Option<Frame> {
+        if self.is_busy() {
+            return None;
+        }
+        let (consumer, request) = self.queue.pop()?;
+        // the consumer gets the response from `receive` instead of the `ResponseFuture`
+        let id = self
+            .session
+            .request(request.clone(), self.response_timeout, now)
+            .id();
+        self.consumers.insert(id, (consumer, request.clone()));
+        Some(request)
+    }
+
+    /// Match a received `frame` with the request on the bus.
+    /// Returns the consumer of the request and the response
+    pub fn receive(&mut self, frame: &Frame, now: Instant) -> Option<(u32, Frame)> {
+        let id = self.session.receive(frame, now)?;
+        let (consumer, _) = self.consumers.remove(&id)?;
+        Some((consumer, frame.clone()))
+    }
+
+    /// Give up on the request on the bus if its response is overdue at `now`.
+    /// Returns the consumers and their requests that timed out
+    pub fn time_out(&mut self, now: Instant) -> Vec<(u32, Frame)> {
+        self.session
+            .time_out(now)
+            .into_iter()
+            .filter_map(|id| self.consumers.remove(&id))
+            .collect()
+    }
+
+    /// Remove all queued requests of `consumer`, e.g. when it disconnects.
+    /// Returns the number of removed requests
+    pub fn cancel(&mut self, consumer: u32) -> usize {
+        self.queue.cancel(consumer)
+    }
+
+    /// Number of requests waiting to be sent
+    #[must_use]
+    pub fn queued(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Check if a request waits for its response
+    #[must_use]
+    pub fn is_busy(&self) -> bool {
+        !self.session.is_idle()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::{Frame, PacketType};
+
+    use super::{BusMux, Priority};
+
+    #[test]
+    fn test_bus_mux() {
+        let now = Instant::now();
+        let mut mux = BusMux::new(Duration::from_secs(1));
+        mux.submit(1, Priority::Background, Frame::new_get(0, 66, 0x053d_19f0));
+        mux.submit(2, Priority::Interactive, Frame::new_get(0, 66, 0x053d_0521));
+        assert_eq!(mux.queued(), 2);
+        let request = mux.poll_transmit(now).unwrap();
+        assert_eq!(request.field_id(), 0x053d_0521);
+        // half-duplex: nothing else is sent until the response arrives
+        assert!(mux.is_busy());
+        assert_eq!(mux.poll_transmit(now), None);
+        // traffic of other devices is not matched
+        let broadcast =
+            Frame::new(0x7f, 0, PacketType::Info as u8, 0x053d_0521, vec![0, 0, 15]).unwrap();
+        assert_eq!(mux.receive(&broadcast, now), None);
+        let response =
+            Frame::new(66, 0, PacketType::Ret as u8, 0x053d_0521, vec![0, 0, 15]).unwrap();
+        assert_eq!(mux.receive(&response, now), Some((2, response)));
+        let request = mux.poll_transmit(now).unwrap();
+        assert_eq!(request.field_id(), 0x053d_19f0);
+        assert!(mux.time_out(now + Duration::from_millis(500)).is_empty());
+        assert_eq!(mux.time_out(now + Duration::from_secs(2)), [(1, request)]);
+        assert!(!mux.is_busy());
+        assert_eq!(mux.poll_transmit(now), None);
+    }
+}
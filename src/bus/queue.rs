@@ -0,0 +1,139 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::Frame;
+
+/// Priority of a `Frame` in the `TransmitQueue`, ordered from lowest to highest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    /// Background polling
+    Background,
+    /// Gets a user waits for
+    Interactive,
+    /// User initiated Sets, e.g. a setpoint change
+    User,
+}
+
+/// Queue of the frames of one consumer
+#[derive(Debug)]
+struct ConsumerQueue {
+    consumer: u32,
+    frames: VecDeque<Frame>,
+}
+
+/// Queue of frames waiting to be sent on the bus.
+/// Frames with a higher `Priority` are always sent first. Within a priority the consumers
+/// take turns, so one consumer with a long polling list cannot delay the others
+#[derive(Debug, Default)]
+pub struct TransmitQueue {
+    levels: BTreeMap<Priority, VecDeque<ConsumerQueue>>,
+    len: usize,
+}
+
+impl TransmitQueue {
+    /// Create a new empty `TransmitQueue`
+    #[must_use]
+    pub fn new() -> TransmitQueue {
+        TransmitQueue::default()
+    }
+
+    /// Queue a `frame` of `consumer` with `priority`
+    pub fn push(&mut self, consumer: u32, priority: Priority, frame: Frame) {
+        let level = self.levels.entry(priority).or_default();
+        match level.iter_mut().find(|queue| queue.consumer == consumer) {
+            Some(queue) => queue.frames.push_back(frame),
+            None => level.push_back(ConsumerQueue {
+                consumer,
+                frames: VecDeque::from([frame]),
+            }),
+        }
+        self.len += 1;
+    }
+
+    /// Take the next `Frame` to send together with its consumer
+    pub fn pop(&mut self) -> Option<(u32, Frame)> {
+        let mut entry = self.levels.last_entry()?;
+        let level = entry.get_mut();
+        let mut queue = level.pop_front()?;
+        let frame = queue.frames.pop_front()?;
+        let consumer = queue.consumer;
+        if !queue.frames.is_empty() {
+            // the consumer has to wait for the other consumers of this priority
+            level.push_back(queue);
+        }
+        if level.is_empty() {
+            entry.remove();
+        }
+        self.len -= 1;
+        Some((consumer, frame))
+    }
+
+    /// Remove all queued frames of `consumer`. Returns the number of removed frames
+    pub fn cancel(&mut self, consumer: u32) -> usize {
+        let mut removed = 0;
+        for level in self.levels.values_mut() {
+            level.retain(|queue| {
+                if queue.consumer == consumer {
+                    removed += queue.frames.len();
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+        self.levels.retain(|_, level| !level.is_empty());
+        self.len -= removed;
+        removed
+    }
+
+    /// Number of queued frames
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check if no frame is queued
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Frame;
+
+    use super::{Priority, TransmitQueue};
+
+    #[test]
+    fn test_transmit_queue_order() {
+        let mut queue = TransmitQueue::new();
+        // consumer 1 polls a long list, consumer 2 polls a single field
+        for field_id in 0..3 {
+            queue.push(1, Priority::Background, Frame::new_get(0, 0, field_id));
+        }
+        queue.push(2, Priority::Background, Frame::new_get(0, 0, 10));
+        queue.push(3, Priority::Interactive, Frame::new_get(0, 0, 20));
+        let set = Frame::new_set(0, 0, 30, vec![0, 1]).unwrap();
+        queue.push(3, Priority::User, set);
+        assert_eq!(queue.len(), 6);
+        let testcase = std::iter::from_fn(|| queue.pop())
+            .map(|(consumer, frame)| (consumer, frame.field_id()))
+            .collect::<Vec<_>>();
+        let want = vec![(3, 30), (3, 20), (1, 0), (2, 10), (1, 1), (1, 2)];
+        assert_eq!(testcase, want);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_transmit_queue_cancel() {
+        let mut queue = TransmitQueue::new();
+        queue.push(1, Priority::Background, Frame::new_get(0, 0, 0));
+        queue.push(1, Priority::Interactive, Frame::new_get(0, 0, 1));
+        queue.push(2, Priority::Background, Frame::new_get(0, 0, 2));
+        assert_eq!(queue.cancel(1), 2);
+        assert_eq!(queue.cancel(1), 0);
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.pop().map(|(consumer, _)| consumer), Some(2));
+        assert_eq!(queue.pop(), None);
+    }
+}
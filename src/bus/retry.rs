@@ -0,0 +1,154 @@
+use std::{collections::BTreeMap, time::Duration};
+
+/// Reason why a request has to be retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ErrorClass {
+    /// No response was received in time
+    Timeout,
+    /// Another device sent at the same time
+    Collision,
+    /// The response was received with a broken checksum
+    Checksum,
+    /// The device answered with a `Nack` or `Error` packet
+    Rejected,
+}
+
+/// How long to wait between two attempts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Backoff {
+    /// Always wait the same time
+    Fixed(Duration),
+    /// Double the delay with every attempt up to `max`. The delay is shortened by up to
+    /// `jitter` (0.0 to 1.0) of its length so clients do not retry in lockstep
+    Exponential {
+        initial: Duration,
+        max: Duration,
+        jitter: f64,
+    },
+}
+
+/// When and how often a request is retried
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    backoff: Backoff,
+    max_attempts: u32,
+    class_max_attempts: BTreeMap<ErrorClass, u32>,
+}
+
+impl Default for RetryPolicy {
+    /// 3 attempts with 500 ms in between
+    fn default() -> Self {
+        RetryPolicy::new(Backoff::Fixed(Duration::from_millis(500)), 3)
+    }
+}
+
+impl RetryPolicy {
+    /// Create a new `RetryPolicy` that sends a request at most `max_attempts` times
+    #[must_use]
+    pub fn new(backoff: Backoff, max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            backoff,
+            max_attempts,
+            class_max_attempts: BTreeMap::new(),
+        }
+    }
+
+    /// Return the `RetryPolicy` with a different `max_attempts` for errors of `class`,
+    /// e.g. `1` to never retry rejected requests
+    #[must_use]
+    pub fn with_error_class(mut self, class: ErrorClass, max_attempts: u32) -> RetryPolicy {
+        self.class_max_attempts.insert(class, max_attempts);
+        self
+    }
+
+    /// Access `RetryPolicy.backoff`
+    #[must_use]
+    pub fn backoff(&self) -> Backoff {
+        self.backoff
+    }
+
+    /// Maximum number of attempts for errors of `class`
+    #[must_use]
+    pub fn max_attempts(&self, class: ErrorClass) -> u32 {
+        self.class_max_attempts
+            .get(&class)
+            .copied()
+            .unwrap_or(self.max_attempts)
+    }
+
+    /// Delay before the next attempt of a request that failed with `class` after `attempts`
+    /// attempts, e.g. `Transaction::attempts`. `random` is a sample between 0.0 and 1.0 used for
+    /// the jitter. Returns `None` if the request must not be retried anymore
+    #[must_use]
+    pub fn delay(&self, class: ErrorClass, attempts: u32, random: f64) -> Option<Duration> {
+        if attempts >= self.max_attempts(class) {
+            return None;
+        }
+        match self.backoff {
+            Backoff::Fixed(delay) => Some(delay),
+            Backoff::Exponential {
+                initial,
+                max,
+                jitter,
+            } => {
+                let exponent = attempts.saturating_sub(1).min(31);
+                let delay = initial.saturating_mul(1 << exponent).min(max);
+                let jitter = (jitter * random).clamp(0.0, 1.0);
+                Some(delay.mul_f64(1.0 - jitter))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Backoff, ErrorClass, RetryPolicy};
+
+    #[test]
+    fn test_retry_policy_delay() {
+        let exponential = RetryPolicy::new(
+            Backoff::Exponential {
+                initial: Duration::from_millis(100),
+                max: Duration::from_millis(500),
+                jitter: 0.5,
+            },
+            5,
+        )
+        .with_error_class(ErrorClass::Rejected, 1);
+        // (<policy>, <error class>, <attempts>, <random>, <delay in ms>)
+        let testcases = vec![
+            (
+                RetryPolicy::default(),
+                ErrorClass::Timeout,
+                1,
+                0.0,
+                Some(500),
+            ),
+            (RetryPolicy::default(), ErrorClass::Timeout, 3, 0.0, None),
+            (exponential.clone(), ErrorClass::Timeout, 1, 0.0, Some(100)),
+            (
+                exponential.clone(),
+                ErrorClass::Collision,
+                3,
+                0.0,
+                Some(400),
+            ),
+            (
+                exponential.clone(),
+                ErrorClass::Collision,
+                3,
+                1.0,
+                Some(200),
+            ),
+            (exponential.clone(), ErrorClass::Checksum, 4, 0.0, Some(500)),
+            (exponential.clone(), ErrorClass::Checksum, 5, 0.0, None),
+            (exponential, ErrorClass::Rejected, 1, 0.0, None),
+        ];
+        for (policy, class, attempts, random, want) in testcases {
+            let testcase = policy.delay(class, attempts, random);
+            assert_eq!(testcase, want.map(Duration::from_millis));
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use std::{fmt::Display, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+/// Parity bit of a serial character
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Parity {
+    None,
+    Odd,
+    Even,
+}
+
+/// Framing of a serial character, e.g. `8O1` on BSB
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Framing {
+    data_bits: u8,
+    parity: Parity,
+    stop_bits: u8,
+}
+
+impl Framing {
+    /// Create a new `Framing`
+    #[must_use]
+    pub fn new(data_bits: u8, parity: Parity, stop_bits: u8) -> Framing {
+        Framing {
+            data_bits,
+            parity,
+            stop_bits,
+        }
+    }
+
+    /// Access `Framing.data_bits`
+    #[must_use]
+    pub fn data_bits(&self) -> u8 {
+        self.data_bits
+    }
+
+    /// Access `Framing.parity`
+    #[must_use]
+    pub fn parity(&self) -> Parity {
+        self.parity
+    }
+
+    /// Access `Framing.stop_bits`
+    #[must_use]
+    pub fn stop_bits(&self) -> u8 {
+        self.stop_bits
+    }
+
+    /// Number of bits on the line for one character including start, parity and stop bits
+    #[must_use]
+    pub fn bits_per_character(&self) -> u32 {
+        let parity = u32::from(self.parity != Parity::None);
+        1 + u32::from(self.data_bits) + parity + u32::from(self.stop_bits)
+    }
+}
+
+impl Display for Framing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let parity = match self.parity {
+            Parity::None => 'N',
+            Parity::Odd => 'O',
+            Parity::Even => 'E',
+        };
+        write!(f, "{}{parity}{}", self.data_bits, self.stop_bits)
+    }
+}
+
+/// Timing of the bus used by transports and clients
+#[derive(Debug, Clone, PartialEq)]
+pub struct BusTiming {
+    baud: u32,
+    framing: Framing,
+    inter_frame_gap: Duration,
+    response_timeout: Duration,
+    collision_delay: (Duration, Duration),
+}
+
+impl Default for BusTiming {
+    fn default() -> Self {
+        BusTiming::bsb()
+    }
+}
+
+impl BusTiming {
+    /// Timing of a BSB bus: 4800 baud, `8O1`
+    #[must_use]
+    pub fn bsb() -> BusTiming {
+        BusTiming {
+            baud: 4800,
+            framing: Framing::new(8, Parity::Odd, 1),
+            inter_frame_gap: Duration::from_millis(20),
+            response_timeout: Duration::from_secs(1),
+            collision_delay: (Duration::from_millis(50), Duration::from_millis(200)),
+        }
+    }
+
+    /// Timing of a LPB bus which connects several controllers and answers slower
+    #[must_use]
+    pub fn lpb() -> BusTiming {
+        BusTiming {
+            response_timeout: Duration::from_secs(3),
+            collision_delay: (Duration::from_millis(100), Duration::from_millis(400)),
+            ..BusTiming::bsb()
+        }
+    }
+
+    /// Return the `BusTiming` with a different `baud` rate
+    #[must_use]
+    pub fn with_baud(mut self, baud: u32) -> BusTiming {
+        self.baud = baud;
+        self
+    }
+
+    /// Return the `BusTiming` with a different `framing`
+    #[must_use]
+    pub fn with_framing(mut self, framing: Framing) -> BusTiming {
+        self.framing = framing;
+        self
+    }
+
+    /// Return the `BusTiming` with a different minimum gap between two frames
+    #[must_use]
+    pub fn with_inter_frame_gap(mut self, inter_frame_gap: Duration) -> BusTiming {
+        self.inter_frame_gap = inter_frame_gap;
+        self
+    }
+
+    /// Return the `BusTiming` with a different time to wait for a response
+    #[must_use]
+    pub fn with_response_timeout(mut self, response_timeout: Duration) -> BusTiming {
+        self.response_timeout = response_timeout;
+        self
+    }
+
+    /// Return the `BusTiming` with a different window for the delay after a collision
+    #[must_use]
+    pub fn with_collision_delay(mut self, min: Duration, max: Duration) -> BusTiming {
+        self.collision_delay = (min, max.max(min));
+        self
+    }
+
+    /// Access `BusTiming.baud`
+    #[must_use]
+    pub fn baud(&self) -> u32 {
+        self.baud
+    }
+
+    /// Access `BusTiming.framing`
+    #[must_use]
+    pub fn framing(&self) -> Framing {
+        self.framing
+    }
+
+    /// Access `BusTiming.inter_frame_gap`
+    #[must_use]
+    pub fn inter_frame_gap(&self) -> Duration {
+        self.inter_frame_gap
+    }
+
+    /// Access `BusTiming.response_timeout`
+    #[must_use]
+    pub fn response_timeout(&self) -> Duration {
+        self.response_timeout
+    }
+
+    /// Access `BusTiming.collision_delay` as minimum and maximum delay
+    #[must_use]
+    pub fn collision_delay(&self) -> (Duration, Duration) {
+        self.collision_delay
+    }
+
+    /// Time to transmit one character on the line
+    ///
+    /// # Panics
+    ///
+    /// Panics if the baud rate is 0
+    #[must_use]
+    pub fn character_time(&self) -> Duration {
+        assert!(self.baud > 0, "baud rate must be greater than 0");
+        Duration::from_secs(u64::from(self.framing.bits_per_character())) / self.baud
+    }
+
+    /// Time to transmit a frame of `length` bytes on the line
+    ///
+    /// # Panics
+    ///
+    /// Panics if the baud rate is 0
+    #[must_use]
+    pub fn frame_time(&self, length: usize) -> Duration {
+        self.character_time()
+            .saturating_mul(u32::try_from(length).unwrap_or(u32::MAX))
+    }
+
+    /// Delay after a collision for a `random` sample between 0.0 and 1.0
+    #[must_use]
+    pub fn collision_delay_for(&self, random: f64) -> Duration {
+        let (min, max) = self.collision_delay;
+        min + max.saturating_sub(min).mul_f64(random.clamp(0.0, 1.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{BusTiming, Framing, Parity};
+
+    #[test]
+    fn test_bus_timing() {
+        let timing = BusTiming::bsb();
+        assert_eq!(timing.framing().to_string(), "8O1");
+        assert_eq!(timing.framing().bits_per_character(), 11);
+        // 11 bits at 4800 baud
+        assert_eq!(timing.character_time(), Duration::from_nanos(2_291_666));
+        assert_eq!(timing.frame_time(11), Duration::from_nanos(25_208_326));
+        assert_eq!(timing.collision_delay_for(0.5), Duration::from_millis(125));
+        assert!(BusTiming::lpb().response_timeout() > timing.response_timeout());
+        let custom = timing
+            .with_baud(9600)
+            .with_framing(Framing::new(8, Parity::None, 2))
+            .with_collision_delay(Duration::from_millis(10), Duration::ZERO);
+        assert_eq!(custom.character_time(), Duration::from_nanos(1_145_833));
+        assert_eq!(custom.collision_delay_for(1.0), Duration::from_millis(10));
+    }
+}
@@ -0,0 +1,286 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+use crate::Frame;
+
+/// Correlation id of a request on the bus
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TransactionId(u64);
+
+impl Display for TransactionId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// State of a `Transaction`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TransactionState {
+    /// The request was sent once and no response was received yet
+    Sent,
+    /// The request was sent again and no response was received yet
+    Retried,
+    /// A matching response was received
+    Answered,
+    /// No response was received in time
+    TimedOut,
+}
+
+/// A request together with its state and timestamps
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transaction {
+    id: TransactionId,
+    request: Frame,
+    state: TransactionState,
+    attempts: u32,
+    started_at: Instant,
+    last_sent_at: Instant,
+    finished_at: Option<Instant>,
+    response: Option<Frame>,
+}
+
+impl Transaction {
+    /// Access `Transaction.id`
+    #[must_use]
+    pub fn id(&self) -> TransactionId {
+        self.id
+    }
+
+    /// Access `Transaction.request`
+    #[must_use]
+    pub fn request(&self) -> &Frame {
+        &self.request
+    }
+
+    /// Access `Transaction.state`
+    #[must_use]
+    pub fn state(&self) -> TransactionState {
+        self.state
+    }
+
+    /// Access `Transaction.attempts`, the number of times the request was sent
+    #[must_use]
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// Access `Transaction.started_at`, the time the request was sent first
+    #[must_use]
+    pub fn started_at(&self) -> Instant {
+        self.started_at
+    }
+
+    /// Access `Transaction.last_sent_at`, the time the request was sent last
+    #[must_use]
+    pub fn last_sent_at(&self) -> Instant {
+        self.last_sent_at
+    }
+
+    /// Access `Transaction.finished_at`, the time it was answered or timed out
+    #[must_use]
+    pub fn finished_at(&self) -> Option<Instant> {
+        self.finished_at
+    }
+
+    /// Access `Transaction.response` if it was answered
+    #[must_use]
+    pub fn response(&self) -> Option<&Frame> {
+        self.response.as_ref()
+    }
+
+    /// Check if the `Transaction` still waits for a response
+    #[must_use]
+    pub fn is_in_flight(&self) -> bool {
+        matches!(
+            self.state,
+            TransactionState::Sent | TransactionState::Retried
+        )
+    }
+}
+
+/// Keeps track of all outstanding requests, e.g. to introspect stalls on a busy bus.
+/// All methods take the current time so the tracker can be driven by any clock
+#[derive(Debug, Default)]
+pub struct TransactionTracker {
+    next_id: u64,
+    transactions: BTreeMap<TransactionId, Transaction>,
+}
+
+impl TransactionTracker {
+    /// Create a new empty `TransactionTracker`
+    #[must_use]
+    pub fn new() -> TransactionTracker {
+        TransactionTracker::default()
+    }
+
+    /// Track a `request` that was sent at `now`
+    pub fn begin(&mut self, request: Frame, now: Instant) -> TransactionId {
+        let id = TransactionId(self.next_id);
+        self.next_id += 1;
+        self.transactions.insert(
+            id,
+            Transaction {
+                id,
+                request,
+                state: TransactionState::Sent,
+                attempts: 1,
+                started_at: now,
+                last_sent_at: now,
+                finished_at: None,
+                response: None,
+            },
+        );
+        id
+    }
+
+    /// Record that the request of `id` was sent again at `now`.
+    /// Returns `false` if the transaction is unknown or not in flight anymore
+    pub fn retried(&mut self, id: TransactionId, now: Instant) -> bool {
+        match self.transactions.get_mut(&id) {
+            Some(transaction) if transaction.is_in_flight() => {
+                transaction.state = TransactionState::Retried;
+                transaction.attempts += 1;
+                transaction.last_sent_at = now;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Match a received `frame` with the oldest in-flight request it answers.
+    /// Returns the id of the answered transaction
+    pub fn answer(&mut self, frame: &Frame, now: Instant) -> Option<TransactionId> {
+        let transaction = self.transactions.values_mut().find(|transaction| {
+            transaction.is_in_flight() && frame.is_response_to(&transaction.request)
+        })?;
+        transaction.state = TransactionState::Answered;
+        transaction.finished_at = Some(now);
+        transaction.response = Some(frame.clone());
+        Some(transaction.id)
+    }
+
+    /// Mark all in-flight transactions that were sent last more than `timeout` before `now`
+    /// as timed out. Returns their ids
+    pub fn time_out(&mut self, timeout: Duration, now: Instant) -> Vec<TransactionId> {
+        let mut timed_out = Vec::new();
+        for transaction in self.transactions.values_mut() {
+            if transaction.is_in_flight()
+                && now.saturating_duration_since(transaction.last_sent_at) > timeout
+            {
+                transaction.state = TransactionState::TimedOut;
+                transaction.finished_at = Some(now);
+                timed_out.push(transaction.id);
+            }
+        }
+        timed_out
+    }
+
+    /// Mark the in-flight transaction `id` as timed out at `now`, e.g. if it has its own timeout.
+    /// Returns `false` if the transaction is unknown or not in flight anymore
+    pub fn expire(&mut self, id: TransactionId, now: Instant) -> bool {
+        match self.transactions.get_mut(&id) {
+            Some(transaction) if transaction.is_in_flight() => {
+                transaction.state = TransactionState::TimedOut;
+                transaction.finished_at = Some(now);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Access a `Transaction` by its `id`
+    #[must_use]
+    pub fn get(&self, id: TransactionId) -> Option<&Transaction> {
+        self.transactions.get(&id)
+    }
+
+    /// Iterate over all transactions that wait for a response, oldest first
+    pub fn in_flight(&self) -> impl Iterator<Item = &Transaction> {
+        self.transactions
+            .values()
+            .filter(|transaction| transaction.is_in_flight())
+    }
+
+    /// Remove and return all answered or timed out transactions
+    pub fn take_finished(&mut self) -> Vec<Transaction> {
+        let finished = self
+            .transactions
+            .iter()
+            .filter(|(_, transaction)| !transaction.is_in_flight())
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        finished
+            .iter()
+            .filter_map(|id| self.transactions.remove(id))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::{Frame, PacketType};
+
+    use super::{TransactionState, TransactionTracker};
+
+    #[test]
+    fn test_transaction_tracker_answer() {
+        let now = Instant::now();
+        let mut tracker = TransactionTracker::new();
+        let pressure = tracker.begin(Frame::new_get(0, 66, 0x053d_19f0), now);
+        let standby = tracker.begin(Frame::new_get(0, 66, 0x053d_0236), now);
+        assert_eq!(tracker.in_flight().count(), 2);
+        let ret = Frame::new(66, 0, PacketType::Ret as u8, 0x053d_19f0, vec![0, 0, 15]).unwrap();
+        assert_eq!(tracker.answer(&ret, now), Some(pressure));
+        // the same response cannot answer twice
+        assert_eq!(tracker.answer(&ret, now), None);
+        let transaction = tracker.get(pressure).unwrap();
+        assert_eq!(transaction.state(), TransactionState::Answered);
+        assert_eq!(transaction.response(), Some(&ret));
+        assert_eq!(
+            tracker
+                .in_flight()
+                .map(super::Transaction::id)
+                .collect::<Vec<_>>(),
+            [standby]
+        );
+        let finished = tracker.take_finished();
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].id(), pressure);
+        assert!(tracker.get(pressure).is_none());
+    }
+
+    #[test]
+    fn test_transaction_tracker_retry_and_time_out() {
+        let start = Instant::now();
+        let mut tracker = TransactionTracker::new();
+        let id = tracker.begin(Frame::new_get(0, 66, 0x053d_19f0), start);
+        let retry = start + Duration::from_millis(500);
+        assert!(tracker.retried(id, retry));
+        assert_eq!(tracker.get(id).unwrap().attempts(), 2);
+        assert_eq!(tracker.get(id).unwrap().state(), TransactionState::Retried);
+        // the timeout starts again with the retry
+        let timeout = Duration::from_millis(600);
+        assert!(tracker
+            .time_out(timeout, start + Duration::from_secs(1))
+            .is_empty());
+        assert_eq!(
+            tracker.time_out(timeout, start + Duration::from_millis(1200)),
+            [id]
+        );
+        assert_eq!(tracker.get(id).unwrap().state(), TransactionState::TimedOut);
+        assert!(!tracker.retried(id, start + Duration::from_millis(1300)));
+        assert!(!tracker.expire(id, start + Duration::from_millis(1300)));
+        let other = tracker.begin(Frame::new_get(0, 66, 0x053d_0236), start);
+        assert!(tracker.expire(other, start));
+        assert_eq!(
+            tracker.get(other).unwrap().state(),
+            TransactionState::TimedOut
+        );
+        assert_eq!(id.to_string(), "#0");
+    }
+}
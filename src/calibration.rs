@@ -0,0 +1,177 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{BsbError, Value};
+
+/// `Calibration` corrects decoded float values of a field with `value * scale + offset`,
+/// e.g. for a sensor that is known to measure 0.5 °C too high (offset -0.5).
+/// Calibrations of a `CalibrationRegistry` are applied when decoding a `FieldValue` from a `Frame`
+/// and inverted when encoding
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Calibration {
+    offset: f32,
+    scale: f32,
+}
+
+impl Calibration {
+    /// Create a new `Calibration` with an `offset` and a `scale`
+    ///
+    /// # Errors
+    ///
+    /// Returns `BsbError::InvalidCalibration` if the `scale` is zero or one of the values is not finite
+    pub fn new(offset: f32, scale: f32) -> Result<Calibration, BsbError> {
+        if scale == 0.0 || !scale.is_finite() || !offset.is_finite() {
+            return Err(BsbError::InvalidCalibration);
+        }
+        Ok(Calibration { offset, scale })
+    }
+
+    /// Create a new `Calibration` that only adds an `offset`
+    ///
+    /// # Errors
+    ///
+    /// Returns `BsbError::InvalidCalibration` if the `offset` is not finite
+    pub fn with_offset(offset: f32) -> Result<Calibration, BsbError> {
+        Calibration::new(offset, 1.0)
+    }
+
+    /// Access `Calibration.offset`
+    #[must_use]
+    pub fn offset(&self) -> f32 {
+        self.offset
+    }
+
+    /// Access `Calibration.scale`
+    #[must_use]
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Apply the `Calibration` to a float `Value`, other values are not changed
+    pub fn apply(&self, value: &mut Value) {
+        if let Value::Float { value, .. } = value {
+            *value = *value * self.scale + self.offset;
+        }
+    }
+
+    /// Revert the `Calibration` of a float `Value`, other values are not changed
+    pub fn invert(&self, value: &mut Value) {
+        if let Value::Float { value, .. } = value {
+            *value = (*value - self.offset) / self.scale;
+        }
+    }
+}
+
+/// `CalibrationRegistry` holds the `Calibration`s of the fields of one installation, e.g. the
+/// sensor offsets of one heater. Decoding applies it via `DecodeOptions::with_calibrations`,
+/// encoding inverts it via `FieldValue::encode_for_with` and `FieldValue::to_set_frame_with`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CalibrationRegistry {
+    calibrations: BTreeMap<u32, Calibration>,
+}
+
+impl CalibrationRegistry {
+    /// Create a new empty `CalibrationRegistry`
+    #[must_use]
+    pub fn new() -> CalibrationRegistry {
+        CalibrationRegistry::default()
+    }
+
+    /// Return the `CalibrationRegistry` with the `calibration` for the field with `field_id`
+    #[must_use]
+    pub fn with_calibration(
+        mut self,
+        field_id: u32,
+        calibration: Calibration,
+    ) -> CalibrationRegistry {
+        self.register(field_id, calibration);
+        self
+    }
+
+    /// Register the `calibration` for the field with `field_id`, replacing an existing one
+    pub fn register(&mut self, field_id: u32, calibration: Calibration) {
+        self.calibrations.insert(field_id, calibration);
+    }
+
+    /// Remove the `Calibration` for the field with `field_id`
+    pub fn unregister(&mut self, field_id: u32) {
+        self.calibrations.remove(&field_id);
+    }
+
+    /// Get the registered `Calibration` for the field with `field_id`
+    #[must_use]
+    pub fn for_field(&self, field_id: u32) -> Option<Calibration> {
+        self.calibrations.get(&field_id).copied()
+    }
+
+    /// Number of registered calibrations
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.calibrations.len()
+    }
+
+    /// Check if no calibration is registered
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.calibrations.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BsbError, Value};
+
+    use super::{Calibration, CalibrationRegistry};
+
+    fn create_test_value() -> Value {
+        Value::Float {
+            flag: 0,
+            value: 20.0,
+            factor: 64,
+        }
+    }
+
+    #[test]
+    fn test_calibration_apply_invert() {
+        let calibration = Calibration::new(-0.5, 2.0).unwrap();
+        let mut testcase = create_test_value();
+        calibration.apply(&mut testcase);
+        assert_eq!(testcase.as_f64(), Some(39.5));
+        calibration.invert(&mut testcase);
+        assert_eq!(testcase, create_test_value());
+    }
+
+    #[test]
+    fn test_calibration_ignores_non_floats() {
+        let calibration = Calibration::with_offset(1.0).unwrap();
+        let mut testcase = Value::Number { flag: 0, value: 5 };
+        calibration.apply(&mut testcase);
+        assert_eq!(testcase, Value::Number { flag: 0, value: 5 });
+    }
+
+    #[test]
+    fn test_calibration_invalid() {
+        assert_eq!(
+            Calibration::new(0.0, 0.0),
+            Err(BsbError::InvalidCalibration)
+        );
+        assert_eq!(
+            Calibration::with_offset(f32::NAN),
+            Err(BsbError::InvalidCalibration)
+        );
+    }
+
+    #[test]
+    fn test_calibration_registry() {
+        let calibration = Calibration::with_offset(-0.5).unwrap();
+        let mut registry = CalibrationRegistry::new().with_calibration(0x113d_051a, calibration);
+        assert_eq!(registry.for_field(0x113d_051a), Some(calibration));
+        assert_eq!(registry.for_field(0x053d_19f0), None);
+        // another registry, e.g. of a second heater, is independent
+        assert!(CalibrationRegistry::new().is_empty());
+        registry.unregister(0x113d_051a);
+        assert_eq!(registry.for_field(0x113d_051a), None);
+        assert!(registry.is_empty());
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Datatype, Field, Frame, PacketType, Value};
+
+/// What a device answered when a field was requested
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum Capability {
+    /// The device answered with a value of `payload_length` bytes. `datatype` is set if the
+    /// payload could be decoded with the datatype of the field database
+    Supported {
+        payload_length: usize,
+        datatype: Option<Datatype>,
+    },
+    /// The device answered with an error
+    Unsupported,
+}
+
+/// Results of field scans for each device address, e.g. to skip probing unsupported fields
+/// on every startup. It can be persisted with any serde format
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapabilityCache {
+    devices: BTreeMap<u8, BTreeMap<u32, Capability>>,
+}
+
+impl CapabilityCache {
+    /// Create a new empty `CapabilityCache`
+    #[must_use]
+    pub fn new() -> CapabilityCache {
+        CapabilityCache::default()
+    }
+
+    /// Record the answer of a device. `Ret` frames mark the field as supported and `Error`
+    /// frames as unsupported by the source of the `frame`.
+    /// Returns `false` if the frame is no answer
+    pub fn record(&mut self, frame: &Frame) -> bool {
+        let capability = if frame.is_packet_type(PacketType::Ret) {
+            let datatype = Field::by_id(frame.field_id())
+                .map(Field::datatype)
+                .filter(|datatype| Value::decode(frame.payload(), *datatype).is_ok());
+            Capability::Supported {
+                payload_length: frame.payload().len(),
+                datatype,
+            }
+        } else if frame.is_packet_type(PacketType::Error) {
+            Capability::Unsupported
+        } else {
+            return false;
+        };
+        self.insert(frame.source_address(), frame.field_id(), capability);
+        true
+    }
+
+    /// Store the `capability` of `field_id` for the device at `address`
+    pub fn insert(&mut self, address: u8, field_id: u32, capability: Capability) {
+        self.devices
+            .entry(address)
+            .or_default()
+            .insert(field_id, capability);
+    }
+
+    /// Access the `Capability` of `field_id` for the device at `address` if it is known
+    #[must_use]
+    pub fn get(&self, address: u8, field_id: u32) -> Option<Capability> {
+        self.devices.get(&address)?.get(&field_id).copied()
+    }
+
+    /// Check if `field_id` was not probed yet on the device at `address`
+    #[must_use]
+    pub fn needs_probe(&self, address: u8, field_id: u32) -> bool {
+        self.get(address, field_id).is_none()
+    }
+
+    /// Check if the device at `address` is known to not support `field_id`
+    #[must_use]
+    pub fn is_unsupported(&self, address: u8, field_id: u32) -> bool {
+        self.get(address, field_id) == Some(Capability::Unsupported)
+    }
+
+    /// Iterate over the ids of all fields the device at `address` is known to support
+    pub fn supported_fields(&self, address: u8) -> impl Iterator<Item = u32> + '_ {
+        self.devices
+            .get(&address)
+            .into_iter()
+            .flatten()
+            .filter(|(_, capability)| matches!(capability, Capability::Supported { .. }))
+            .map(|(field_id, _)| *field_id)
+    }
+
+    /// Forget everything about the device at `address`, e.g. after it was replaced
+    pub fn forget(&mut self, address: u8) {
+        self.devices.remove(&address);
+    }
+
+    /// Number of devices with recorded capabilities
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.devices.len()
+    }
+
+    /// Check if no capability was recorded
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.devices.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Datatype, Frame, PacketType};
+
+    use super::{Capability, CapabilityCache};
+
+    #[test]
+    fn test_capability_cache_record() {
+        let mut cache = CapabilityCache::new();
+        // requests are no answers
+        assert!(!cache.record(&Frame::new_get(0, 66, 0x053d_19f0)));
+        assert!(cache.is_empty());
+        // (<frame>, <capability>)
+        let testcases = vec![
+            (
+                Frame::new(66, 0, PacketType::Ret.into(), 0x053d_19f0, vec![0, 0, 15]).unwrap(),
+                Capability::Supported {
+                    payload_length: 3,
+                    datatype: Some(Datatype::Float(10)),
+                },
+            ),
+            (
+                Frame::new(66, 0, PacketType::Ret.into(), 0x1234_5678, vec![0, 1]).unwrap(),
+                Capability::Supported {
+                    payload_length: 2,
+                    datatype: None,
+                },
+            ),
+            (
+                Frame::new(66, 0, PacketType::Error.into(), 0x053d_0236, vec![]).unwrap(),
+                Capability::Unsupported,
+            ),
+        ];
+        for (frame, want) in testcases {
+            assert!(cache.record(&frame));
+            assert_eq!(cache.get(0, frame.field_id()), Some(want));
+        }
+        assert_eq!(cache.len(), 1);
+        assert!(cache.is_unsupported(0, 0x053d_0236));
+        assert!(cache.needs_probe(0, 0x0d3d_0519));
+        assert!(cache.needs_probe(10, 0x053d_19f0));
+        assert_eq!(
+            cache.supported_fields(0).collect::<Vec<_>>(),
+            [0x053d_19f0, 0x1234_5678]
+        );
+        cache.forget(0);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_capability_cache_serde() {
+        let mut cache = CapabilityCache::new();
+        cache.record(&Frame::new(66, 0, 7, 0x053d_19f0, vec![0, 0, 15]).unwrap());
+        cache.insert(10, 0x053d_0236, Capability::Unsupported);
+        let json = serde_json::to_string(&cache).unwrap();
+        let testcase: CapabilityCache = serde_json::from_str(&json).unwrap();
+        assert_eq!(testcase, cache);
+    }
+}
@@ -0,0 +1,115 @@
+use bytes::{Buf as _, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::{Frame, ParseResult, Quirks, SOF};
+
+/// `Decoder` and `Encoder` for BSB frames, e.g. for `Framed<SerialStream, BsbCodec>`.
+/// Like `FrameStream` it skips garbage and broken frames instead of failing the stream
+#[derive(Debug, Default, Clone)]
+pub struct BsbCodec {
+    quirks: Quirks,
+    errors: usize,
+}
+
+impl BsbCodec {
+    /// Create a new `BsbCodec`
+    #[must_use]
+    pub fn new() -> BsbCodec {
+        BsbCodec::default()
+    }
+
+    /// Return the `BsbCodec` parsing and serializing frames with the device `quirks`
+    #[must_use]
+    pub fn with_quirks(mut self, quirks: Quirks) -> BsbCodec {
+        self.quirks = quirks;
+        self
+    }
+
+    /// Number of broken frames that were skipped
+    #[must_use]
+    pub fn errors(&self) -> usize {
+        self.errors
+    }
+}
+
+impl Decoder for BsbCodec {
+    type Item = Frame;
+    type Error = std::io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        loop {
+            // garbage before the next SOF can never become part of a frame
+            let start = src
+                .iter()
+                .position(|&byte| byte == SOF)
+                .unwrap_or(src.len());
+            src.advance(start);
+            if src.is_empty() {
+                return Ok(None);
+            }
+            match Frame::parse_with(src, self.quirks) {
+                ParseResult::Ok { rest, frame } => {
+                    let end = src.len() - rest.len();
+                    src.advance(end);
+                    return Ok(Some(frame));
+                }
+                ParseResult::Incomplete => return Ok(None),
+                ParseResult::Failure { rest, .. } => {
+                    // continue at the next SOF after the broken frame
+                    self.errors += 1;
+                    let end = src.len() - rest.len();
+                    src.advance(end);
+                }
+            }
+        }
+    }
+}
+
+impl Encoder<Frame> for BsbCodec {
+    type Error = std::io::Error;
+
+    fn encode(&mut self, item: Frame, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        self.encode(&item, dst)
+    }
+}
+
+impl Encoder<&Frame> for BsbCodec {
+    type Error = std::io::Error;
+
+    fn encode(&mut self, item: &Frame, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        dst.extend_from_slice(&item.serialize_with(self.quirks));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+    use tokio_util::codec::{Decoder as _, Encoder as _};
+
+    use crate::Frame;
+
+    use super::BsbCodec;
+
+    #[test]
+    fn test_bsb_codec_roundtrip() {
+        let mut codec = BsbCodec::new();
+        let first = Frame::new(66, 0, 7, 0x053d_19f0, vec![0, 0, 15]).unwrap();
+        let second = Frame::new_get(0, 66, 0x053d_19f0);
+        let mut buffer = BytesMut::from(&[0x00, 0xdc, 0x00][..]);
+        codec.encode(first.clone(), &mut buffer).unwrap();
+        codec.encode(&second, &mut buffer).unwrap();
+        // the garbage and the broken frame are skipped
+        assert_eq!(codec.decode(&mut buffer).unwrap(), Some(first));
+        assert_eq!(codec.errors(), 1);
+        assert_eq!(codec.decode(&mut buffer).unwrap(), Some(second.clone()));
+        assert_eq!(codec.decode(&mut buffer).unwrap(), None);
+        assert!(buffer.is_empty());
+        // an incomplete frame stays in the buffer
+        let raw = second.serialize();
+        buffer.extend_from_slice(&raw[..5]);
+        assert_eq!(codec.decode(&mut buffer).unwrap(), None);
+        buffer.extend_from_slice(&raw[5..]);
+        assert_eq!(codec.decode(&mut buffer).unwrap(), Some(second));
+    }
+}
@@ -0,0 +1,297 @@
+//! TOML configuration for applications using this crate, e.g.
+//!
+//! ```toml
+//! [bus]
+//! port = "/dev/ttyUSB0"
+//! address = 66
+//!
+//! [[poll]]
+//! field = "temperature/outside"
+//! interval = 60
+//!
+//! [mqtt]
+//! host = "localhost"
+//! ```
+
+use std::{path::PathBuf, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    bus::{Framing, Parity},
+    Field,
+};
+
+/// Errors that occur while loading a `Config`
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("cannot read configuration: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid configuration syntax: {0}")]
+    Toml(#[from] toml::de::Error),
+    #[error("invalid value for `{key}`: {message}")]
+    Invalid { key: String, message: String },
+}
+
+/// The complete configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub bus: BusConfig,
+    #[serde(default)]
+    pub poll: Vec<PollConfig>,
+    pub mqtt: Option<MqttConfig>,
+    pub storage: Option<StorageConfig>,
+}
+
+/// Settings of the connection to the bus
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BusConfig {
+    /// Serial port of the bus adapter, e.g. "/dev/ttyUSB0"
+    pub port: String,
+    /// Baud rate of the serial port
+    #[serde(default = "default_baud")]
+    pub baud: u32,
+    /// Parity of the serial port, BSB uses odd parity
+    #[serde(default = "default_parity")]
+    pub parity: Parity,
+    /// Number of stop bits of the serial port, 1 or 2
+    #[serde(default = "default_stop_bits")]
+    pub stop_bits: u8,
+    /// Own address on the bus that is used as source address
+    #[serde(default = "default_address")]
+    pub address: u8,
+    /// Address of the controller that is used as destination address
+    #[serde(default)]
+    pub destination: u8,
+    /// The adapter delivers the signal logically inverted, see `bus::Inverted`
+    #[serde(default)]
+    pub inverted: bool,
+}
+
+/// A field that is polled periodically
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PollConfig {
+    /// The field as name, path or program number
+    pub field: String,
+    /// Poll interval in seconds
+    pub interval: u64,
+}
+
+/// Settings of the MQTT broker connection
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MqttConfig {
+    pub host: String,
+    #[serde(default = "default_mqtt_port")]
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Prefix for all topics, the `Field` path is appended
+    #[serde(default = "default_topic_prefix")]
+    pub topic_prefix: String,
+}
+
+/// Settings of the storage of decoded values
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StorageConfig {
+    pub path: PathBuf,
+}
+
+fn default_baud() -> u32 {
+    4800
+}
+
+fn default_parity() -> Parity {
+    Parity::Odd
+}
+
+fn default_stop_bits() -> u8 {
+    1
+}
+
+fn default_address() -> u8 {
+    66
+}
+
+fn default_mqtt_port() -> u16 {
+    1883
+}
+
+fn default_topic_prefix() -> String {
+    "bsb".to_string()
+}
+
+impl Config {
+    /// Load and validate a `Config` from the TOML file at `path`
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ConfigError` if the file cannot be read, parsed or is invalid
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Config, ConfigError> {
+        std::fs::read_to_string(path)?.parse()
+    }
+
+    /// Validate the values of the `Config`
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigError::Invalid` with the key and a description of the first invalid value
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.bus.port.is_empty() {
+            return Err(invalid("bus.port", "must not be empty"));
+        }
+        if self.bus.baud == 0 {
+            return Err(invalid("bus.baud", "must be greater than 0"));
+        }
+        if !(1..=2).contains(&self.bus.stop_bits) {
+            return Err(invalid("bus.stop_bits", "must be 1 or 2"));
+        }
+        if self.bus.address > 0x7f {
+            return Err(invalid("bus.address", "must be between 0 and 127"));
+        }
+        if self.bus.address == self.bus.destination {
+            return Err(invalid("bus.address", "must differ from bus.destination"));
+        }
+        for (i, poll) in self.poll.iter().enumerate() {
+            if poll.resolve().is_none() {
+                return Err(invalid(
+                    &format!("poll[{i}].field"),
+                    &format!("unknown field \"{}\"", poll.field),
+                ));
+            }
+            if poll.interval == 0 {
+                return Err(invalid(
+                    &format!("poll[{i}].interval"),
+                    "must be greater than 0",
+                ));
+            }
+        }
+        if let Some(mqtt) = &self.mqtt {
+            if mqtt.host.is_empty() {
+                return Err(invalid("mqtt.host", "must not be empty"));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Config {
+    type Err = ConfigError;
+
+    /// Parse and validate a `Config` from a TOML string
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let config: Config = toml::from_str(s)?;
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+impl BusConfig {
+    /// `Framing` of the serial port with 8 data bits
+    #[must_use]
+    pub fn framing(&self) -> Framing {
+        Framing::new(8, self.parity, self.stop_bits)
+    }
+}
+
+impl PollConfig {
+    /// Resolve the `field` by name, path or program number
+    #[must_use]
+    pub fn resolve(&self) -> Option<&'static Field> {
+        Field::by_name(&self.field)
+            .or_else(|| Field::by_path(&self.field))
+            .or_else(|| Field::by_full_prognr(self.field.parse().ok()?))
+    }
+}
+
+/// Create a `ConfigError::Invalid`
+fn invalid(key: &str, message: &str) -> ConfigError {
+    ConfigError::Invalid {
+        key: key.to_string(),
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Config, ConfigError};
+
+    #[test]
+    fn test_config_from_str() {
+        let testcase: Config = r#"
+            [bus]
+            port = "/dev/ttyUSB0"
+
+            [[poll]]
+            field = "temperature/outside"
+            interval = 60
+
+            [[poll]]
+            field = "8704"
+            interval = 300
+
+            [mqtt]
+            host = "localhost"
+        "#
+        .parse()
+        .unwrap();
+        assert_eq!(testcase.bus.baud, 4800);
+        assert_eq!(testcase.bus.address, 66);
+        assert!(!testcase.bus.inverted);
+        assert_eq!(testcase.bus.framing().to_string(), "8O1");
+        assert_eq!(testcase.poll.len(), 2);
+        assert_eq!(
+            testcase.poll[0].resolve().unwrap().name(),
+            "outside_temperature"
+        );
+        assert_eq!(testcase.poll[1].resolve().unwrap().name(), "water_pressure");
+        let mqtt = testcase.mqtt.unwrap();
+        assert_eq!(mqtt.port, 1883);
+        assert_eq!(mqtt.topic_prefix, "bsb");
+        assert_eq!(testcase.storage, None);
+    }
+
+    #[test]
+    fn test_config_invalid() {
+        let error = r#"
+            [bus]
+            port = "/dev/ttyUSB0"
+
+            [[poll]]
+            field = "unknown"
+            interval = 60
+        "#
+        .parse::<Config>()
+        .expect_err("not an error");
+        assert_eq!(
+            error.to_string(),
+            "invalid value for `poll[0].field`: unknown field \"unknown\""
+        );
+        let error = "[bus]\nport = \"\""
+            .parse::<Config>()
+            .expect_err("not an error");
+        assert!(matches!(error, ConfigError::Invalid { key, .. } if key == "bus.port"));
+        let error = "[bus]\nport = \"/dev/ttyUSB0\"\nstop_bits = 3"
+            .parse::<Config>()
+            .expect_err("not an error");
+        assert!(matches!(error, ConfigError::Invalid { key, .. } if key == "bus.stop_bits"));
+    }
+
+    #[test]
+    fn test_config_syntax_error() {
+        let error = "[bus]\nprot = \"/dev/ttyUSB0\""
+            .parse::<Config>()
+            .expect_err("not an error");
+        assert!(matches!(error, ConfigError::Toml(_)));
+        // only none, odd and even are valid parities
+        let error = "[bus]\nport = \"/dev/ttyUSB0\"\nparity = \"o\""
+            .parse::<Config>()
+            .expect_err("not an error");
+        assert!(matches!(error, ConfigError::Toml(_)));
+    }
+}
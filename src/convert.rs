@@ -0,0 +1,243 @@
+use std::io::Write;
+
+use chrono::{DateTime, Utc};
+
+use crate::{DecodedEvent, FieldValue, Frame, FrameStream, UnitPreference, Value};
+
+/// Measurement name of the InfluxDB line protocol output
+const MEASUREMENT: &str = "bsb";
+
+/// Parse all `Frame`s from a raw byte capture of the bus.
+/// Broken frames are skipped and an incomplete frame at the end is ignored
+#[must_use]
+pub fn frames_from_capture(raw: &[u8]) -> Vec<Frame> {
+    let mut stream = FrameStream::new();
+    stream.push_bytes(raw);
+    stream.collect()
+}
+
+/// Decode all `Frame`s of a raw byte capture into `DecodedEvent`s
+#[must_use]
+pub fn decode_capture(raw: &[u8]) -> Vec<DecodedEvent> {
+    frames_from_capture(raw)
+        .iter()
+        .map(DecodedEvent::from_frame)
+        .collect()
+}
+
+/// Convert a raw byte capture into JSON Lines (one `DecodedEvent` per line) and write them to `writer`.
+/// Returns the number of written events
+///
+/// # Errors
+///
+/// Returns an error if writing to `writer` fails
+pub fn to_json_lines<W: Write>(raw: &[u8], mut writer: W) -> std::io::Result<usize> {
+    let events = decode_capture(raw);
+    for event in &events {
+        serde_json::to_writer(&mut writer, event)?;
+        writer.write_all(b"\n")?;
+    }
+    Ok(events.len())
+}
+
+/// Convert a raw byte capture into CSV (with header, one `DecodedEvent` per row) and write it to `writer`.
+/// Returns the number of written events
+///
+/// # Errors
+///
+/// Returns an error if writing to `writer` fails
+pub fn to_csv<W: Write>(raw: &[u8], writer: W) -> std::io::Result<usize> {
+    let events = decode_capture(raw);
+    let mut writer = csv::Writer::from_writer(writer);
+    for event in &events {
+        writer.serialize(event)?;
+    }
+    writer.flush()?;
+    Ok(events.len())
+}
+
+/// Escape a tag value of the InfluxDB line protocol
+fn escape_tag(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(',', "\\,")
+        .replace('=', "\\=")
+        .replace(' ', "\\ ")
+}
+
+/// Number of a numeric `FieldValue` converted into the unit of the `preference`, `None` for
+/// values without a numeric representation. Settings are written as their number, not their label
+fn numeric_value(field_value: &FieldValue, preference: UnitPreference) -> Option<String> {
+    let number = field_value.value().as_f64()?;
+    let converted = field_value
+        .unit_in(&preference)
+        .filter(|unit| Some(*unit) != field_value.field().unit())
+        .and_then(|unit| field_value.value_in(unit).ok());
+    Some(match (converted, field_value.value()) {
+        (Some(converted), _) => ((converted * 100.0).round() / 100.0).to_string(),
+        // the shortest representation of the f32, e.g. 0.1 instead of 0.10000000149011612
+        (None, Value::Float { value, .. }) => value.to_string(),
+        (None, _) => number.to_string(),
+    })
+}
+
+/// Render a `FieldValue` as one line of the InfluxDB line protocol (e.g. for Telegraf `execd`).
+/// The field name, path and unit are tags, numeric values are written as float field `value`,
+/// all other values as string. Without `timestamp` the receiver assigns the time
+#[must_use]
+pub fn line_protocol(field_value: &FieldValue, timestamp: Option<DateTime<Utc>>) -> String {
+    line_protocol_in(field_value, timestamp, &UnitPreference::default())
+}
+
+/// Render a `FieldValue` as one line of the InfluxDB line protocol like `line_protocol`
+/// with the value and unit tag converted into the unit of the `preference`
+#[must_use]
+pub fn line_protocol_in(
+    field_value: &FieldValue,
+    timestamp: Option<DateTime<Utc>>,
+    preference: &UnitPreference,
+) -> String {
+    let field = field_value.field();
+    let unit = field_value
+        .unit_in(preference)
+        .map(|unit| format!(",unit={}", escape_tag(&unit.to_string())))
+        .unwrap_or_default();
+    let value = numeric_value(field_value, *preference).unwrap_or_else(|| {
+        let value = field_value.value_str_in(preference);
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    });
+    let timestamp = timestamp
+        .and_then(|timestamp| timestamp.timestamp_nanos_opt())
+        .map(|nanoseconds| format!(" {nanoseconds}"))
+        .unwrap_or_default();
+    format!(
+        "{MEASUREMENT},name={},path={}{unit} value={value}{timestamp}",
+        escape_tag(field.name()),
+        escape_tag(field.path())
+    )
+}
+
+/// Convert a raw byte capture into the InfluxDB line protocol (one line per decoded value of a
+/// known field) and write it to `writer`. Returns the number of written lines
+///
+/// # Errors
+///
+/// Returns an error if writing to `writer` fails
+pub fn to_line_protocol<W: Write>(raw: &[u8], mut writer: W) -> std::io::Result<usize> {
+    let mut count = 0;
+    for frame in frames_from_capture(raw) {
+        // requests and unknown fields have no value
+        let Ok(field_value) = FieldValue::from_frame(&frame) else {
+            continue;
+        };
+        writeln!(writer, "{}", line_protocol(&field_value, None))?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+
+    use crate::{FieldValue, Frame, Unit, UnitPreference};
+
+    use super::{
+        decode_capture, frames_from_capture, line_protocol, line_protocol_in, to_csv,
+        to_json_lines, to_line_protocol,
+    };
+
+    /// a capture with a garbage byte, a Get, a frame with a broken checksum, a Ret and an incomplete frame
+    fn create_capture() -> Vec<u8> {
+        [
+            &[0x00][..],
+            &[220, 194, 0, 11, 6, 61, 5, 25, 240, 36, 62],
+            &[220, 128, 66, 14, 7, 5, 61, 25, 240, 0, 0, 15, 29, 0],
+            &[220, 128, 66, 14, 7, 5, 61, 25, 240, 0, 0, 15, 29, 116],
+            &[220, 128, 66],
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn test_frames_from_capture() {
+        let testcase = frames_from_capture(&create_capture());
+        let want = vec![
+            Frame::new(0, 66, 6, 0x053d_19f0, vec![]).unwrap(),
+            Frame::new(66, 0, 7, 0x053d_19f0, vec![0, 0, 15]).unwrap(),
+        ];
+        assert_eq!(testcase, want);
+    }
+
+    #[test]
+    fn test_decode_capture() {
+        let testcase = decode_capture(&create_capture());
+        assert_eq!(testcase.len(), 2);
+        assert_eq!(testcase[0].value(), None);
+        assert_eq!(testcase[1].value(), Some("1.5"));
+    }
+
+    #[test]
+    fn test_to_json_lines() {
+        let mut output = Vec::new();
+        let count = to_json_lines(&create_capture(), &mut output).unwrap();
+        assert_eq!(count, 2);
+        let testcase = String::from_utf8(output).unwrap();
+        let want = concat!(
+            r#"{"source_address":66,"destination_address":0,"packet_type":"Get","field_id":"0x053d19f0","name":null,"value":null,"payload":""}"#,
+            "\n",
+            r#"{"source_address":0,"destination_address":66,"packet_type":"Ret","field_id":"0x053d19f0","name":"water_pressure","value":"1.5","payload":"00000f"}"#,
+            "\n"
+        );
+        assert_eq!(testcase, want);
+    }
+
+    #[test]
+    fn test_to_csv() {
+        let mut output = Vec::new();
+        let count = to_csv(&create_capture(), &mut output).unwrap();
+        assert_eq!(count, 2);
+        let testcase = String::from_utf8(output).unwrap();
+        let want = "source_address,destination_address,packet_type,field_id,name,value,payload\n\
+                    66,0,Get,0x053d19f0,,,\n\
+                    0,66,Ret,0x053d19f0,water_pressure,1.5,00000f\n";
+        assert_eq!(testcase, want);
+    }
+
+    #[test]
+    fn test_to_line_protocol() {
+        let mut output = Vec::new();
+        let count = to_line_protocol(&create_capture(), &mut output).unwrap();
+        assert_eq!(count, 1);
+        let testcase = String::from_utf8(output).unwrap();
+        let want = "bsb,name=water_pressure,path=system/water_pressure,unit=bar value=1.5\n";
+        assert_eq!(testcase, want);
+    }
+
+    #[test]
+    fn test_line_protocol() {
+        let field_value =
+            FieldValue::from_str("current_time: 2024-11-11T09:36:57", 0x0500_006c).unwrap();
+        let timestamp = DateTime::from_timestamp(1_700_000_000, 0);
+        let testcase = line_protocol(&field_value, timestamp);
+        let want = "bsb,name=current_time,path=system/time value=\"2024-11-11T09:36:57\" 1700000000000000000";
+        assert_eq!(testcase, want);
+    }
+
+    #[test]
+    fn test_line_protocol_in() {
+        let field_value = FieldValue::from_value_str("21.5", 0x2d3d_058e).unwrap();
+        let preference = UnitPreference::default().with_temperature(Unit::Fahrenheit);
+        let testcase = line_protocol_in(&field_value, None, &preference);
+        let want = "bsb,name=heating_circuit_1_set_point_comfort,path=heating_circuit/1/set_point/comfort,unit=°F value=70.7";
+        assert_eq!(testcase, want);
+    }
+
+    #[test]
+    fn test_line_protocol_labeled_setting() {
+        let field_value = FieldValue::from_value_str("Automatic", 0x2d3d_0574).unwrap();
+        let testcase = line_protocol(&field_value, None);
+        let want = "bsb,name=heating_circuit_1_mode,path=heating_circuit/1/mode value=1";
+        assert_eq!(testcase, want);
+    }
+}
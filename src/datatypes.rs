@@ -1,16 +1,181 @@
+use std::{fmt::Display, str::FromStr};
+
 use serde::{Deserialize, Serialize};
 
+use crate::BsbError;
+
 /// The Datatype enum is aligned with the Value enum.
-/// This type stores the information about the type/encoding
-#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
+/// This type stores the information about the type/encoding.
+/// New datatypes are added without a major version, so matches need a wildcard arm
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub enum Datatype {
     /// Settings with states mapped to unsigned ints. The number is the maximum value of the "highest" settings for this field (e.g. 1 for [Off(0),On(1)])
     /// The mapping to strings is not yet defined
     Setting(u8),
     /// Integer value
     Number,
+    /// Signed integer value, e.g. for offsets or differentials
+    SignedNumber,
     /// Float with a division factor, e.g. pressure → 10, slope → 50, temperature → 64
     Float(u8),
     DateTime,
     Schedule,
+    /// Write-only command like a reset, the number is the fixed token that is sent with the `Set`
+    Trigger(u8),
+    /// Null-terminated ASCII text, e.g. device identifications or holiday program names
+    String,
+    /// Entry of the error history, an error code together with the time it occurred
+    ErrorRecord,
+}
+
+impl Datatype {
+    /// A stable numeric code for the kind of this `Datatype` (without its parameter)
+    /// that does not change between versions of this crate
+    #[must_use]
+    pub fn code(&self) -> u8 {
+        match self {
+            Datatype::Setting(_) => 1,
+            Datatype::Number => 2,
+            Datatype::SignedNumber => 3,
+            Datatype::Float(_) => 4,
+            Datatype::DateTime => 5,
+            Datatype::Schedule => 6,
+            Datatype::Trigger(_) => 7,
+            Datatype::String => 8,
+            Datatype::ErrorRecord => 9,
+        }
+    }
+
+    /// A stable name for the kind of this `Datatype` (without its parameter), e.g. "Float"
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Datatype::Setting(_) => "Setting",
+            Datatype::Number => "Number",
+            Datatype::SignedNumber => "SignedNumber",
+            Datatype::Float(_) => "Float",
+            Datatype::DateTime => "DateTime",
+            Datatype::Schedule => "Schedule",
+            Datatype::Trigger(_) => "Trigger",
+            Datatype::String => "String",
+            Datatype::ErrorRecord => "ErrorRecord",
+        }
+    }
+}
+
+impl Display for Datatype {
+    /// Display the `Datatype` in the same notation as in the field database, e.g. "Float(64)"
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Datatype::Setting(max) => write!(f, "Setting({max})"),
+            Datatype::Number => write!(f, "Number"),
+            Datatype::SignedNumber => write!(f, "SignedNumber"),
+            Datatype::Float(factor) => write!(f, "Float({factor})"),
+            Datatype::DateTime => write!(f, "DateTime"),
+            Datatype::Schedule => write!(f, "Schedule"),
+            Datatype::Trigger(token) => write!(f, "Trigger({token})"),
+            Datatype::String => write!(f, "String"),
+            Datatype::ErrorRecord => write!(f, "ErrorRecord"),
+        }
+    }
+}
+
+impl FromStr for Datatype {
+    type Err = BsbError;
+
+    /// Parse a `Datatype` from the notation used in the field database, e.g. "Setting(3)".
+    /// This is the reverse of Display for `Datatype`. A `Float` needs a factor greater than 0
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        // split "<name>(<parameter>)" into name and optional parameter
+        let (name, parameter) = match s.split_once('(') {
+            Some((name, rest)) => {
+                let parameter = rest.strip_suffix(')').ok_or(BsbError::InvalidDatatype)?;
+                (name.trim(), Some(parameter.trim().parse::<u8>()?))
+            }
+            None => (s, None),
+        };
+        match (name, parameter) {
+            ("Setting", Some(max)) => Ok(Datatype::Setting(max)),
+            ("Number", None) => Ok(Datatype::Number),
+            ("SignedNumber", None) => Ok(Datatype::SignedNumber),
+            // a factor of 0 would decode every value to inf or NaN
+            ("Float", Some(factor)) if factor > 0 => Ok(Datatype::Float(factor)),
+            ("DateTime", None) => Ok(Datatype::DateTime),
+            ("Schedule", None) => Ok(Datatype::Schedule),
+            ("Trigger", Some(token)) => Ok(Datatype::Trigger(token)),
+            ("String", None) => Ok(Datatype::String),
+            ("ErrorRecord", None) => Ok(Datatype::ErrorRecord),
+            _ => Err(BsbError::InvalidDatatype),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr as _;
+
+    use crate::BsbError;
+
+    use super::Datatype;
+
+    /// a set of datatypes together with their string representation
+    fn datatype_testcases() -> Vec<(Datatype, &'static str)> {
+        vec![
+            (Datatype::Setting(3), "Setting(3)"),
+            (Datatype::Number, "Number"),
+            (Datatype::SignedNumber, "SignedNumber"),
+            (Datatype::Float(64), "Float(64)"),
+            (Datatype::DateTime, "DateTime"),
+            (Datatype::Schedule, "Schedule"),
+            (Datatype::Trigger(1), "Trigger(1)"),
+            (Datatype::String, "String"),
+            (Datatype::ErrorRecord, "ErrorRecord"),
+        ]
+    }
+
+    #[test]
+    fn test_datatype_to_string() {
+        for (datatype, string) in datatype_testcases() {
+            assert_eq!(datatype.to_string(), string);
+        }
+    }
+
+    #[test]
+    fn test_datatype_code_and_name() {
+        let codes = datatype_testcases()
+            .iter()
+            .map(|(datatype, _)| datatype.code())
+            .collect::<Vec<_>>();
+        assert_eq!(codes, [1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        for (datatype, string) in datatype_testcases() {
+            assert!(string.starts_with(datatype.name()));
+        }
+    }
+
+    #[test]
+    fn test_datatype_from_str() {
+        for (datatype, string) in datatype_testcases() {
+            assert_eq!(Datatype::from_str(string).unwrap(), datatype);
+        }
+    }
+
+    #[test]
+    fn test_datatype_from_str_errors() {
+        let error_testcases = vec![
+            ("Float", BsbError::InvalidDatatype),
+            ("Float(0)", BsbError::InvalidDatatype),
+            ("Number(1)", BsbError::InvalidDatatype),
+            ("Setting(3", BsbError::InvalidDatatype),
+            ("Unknown", BsbError::InvalidDatatype),
+        ];
+        for (string, error) in error_testcases {
+            assert_eq!(Datatype::from_str(string).expect_err("not an error"), error);
+        }
+        assert!(matches!(
+            Datatype::from_str("Float(256)"),
+            Err(BsbError::ParseIntError(_))
+        ));
+    }
 }
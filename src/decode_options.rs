@@ -0,0 +1,100 @@
+use std::sync::Arc;
+
+use crate::{CalibrationRegistry, PacketType, Quirks};
+
+/// `WeekdayCheck` defines how the day of week byte of a `DateTime` payload is handled while decoding
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum WeekdayCheck {
+    /// The day of week is ignored, it is derived from the date anyway
+    #[default]
+    Ignore,
+    /// Payloads with a day of week that doesn't match the date are rejected with `BsbError::InvalidWeekday`.
+    /// This catches shifted bytes and corrupted controller clocks
+    Reject,
+}
+
+/// `DecodeOptions` allow to customize the decoding of payloads with `Value::decode_with`
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DecodeOptions {
+    weekday_check: WeekdayCheck,
+    quirks: Quirks,
+    retain_raw: bool,
+    packet_type: Option<PacketType>,
+    calibrations: Arc<CalibrationRegistry>,
+}
+
+impl DecodeOptions {
+    /// Return the `DecodeOptions` with the `weekday_check` for `DateTime` payloads
+    #[must_use]
+    pub fn with_weekday_check(mut self, weekday_check: WeekdayCheck) -> DecodeOptions {
+        self.weekday_check = weekday_check;
+        self
+    }
+
+    /// Access `DecodeOptions.weekday_check`
+    #[must_use]
+    pub fn weekday_check(&self) -> WeekdayCheck {
+        self.weekday_check
+    }
+
+    /// Return the `DecodeOptions` with the device `quirks`
+    #[must_use]
+    pub fn with_quirks(mut self, quirks: Quirks) -> DecodeOptions {
+        self.quirks = quirks;
+        self
+    }
+
+    /// Access `DecodeOptions.quirks`
+    #[must_use]
+    pub fn quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    /// Return the `DecodeOptions` that keep the original payload in `FieldValue::raw`,
+    /// e.g. to store byte-exact evidence next to a lossy decoded value
+    #[must_use]
+    pub fn with_retain_raw(mut self, retain_raw: bool) -> DecodeOptions {
+        self.retain_raw = retain_raw;
+        self
+    }
+
+    /// Access `DecodeOptions.retain_raw`
+    #[must_use]
+    pub fn retain_raw(&self) -> bool {
+        self.retain_raw
+    }
+
+    /// Return the `DecodeOptions` for a payload of a frame with `packet_type`.
+    /// In `Ret` answers the flag 1 of a numeric payload marks an unset value and `Info` payloads
+    /// carry no flag
+    #[must_use]
+    pub fn with_packet_type(mut self, packet_type: PacketType) -> DecodeOptions {
+        self.packet_type = Some(packet_type);
+        self
+    }
+
+    /// Access `DecodeOptions.packet_type` if it is known
+    #[must_use]
+    pub fn packet_type(&self) -> Option<PacketType> {
+        self.packet_type
+    }
+
+    /// Return the `DecodeOptions` that apply the `calibrations` in `FieldValue::from_frame_with`.
+    /// Clones of the `DecodeOptions` share the `calibrations`
+    #[must_use]
+    pub fn with_calibrations(mut self, calibrations: CalibrationRegistry) -> DecodeOptions {
+        self.calibrations = Arc::new(calibrations);
+        self
+    }
+
+    /// Access `DecodeOptions.calibrations`
+    #[must_use]
+    pub fn calibrations(&self) -> &CalibrationRegistry {
+        &self.calibrations
+    }
+
+    /// Access `DecodeOptions.calibrations` to change them at runtime
+    pub fn calibrations_mut(&mut self) -> &mut CalibrationRegistry {
+        Arc::make_mut(&mut self.calibrations)
+    }
+}
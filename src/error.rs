@@ -1,6 +1,14 @@
+use std::fmt::Display;
+
 use thiserror::Error;
 
-#[derive(Debug, Error, PartialEq)]
+use crate::ParseErrorKind;
+
+/// Errors of this crate. An error compares equal to the error it wraps in
+/// `BsbError::WithContext`, so `error == BsbError::UnsupportedField` holds regardless of the
+/// context. Use `BsbError::root` to match on the variant
+#[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum BsbError {
     #[error("invalid setting")]
     InvalidSetting,
@@ -8,10 +16,20 @@ pub enum BsbError {
     InvalidSchedule,
     #[error("invalid date time")]
     InvalidDateTime,
+    #[error("day of week does not match the date")]
+    InvalidWeekday,
     #[error("invalid payload length")]
     InvalidPayloadLength,
     #[error("cannot parse FieldValue string")]
     InvalidFieldValue,
+    #[error("invalid datatype")]
+    InvalidDatatype,
+    #[error("invalid packet type")]
+    InvalidPacketType,
+    #[error("incompatible unit")]
+    IncompatibleUnit,
+    #[error("invalid calibration")]
+    InvalidCalibration,
     #[error("no flag")]
     NoFlag,
     #[error(transparent)]
@@ -22,4 +40,292 @@ pub enum BsbError {
     ParseDateTimeError(#[from] chrono::ParseError),
     #[error("unsupported field")]
     UnsupportedField,
+    /// The field cannot be written
+    #[error("read-only field")]
+    ReadOnlyField,
+    /// The value is outside the limits of the field
+    #[error("value out of range")]
+    OutOfRange,
+    /// A `Frame` could not be parsed from the byte stream
+    #[error(transparent)]
+    Parse(#[from] ParseErrorKind),
+    /// An error with additional information about the failed operation, the wrapped error is
+    /// its `source`
+    #[error("{context}")]
+    WithContext {
+        context: ErrorContext,
+        source: Box<BsbError>,
+    },
+}
+
+/// `ErrorCategory` groups the errors by their typical cause so they can be handled programmatically
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorCategory {
+    /// The field is not known to this crate, this is expected and can usually be ignored
+    Unsupported,
+    /// The payload of a `Frame` contains data that does not fit the datatype
+    Decode,
+    /// A string or other user provided input could not be parsed
+    Input,
+    /// The byte stream is broken (e.g. checksum errors) which hints at wiring or timing problems
+    Protocol,
+}
+
+/// `Operation` that was executed when an error occurred
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Operation {
+    /// Decoding a payload into a `Value`
+    Decode,
+    /// Parsing a `Value` from a string
+    Parse,
+    /// Looking up a `Field`
+    Lookup,
+}
+
+/// `ErrorContext` describes the failed operation, the involved field and the payload if available
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorContext {
+    operation: Operation,
+    field_id: Option<u32>,
+    payload: Option<Vec<u8>>,
+}
+
+impl ErrorContext {
+    /// Create a new `ErrorContext` for an `operation` on the field with `field_id`
+    #[must_use]
+    pub fn new(operation: Operation, field_id: u32) -> ErrorContext {
+        ErrorContext {
+            operation,
+            field_id: Some(field_id),
+            payload: None,
+        }
+    }
+
+    /// Create a new `ErrorContext` for an `operation` without a field id,
+    /// e.g. the lookup of a field by its name
+    #[must_use]
+    pub fn without_field(operation: Operation) -> ErrorContext {
+        ErrorContext {
+            operation,
+            field_id: None,
+            payload: None,
+        }
+    }
+
+    /// Attach the `payload` that could not be processed
+    #[must_use]
+    pub fn with_payload(mut self, payload: &[u8]) -> ErrorContext {
+        self.payload = Some(payload.to_vec());
+        self
+    }
+
+    /// Access `ErrorContext.operation`
+    #[must_use]
+    pub fn operation(&self) -> Operation {
+        self.operation
+    }
+
+    /// Access `ErrorContext.field_id`
+    #[must_use]
+    pub fn field_id(&self) -> Option<u32> {
+        self.field_id
+    }
+
+    /// Access `ErrorContext.payload`
+    #[must_use]
+    pub fn payload(&self) -> Option<&[u8]> {
+        self.payload.as_deref()
+    }
+}
+
+impl Display for ErrorContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} failed", self.operation)?;
+        if let Some(field_id) = self.field_id {
+            write!(f, " for field 0x{field_id:08x}")?;
+        }
+        if let Some(payload) = &self.payload {
+            write!(f, " with payload {payload:02x?}")?;
+        }
+        Ok(())
+    }
+}
+
+impl PartialEq for BsbError {
+    /// Compare the root errors, the `ErrorContext` is ignored
+    fn eq(&self, other: &Self) -> bool {
+        match (self.root(), other.root()) {
+            (BsbError::ParseIntError(a), BsbError::ParseIntError(b)) => a == b,
+            (BsbError::ParseFloatError(a), BsbError::ParseFloatError(b)) => a == b,
+            (BsbError::ParseDateTimeError(a), BsbError::ParseDateTimeError(b)) => a == b,
+            (BsbError::Parse(a), BsbError::Parse(b)) => a == b,
+            (a, b) => std::mem::discriminant(a) == std::mem::discriminant(b),
+        }
+    }
+}
+
+impl BsbError {
+    /// Attach an `ErrorContext` to this error
+    #[must_use]
+    pub fn with_context(self, context: ErrorContext) -> BsbError {
+        BsbError::WithContext {
+            context,
+            source: Box::new(self),
+        }
+    }
+
+    /// Access the `ErrorContext` if available
+    #[must_use]
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            BsbError::WithContext { context, .. } => Some(context),
+            _ => None,
+        }
+    }
+
+    /// The underlying error without any `ErrorContext`
+    #[must_use]
+    pub fn root(&self) -> &BsbError {
+        match self {
+            BsbError::WithContext { source, .. } => source.root(),
+            error => error,
+        }
+    }
+
+    /// The `ErrorCategory` of this error. Errors that occur both when decoding a payload and
+    /// when parsing a string are categorized by the `Operation` of the `ErrorContext`
+    #[must_use]
+    pub fn category(&self) -> ErrorCategory {
+        let operation = self.context().map(ErrorContext::operation);
+        match self.root() {
+            BsbError::UnsupportedField => ErrorCategory::Unsupported,
+            BsbError::InvalidSetting | BsbError::InvalidSchedule
+                if operation == Some(Operation::Parse) =>
+            {
+                ErrorCategory::Input
+            }
+            BsbError::InvalidFieldValue if operation == Some(Operation::Decode) => {
+                ErrorCategory::Decode
+            }
+            BsbError::InvalidSetting
+            | BsbError::InvalidSchedule
+            | BsbError::InvalidDateTime
+            | BsbError::InvalidWeekday
+            | BsbError::InvalidPayloadLength
+            | BsbError::NoFlag => ErrorCategory::Decode,
+            BsbError::InvalidFieldValue
+            | BsbError::InvalidDatatype
+            | BsbError::InvalidPacketType
+            | BsbError::IncompatibleUnit
+            | BsbError::InvalidCalibration
+            | BsbError::ParseIntError(_)
+            | BsbError::ParseFloatError(_)
+            | BsbError::ParseDateTimeError(_)
+            | BsbError::ReadOnlyField
+            | BsbError::OutOfRange => ErrorCategory::Input,
+            BsbError::Parse(_) => ErrorCategory::Protocol,
+            BsbError::WithContext { .. } => unreachable!("root() never returns a context"),
+        }
+    }
+
+    /// A stable numeric code for this error that does not change between versions of this crate
+    #[must_use]
+    pub fn code(&self) -> u16 {
+        match self.root() {
+            BsbError::InvalidSetting => 1,
+            BsbError::InvalidSchedule => 2,
+            BsbError::InvalidDateTime => 3,
+            BsbError::InvalidPayloadLength => 4,
+            BsbError::InvalidFieldValue => 5,
+            BsbError::NoFlag => 6,
+            BsbError::ParseIntError(_) => 7,
+            BsbError::ParseFloatError(_) => 8,
+            BsbError::ParseDateTimeError(_) => 9,
+            BsbError::UnsupportedField => 10,
+            BsbError::InvalidPacketType => 11,
+            BsbError::InvalidDatatype => 12,
+            BsbError::IncompatibleUnit => 15,
+            BsbError::InvalidCalibration => 16,
+            BsbError::InvalidWeekday => 17,
+            BsbError::ReadOnlyField => 18,
+            BsbError::OutOfRange => 19,
+            BsbError::Parse(kind) => kind.code(),
+            BsbError::WithContext { .. } => unreachable!("root() never returns a context"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ParseErrorKind;
+
+    use super::{BsbError, ErrorCategory, ErrorContext, Operation};
+
+    #[test]
+    fn test_error_category() {
+        assert_eq!(
+            BsbError::UnsupportedField.category(),
+            ErrorCategory::Unsupported
+        );
+        assert_eq!(BsbError::InvalidSetting.category(), ErrorCategory::Decode);
+        assert_eq!(BsbError::InvalidDatatype.category(), ErrorCategory::Input);
+        assert_eq!(
+            BsbError::from(ParseErrorKind::ChecksumError).category(),
+            ErrorCategory::Protocol
+        );
+        // (<error>, <operation>, <category>)
+        let testcases = vec![
+            (
+                BsbError::InvalidFieldValue,
+                Operation::Decode,
+                ErrorCategory::Decode,
+            ),
+            (
+                BsbError::InvalidFieldValue,
+                Operation::Parse,
+                ErrorCategory::Input,
+            ),
+            (
+                BsbError::InvalidSetting,
+                Operation::Decode,
+                ErrorCategory::Decode,
+            ),
+            (
+                BsbError::InvalidSetting,
+                Operation::Parse,
+                ErrorCategory::Input,
+            ),
+        ];
+        for (error, operation, want) in testcases {
+            let testcase = error.with_context(ErrorContext::new(operation, 0x053d_19f0));
+            assert_eq!(testcase.category(), want);
+        }
+    }
+
+    #[test]
+    fn test_error_with_context() {
+        let context = ErrorContext::new(Operation::Decode, 0x053d_19f0).with_payload(&[0, 3]);
+        let testcase = BsbError::InvalidSetting.with_context(context.clone());
+        assert_eq!(testcase, BsbError::InvalidSetting);
+        assert_ne!(testcase, BsbError::UnsupportedField);
+        assert_eq!(testcase.root(), &BsbError::InvalidSetting);
+        assert_eq!(testcase.context(), Some(&context));
+        assert_eq!(testcase.code(), BsbError::InvalidSetting.code());
+        assert_eq!(testcase.category(), ErrorCategory::Decode);
+        // the wrapped error is only the source, so a report of the chain does not repeat it
+        assert_eq!(
+            testcase.to_string(),
+            "Decode failed for field 0x053d19f0 with payload [00, 03]"
+        );
+        let source = std::error::Error::source(&testcase).unwrap();
+        assert_eq!(source.to_string(), "invalid setting");
+    }
+
+    #[test]
+    fn test_error_code() {
+        assert_eq!(BsbError::UnsupportedField.code(), 10);
+        assert_eq!(BsbError::from(ParseErrorKind::ChecksumError).code(), 13);
+    }
 }
@@ -0,0 +1,154 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{hex::to_hex, FieldValue, Frame, PacketType, UnitPreference};
+
+/// `DecodedEvent` is a flat representation of a `Frame` together with the decoded value
+/// if the field is known. It is used for the conversion into JSON Lines or CSV
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecodedEvent {
+    source_address: u8,
+    destination_address: u8,
+    packet_type: String,
+    field_id: String,
+    name: Option<String>,
+    value: Option<String>,
+    payload: String,
+    /// Identifier of the bus the frame was received on if several buses are used
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    bus: Option<String>,
+}
+
+impl DecodedEvent {
+    /// Create a `DecodedEvent` from a `Frame` and decode the payload if possible
+    #[must_use]
+    pub fn from_frame(frame: &Frame) -> DecodedEvent {
+        Self::from_frame_in(frame, &UnitPreference::default())
+    }
+
+    /// Create a `DecodedEvent` from a `Frame` with the value converted into the unit of the
+    /// `preference`
+    #[must_use]
+    pub fn from_frame_in(frame: &Frame, preference: &UnitPreference) -> DecodedEvent {
+        let field_value = FieldValue::from_frame(frame).ok();
+        DecodedEvent {
+            source_address: frame.source_address(),
+            destination_address: frame.destination_address(),
+            packet_type: PacketType::try_from(frame.packet_type())
+                .map_or_else(|_| frame.packet_type().to_string(), |t| t.to_string()),
+            field_id: format!("0x{:08x}", frame.field_id()),
+            name: field_value
+                .as_ref()
+                .map(|field_value| field_value.field().name().to_string()),
+            value: field_value
+                .as_ref()
+                .map(|field_value| field_value.value_str_in(preference)),
+            payload: to_hex(frame.payload()),
+            bus: None,
+        }
+    }
+
+    /// Return the `DecodedEvent` tagged with the identifier of the `bus` it was received on,
+    /// e.g. "lpb" or "boiler2"
+    #[must_use]
+    pub fn with_bus(mut self, bus: impl Into<String>) -> DecodedEvent {
+        self.bus = Some(bus.into());
+        self
+    }
+
+    /// Access `DecodedEvent.source_address`
+    #[must_use]
+    pub fn source_address(&self) -> u8 {
+        self.source_address
+    }
+
+    /// Access `DecodedEvent.destination_address`
+    #[must_use]
+    pub fn destination_address(&self) -> u8 {
+        self.destination_address
+    }
+
+    /// Access `DecodedEvent.packet_type`, e.g. "Ret"
+    #[must_use]
+    pub fn packet_type(&self) -> &str {
+        &self.packet_type
+    }
+
+    /// Access `DecodedEvent.field_id` as hex string, e.g. "0x053d19f0"
+    #[must_use]
+    pub fn field_id(&self) -> &str {
+        &self.field_id
+    }
+
+    /// Access `DecodedEvent.name` if the field is known
+    #[must_use]
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Access `DecodedEvent.value` if the payload could be decoded
+    #[must_use]
+    pub fn value(&self) -> Option<&str> {
+        self.value.as_deref()
+    }
+
+    /// Access `DecodedEvent.payload` as hex string
+    #[must_use]
+    pub fn payload(&self) -> &str {
+        &self.payload
+    }
+
+    /// Access `DecodedEvent.bus` if the event is tagged with a bus identifier
+    #[must_use]
+    pub fn bus(&self) -> Option<&str> {
+        self.bus.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Frame, Unit, UnitPreference};
+
+    use super::DecodedEvent;
+
+    #[test]
+    fn test_decoded_event_from_frame() {
+        let frame = Frame::new(66, 0, 7, 0x053d_19f0, vec![0, 0, 15]).unwrap();
+        let testcase = DecodedEvent::from_frame(&frame);
+        assert_eq!(testcase.source_address(), 0);
+        assert_eq!(testcase.destination_address(), 66);
+        assert_eq!(testcase.packet_type(), "Ret");
+        assert_eq!(testcase.field_id(), "0x053d19f0");
+        assert_eq!(testcase.name(), Some("water_pressure"));
+        assert_eq!(testcase.value(), Some("1.5"));
+        assert_eq!(testcase.payload(), "00000f");
+        let preference = UnitPreference::default().with_pressure(Unit::Psi);
+        let testcase = DecodedEvent::from_frame_in(&frame, &preference);
+        assert_eq!(testcase.value(), Some("21.76"));
+    }
+
+    #[test]
+    fn test_decoded_event_from_unknown_frame() {
+        let frame = Frame::new(66, 0, 42, 0x0000_0001, vec![1, 2]).unwrap();
+        let testcase = DecodedEvent::from_frame(&frame);
+        assert_eq!(testcase.packet_type(), "42");
+        assert_eq!(testcase.name(), None);
+        assert_eq!(testcase.value(), None);
+        assert_eq!(testcase.payload(), "0102");
+    }
+
+    #[test]
+    fn test_decoded_event_with_bus() {
+        let frame = Frame::new(66, 0, 7, 0x053d_19f0, vec![0, 0, 15]).unwrap();
+        let untagged = DecodedEvent::from_frame(&frame);
+        assert_eq!(untagged.bus(), None);
+        assert!(!serde_json::to_string(&untagged).unwrap().contains("bus"));
+        let testcase = untagged.with_bus("lpb");
+        assert_eq!(testcase.bus(), Some("lpb"));
+        let json = serde_json::to_string(&testcase).unwrap();
+        assert!(json.ends_with(r#""bus":"lpb"}"#));
+        assert_eq!(
+            serde_json::from_str::<DecodedEvent>(&json).unwrap(),
+            testcase
+        );
+    }
+}
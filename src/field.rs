@@ -1,58 +1,248 @@
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr};
 
 use serde::Serialize;
 
-use crate::Datatype;
+use crate::{BsbError, Datatype, Unit, Value};
 // include the bsb field definitions in a static map in `FIELDS`
 include!(concat!(env!("OUT_DIR"), "/field_db.rs"));
 
 /// the `name` and `datatype` of this `Field`
-#[derive(Debug, PartialEq, Serialize)]
+#[derive(Debug, PartialEq, Eq, Hash, Serialize)]
 pub struct Field {
     id: u32,
     name: &'static str,
     prognr: usize,
+    /// sub number of fields with several logical values in one telegram, 0 if there is none
+    #[serde(skip_serializing_if = "is_zero")]
+    sub_prognr: u8,
     datatype: Datatype,
     path: &'static str,
+    unit: Option<Unit>,
+    /// lowest accepted value as raw integer of the payload
+    #[serde(skip)]
+    min: Option<i32>,
+    /// highest accepted value as raw integer of the payload
+    #[serde(skip)]
+    max: Option<i32>,
+    writable: bool,
+    /// labels of `Setting` values, e.g. `(1, "Automatic")`
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    labels: &'static [(u8, &'static str)],
+}
+
+/// Check if a `sub_prognr` is not set
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn is_zero(sub_prognr: &u8) -> bool {
+    *sub_prognr == 0
+}
+
+/// Program number in the dotted BSB-LAN notation, e.g. "8700" or "8700.1"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Prognr {
+    number: usize,
+    sub: u8,
+}
+
+impl Prognr {
+    /// Create a new `Prognr`. A `sub` number of 0 means there is no sub number
+    #[must_use]
+    pub fn new(number: usize, sub: u8) -> Prognr {
+        Prognr { number, sub }
+    }
+
+    /// Access `Prognr.number`
+    #[must_use]
+    pub fn number(&self) -> usize {
+        self.number
+    }
+
+    /// Access `Prognr.sub` if it is set
+    #[must_use]
+    pub fn sub(&self) -> Option<u8> {
+        (self.sub != 0).then_some(self.sub)
+    }
+}
+
+impl Display for Prognr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.sub() {
+            Some(sub) => write!(f, "{}.{sub}", self.number),
+            None => write!(f, "{}", self.number),
+        }
+    }
+}
+
+impl FromStr for Prognr {
+    type Err = BsbError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (number, sub) = match s.split_once('.') {
+            Some((number, sub)) => (number, sub.parse()?),
+            None => (s, 0),
+        };
+        Ok(Prognr::new(number.parse()?, sub))
+    }
 }
 
 impl Field {
     /// Try to get a `Field` definition from an field `id`
+    #[must_use]
     pub fn by_id(id: u32) -> Option<&'static Field> {
         FIELDS.get(&id)
     }
 
     /// Try to get a `Field` definition from a field `name`
+    #[must_use]
     pub fn by_name(name: &str) -> Option<&'static Field> {
         FIELDS.values().find(|field| field.name == name)
     }
 
+    /// Try to get a `Field` definition from a field `path`
+    #[must_use]
+    pub fn by_path(path: &str) -> Option<&'static Field> {
+        FIELDS.values().find(|field| field.path == path)
+    }
+
+    /// Try to get a `Field` definition from a program number `prognr` without a sub number.
+    /// Fields without a known program number use 0 and cannot be found with this function
+    #[must_use]
+    pub fn by_prognr(prognr: usize) -> Option<&'static Field> {
+        Self::by_full_prognr(Prognr::new(prognr, 0))
+    }
+
+    /// Try to get a `Field` definition from a program number with an optional sub number,
+    /// e.g. `"8700.1".parse()?`
+    #[must_use]
+    pub fn by_full_prognr(prognr: Prognr) -> Option<&'static Field> {
+        if prognr.number == 0 {
+            return None;
+        }
+        FIELDS.values().find(|field| field.full_prognr() == prognr)
+    }
+
     /// Access `Field.id`
+    #[must_use]
     pub fn id(&self) -> u32 {
         self.id
     }
 
     /// Access `Field.datatype`
+    #[must_use]
     pub fn datatype(&self) -> Datatype {
         self.datatype
     }
 
     /// Access `Field.prognr`
+    #[must_use]
     pub fn prognr(&self) -> usize {
         self.prognr
     }
 
+    /// Access `Field.sub_prognr` if the field has a sub number
+    #[must_use]
+    pub fn sub_prognr(&self) -> Option<u8> {
+        (self.sub_prognr != 0).then_some(self.sub_prognr)
+    }
+
+    /// Program number together with the sub number, e.g. "8700.1"
+    #[must_use]
+    pub fn full_prognr(&self) -> Prognr {
+        Prognr::new(self.prognr, self.sub_prognr)
+    }
+
     /// Access `Field.name`
+    #[must_use]
     pub fn name(&self) -> &'static str {
         self.name
     }
 
     /// Access `Field.path`
+    #[must_use]
     pub fn path(&self) -> &'static str {
         self.path
     }
 
+    /// Access `Field.unit`
+    #[must_use]
+    pub fn unit(&self) -> Option<Unit> {
+        self.unit
+    }
+
+    /// Lowest value the device accepts, e.g. for a set point
+    #[must_use]
+    pub fn min(&self) -> Option<f64> {
+        self.min.map(|min| f64::from(min) / self.limit_factor())
+    }
+
+    /// Highest value the device accepts, e.g. for a set point
+    #[must_use]
+    pub fn max(&self) -> Option<f64> {
+        self.max.map(|max| f64::from(max) / self.limit_factor())
+    }
+
+    /// Access `Field.writable`, `false` for read-only fields like measurements
+    #[must_use]
+    pub fn is_writable(&self) -> bool {
+        self.writable
+    }
+
+    /// Label of a `setting` value of this `Field`, e.g. "Automatic" for 1 of an operating mode
+    #[must_use]
+    pub fn setting_label(&self, setting: u8) -> Option<&'static str> {
+        self.labels
+            .iter()
+            .find(|(value, _)| *value == setting)
+            .map(|(_, label)| *label)
+    }
+
+    /// Setting value of a `label` of this `Field`, the reverse of `Field::setting_label`.
+    /// Labels are matched case-insensitive
+    #[must_use]
+    pub fn setting_by_label(&self, label: &str) -> Option<u8> {
+        self.labels
+            .iter()
+            .find(|(_, known)| known.eq_ignore_ascii_case(label))
+            .map(|(value, _)| *value)
+    }
+
+    /// Check if the `value` is within the limits of this `Field`.
+    /// Values without a numeric representation are always within the limits
+    ///
+    /// # Errors
+    ///
+    /// Returns `BsbError::OutOfRange` if the value is lower than `min` or higher than `max`, or if a
+    /// float is not finite or does not fit into the payload
+    pub fn check_range(&self, value: &Value) -> Result<(), BsbError> {
+        if !value.fits_payload() {
+            return Err(BsbError::OutOfRange);
+        }
+        // compare the raw integers of the payload to avoid float artifacts
+        let raw = match value {
+            Value::Setting { setting, .. } => i32::from(*setting),
+            Value::Number { value, .. } => i32::from(*value),
+            Value::SignedNumber { value, .. } => i32::from(*value),
+            Value::Float { .. } => match value.as_fixed_point() {
+                Some(fixed_point) => i32::from(fixed_point.raw()),
+                None => return Ok(()),
+            },
+            _ => return Ok(()),
+        };
+        if self.min.is_some_and(|min| raw < min) || self.max.is_some_and(|max| raw > max) {
+            return Err(BsbError::OutOfRange);
+        }
+        Ok(())
+    }
+
+    /// Factor between the raw limits and the values
+    fn limit_factor(&self) -> f64 {
+        match self.datatype {
+            Datatype::Float(factor) => f64::from(factor),
+            _ => 1.0,
+        }
+    }
+
     /// Iterator over the known fields
+    #[must_use]
     pub fn iter<'a>() -> phf::map::Entries<'a, u32, Field> {
         FIELDS.entries()
     }
@@ -66,16 +256,22 @@ impl Display for Field {
 
 #[cfg(test)]
 mod tests {
-    use crate::Datatype;
+    use crate::{BsbError, Datatype, Unit, Value};
 
-    use super::Field;
+    use super::{Field, Prognr};
 
     const TESTFIELD: Field = Field {
         id: 0x313d052f,
         name: "warmwater_temperature",
         prognr: 8701,
+        sub_prognr: 0,
         datatype: Datatype::Float(64),
         path: "temperature/warmwater",
+        unit: Some(Unit::Celsius),
+        min: None,
+        max: None,
+        writable: false,
+        labels: &[],
     };
 
     #[test]
@@ -92,6 +288,45 @@ mod tests {
         assert_eq!(testcase, &want)
     }
 
+    #[test]
+    fn test_field_db_by_path() {
+        let testcase = Field::by_path(TESTFIELD.path).unwrap();
+        let want = TESTFIELD;
+        assert_eq!(testcase, &want);
+    }
+
+    #[test]
+    fn test_field_db_by_prognr() {
+        let testcase = Field::by_prognr(TESTFIELD.prognr).unwrap();
+        let want = TESTFIELD;
+        assert_eq!(testcase, &want);
+        assert_eq!(Field::by_prognr(0), None);
+        let testcase = Field::by_full_prognr("8701".parse().unwrap()).unwrap();
+        assert_eq!(testcase, &want);
+        assert_eq!(Field::by_full_prognr(Prognr::new(8701, 1)), None);
+    }
+
+    #[test]
+    fn test_prognr_from_str() {
+        // (<input>, <prognr>)
+        let testcases = vec![
+            ("8700", Prognr::new(8700, 0)),
+            ("8700.1", Prognr::new(8700, 1)),
+            ("8700.0", Prognr::new(8700, 0)),
+        ];
+        for (input, want) in testcases {
+            let testcase: Prognr = input.parse().unwrap();
+            assert_eq!(testcase, want);
+        }
+        assert_eq!(Prognr::new(8700, 1).to_string(), "8700.1");
+        assert_eq!(Prognr::new(8700, 0).to_string(), "8700");
+        assert_eq!(Prognr::new(8700, 1).sub(), Some(1));
+        assert!(matches!(
+            "8700.x".parse::<Prognr>(),
+            Err(BsbError::ParseIntError(_))
+        ));
+    }
+
     #[test]
     fn test_field_to_string() {
         let testcase = TESTFIELD.to_string();
@@ -118,6 +353,8 @@ mod tests {
         let testcase = TESTFIELD.prognr();
         let want = 8701;
         assert_eq!(testcase, want);
+        assert_eq!(TESTFIELD.sub_prognr(), None);
+        assert_eq!(TESTFIELD.full_prognr().to_string(), "8701");
     }
 
     #[test]
@@ -134,6 +371,51 @@ mod tests {
         assert_eq!(testcase, want);
     }
 
+    #[test]
+    fn test_field_unit() {
+        let testcase = TESTFIELD.unit();
+        let want = Some(Unit::Celsius);
+        assert_eq!(testcase, want);
+    }
+
+    #[test]
+    fn test_field_limits() {
+        let field = Field::by_name("heating_circuit_1_curve_shift").unwrap();
+        assert_eq!(field.min(), Some(-4.5));
+        assert_eq!(field.max(), Some(4.5));
+        assert!(field.is_writable());
+        assert!(!TESTFIELD.is_writable());
+        assert_eq!(TESTFIELD.min(), None);
+        // (<value>, <result>)
+        let testcases = vec![
+            ("-4.5", Ok(())),
+            ("4.5", Ok(())),
+            ("4.52", Err(BsbError::OutOfRange)),
+            ("-5", Err(BsbError::OutOfRange)),
+        ];
+        for (value, want) in testcases {
+            let value = Value::from_str(value, field.datatype()).unwrap();
+            assert_eq!(field.check_range(&value), want);
+        }
+        // fields without limits accept everything that fits into the payload
+        let value = Value::from_str("100", TESTFIELD.datatype()).unwrap();
+        assert_eq!(TESTFIELD.check_range(&value), Ok(()));
+        for value in ["NaN", "inf", "-inf", "512", "-512.5"] {
+            let value = Value::from_str(value, TESTFIELD.datatype()).unwrap();
+            assert_eq!(TESTFIELD.check_range(&value), Err(BsbError::OutOfRange));
+        }
+    }
+
+    #[test]
+    fn test_field_setting_label() {
+        let field = Field::by_prognr(700).unwrap();
+        assert_eq!(field.setting_label(1), Some("Automatic"));
+        assert_eq!(field.setting_label(4), None);
+        assert_eq!(field.setting_by_label("comfort"), Some(3));
+        assert_eq!(field.setting_by_label("Eco"), None);
+        assert_eq!(TESTFIELD.setting_label(0), None);
+    }
+
     #[test]
     fn test_field_iter() {
         let testcase = Field::iter().next();
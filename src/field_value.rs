@@ -1,59 +1,155 @@
-use std::fmt::Display;
+use std::{borrow::Cow, fmt::Display};
 
 use serde::{Deserialize, Serialize};
 
-use crate::{BsbError, Field, Frame, NamedValue, Value};
+use crate::{
+    BsbError, CalibrationRegistry, Datatype, DecodeOptions, ErrorContext, Field, Frame,
+    FrameSegment, NamedValue, Operation, PacketType, Unit, UnitPreference, Value,
+};
 
 /// `FieldValue` contains information about the `Field` (via `field_id`) and the `Value`.
 /// Due to the construction, it is guaranteed that the field is supported by this crate.
 /// It can be used to render a datapoint
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 pub struct FieldValue {
     field_id: u32,
     value: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    raw: Option<Vec<u8>>,
 }
 
 impl FieldValue {
     /// Create a new `FieldValue` based on a `value` and a `field_id` that is
     /// guaranteed to exist if it returns a `FieldValue`
+    ///
+    /// # Errors
+    ///
+    /// Returns `BsbError::UnsupportedField` if the `field_id` is unknown.
+    /// Like all errors of the constructors it carries an `ErrorContext`
     pub fn new(field_id: u32, value: Value) -> Result<FieldValue, BsbError> {
-        let field = Field::by_id(field_id).ok_or(BsbError::UnsupportedField)?;
+        let field = lookup(field_id, Operation::Lookup)?;
         Ok(FieldValue {
             field_id: field.id(),
             value,
+            raw: None,
+        })
+    }
+
+    /// Create the `FieldValue` that fires the command of a write-only `Datatype::Trigger` field.
+    /// Use `to_set_frame` to send it
+    ///
+    /// # Errors
+    ///
+    /// Returns `BsbError::UnsupportedField` if the `field_id` is unknown or
+    /// `BsbError::InvalidDatatype` if the field is not a trigger
+    pub fn trigger(field_id: u32) -> Result<FieldValue, BsbError> {
+        let field = lookup(field_id, Operation::Lookup)?;
+        let Datatype::Trigger(token) = field.datatype() else {
+            return Err(BsbError::InvalidDatatype
+                .with_context(ErrorContext::new(Operation::Lookup, field_id)));
+        };
+        Ok(FieldValue {
+            field_id,
+            value: Value::Trigger { flag: 0, token },
+            raw: None,
+        })
+    }
+
+    /// Create the `FieldValue` of a two-state `Datatype::Setting(1)` field, e.g. a switch
+    ///
+    /// # Errors
+    ///
+    /// Returns `BsbError::UnsupportedField` if the `field_id` is unknown or
+    /// `BsbError::InvalidDatatype` if the field is not a two-state setting
+    pub fn from_bool(field_id: u32, value: bool) -> Result<FieldValue, BsbError> {
+        let field = lookup(field_id, Operation::Lookup)?;
+        if field.datatype() != Datatype::Setting(1) {
+            return Err(BsbError::InvalidDatatype
+                .with_context(ErrorContext::new(Operation::Lookup, field_id)));
+        }
+        Ok(FieldValue {
+            field_id,
+            value: Value::Setting {
+                flag: 0,
+                setting: u8::from(value),
+                max: 1,
+            },
+            raw: None,
         })
     }
 
     /// Convert a `Frame` to a `FieldValue` if that `Field` is known
+    ///
+    /// # Errors
+    ///
+    /// Returns `BsbError::UnsupportedField` if the field is unknown or an error if the payload cannot be decoded
     pub fn from_frame(frame: &Frame) -> Result<FieldValue, BsbError> {
-        let field = Field::by_id(frame.field_id()).ok_or(BsbError::UnsupportedField)?;
-        let value = Value::decode(frame.payload(), field.datatype())?;
+        Self::from_frame_with(frame, &DecodeOptions::default())
+    }
+
+    /// Convert a `Frame` to a `FieldValue` if that `Field` is known using custom `DecodeOptions`.
+    /// A `Calibration` of the field in `DecodeOptions::calibrations` is applied to the decoded value
+    ///
+    /// # Errors
+    ///
+    /// Returns `BsbError::UnsupportedField` if the field is unknown or an error if the payload cannot be decoded
+    pub fn from_frame_with(frame: &Frame, options: &DecodeOptions) -> Result<FieldValue, BsbError> {
+        let field = lookup(frame.field_id(), Operation::Decode)?;
+        let options = match PacketType::from_repr(frame.packet_type()) {
+            Some(packet_type) => options.clone().with_packet_type(packet_type),
+            None => options.clone(),
+        };
+        let mut value =
+            Value::decode_with(frame.payload(), field.datatype(), &options).map_err(|error| {
+                error.with_context(
+                    ErrorContext::new(Operation::Decode, field.id()).with_payload(frame.payload()),
+                )
+            })?;
+        if let Some(calibration) = options.calibrations().for_field(field.id()) {
+            calibration.apply(&mut value);
+        }
         Ok(FieldValue {
             field_id: frame.field_id(),
             value,
+            raw: options.retain_raw().then(|| frame.payload().to_vec()),
         })
     }
 
     /// Access `FieldValue.field().path` (e.g. for MQTT)
+    #[must_use]
     pub fn path(&self) -> &'static str {
         self.field().path()
     }
 
     /// Access `FieldValue.field_id`
+    #[must_use]
     pub fn field_id(&self) -> u32 {
         self.field_id
     }
 
     /// Access `FieldValue.field`
+    ///
+    /// # Panics
+    ///
+    /// Does not panic, the field is guaranteed to exist due to construction
+    #[must_use]
     pub fn field(&self) -> &'static Field {
         Field::by_id(self.field_id).expect("field is expected to exist due to construction")
     }
 
     /// Access `FieldValue.value`
+    #[must_use]
     pub fn value(&self) -> &Value {
         &self.value
     }
 
+    /// Access `FieldValue.raw`, the original payload if it was retained while decoding
+    /// (see `DecodeOptions::with_retain_raw`)
+    #[must_use]
+    pub fn raw(&self) -> Option<&[u8]> {
+        self.raw.as_deref()
+    }
+
     /// Access a mutable `FieldValue.value` reference
     pub fn value_mut(&mut self) -> &mut Value {
         &mut self.value
@@ -61,64 +157,304 @@ impl FieldValue {
 
     /// Create a `FieldValue` from a string representation based on the datatype.
     /// This is the reverse of Display for `FieldValue` which prints "`<fieldname>: <value_str>`"
+    ///
+    /// # Errors
+    ///
+    /// Returns a `BsbError` if the string is malformed, the field is unknown or the value cannot be parsed
     pub fn from_str(s: &str, field_id: u32) -> Result<FieldValue, BsbError> {
-        let (name_str, value_str) = s.split_once(":").ok_or(BsbError::InvalidFieldValue)?;
-        let field = Field::by_name(name_str.trim()).ok_or(BsbError::UnsupportedField)?;
-        let value = Value::from_str(value_str.trim(), field.datatype())?;
-        Ok(FieldValue { field_id, value })
+        let (name_str, value_str) = s.split_once(':').ok_or_else(|| {
+            BsbError::InvalidFieldValue.with_context(ErrorContext::without_field(Operation::Parse))
+        })?;
+        let field = lookup_name(name_str.trim())?;
+        let value = Value::from_str_for(value_str.trim(), field)
+            .map_err(|error| error.with_context(ErrorContext::new(Operation::Parse, field.id())))?;
+        Ok(FieldValue {
+            field_id,
+            value,
+            raw: None,
+        })
     }
 
     /// Create a `FieldValue` from a string representatino of the value.
     /// This is the reverse of `FieldValue.value_str()`
+    ///
+    /// # Errors
+    ///
+    /// Returns a `BsbError` if the field is unknown or the value cannot be parsed
     pub fn from_value_str(s: &str, field_id: u32) -> Result<FieldValue, BsbError> {
-        let field = Field::by_id(field_id).ok_or(BsbError::UnsupportedField)?;
-        let value = Value::from_str(s, field.datatype())?;
-        Ok(FieldValue { field_id, value })
+        let field = lookup(field_id, Operation::Parse)?;
+        let value = Value::from_str_for(s, field)
+            .map_err(|error| error.with_context(ErrorContext::new(Operation::Parse, field_id)))?;
+        Ok(FieldValue {
+            field_id,
+            value,
+            raw: None,
+        })
     }
 
-    /// String representation of `FieldValue.value`
+    /// Numeric value converted into `unit` based on the unit of the `Field`
+    ///
+    /// # Errors
+    ///
+    /// Returns `BsbError::IncompatibleUnit` if the field has no unit, the units cannot be converted
+    /// or the value is not numeric
+    pub fn value_in(&self, unit: Unit) -> Result<f64, BsbError> {
+        self.field()
+            .unit()
+            .zip(self.value.as_f64())
+            .and_then(|(field_unit, value)| field_unit.convert(value, unit))
+            .ok_or(BsbError::IncompatibleUnit)
+    }
+
+    /// State of a two-state setting (`On`/`Yes` is `true`, `Off`/`No` is `false`),
+    /// `None` for all other values
+    #[must_use]
+    pub fn as_bool(&self) -> Option<bool> {
+        match self.value {
+            Value::Setting {
+                setting, max: 1, ..
+            } => Some(setting == 1),
+            _ => None,
+        }
+    }
+
+    /// JSON representation of `FieldValue.value`: a number for `Setting`, `Number`, `SignedNumber`,
+    /// `Float` and `Trigger`, a string for `DateTime`, `Schedule` and `String` and null for `Invalid`
+    /// and `Unset`
+    #[must_use]
+    pub fn to_json_value(&self) -> serde_json::Value {
+        match &self.value {
+            Value::Setting { setting: v, .. } | Value::Trigger { token: v, .. } => (*v).into(),
+            Value::Number { value, .. } => (*value).into(),
+            Value::SignedNumber { value, .. } => (*value).into(),
+            // the shortest representation of the f32 avoids artifacts like 22.299999237060547
+            Value::Float { value, .. } => value
+                .to_string()
+                .parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+                .map_or(serde_json::Value::Null, serde_json::Value::Number),
+            Value::Invalid { .. } | Value::Unset { .. } => serde_json::Value::Null,
+            Value::DateTime { .. }
+            | Value::Schedule(_)
+            | Value::String { .. }
+            | Value::ErrorRecord { .. } => self.value_str().into(),
+        }
+    }
+
+    /// String representation of `FieldValue.value`, `Setting` values are shown with their label
+    #[must_use]
     pub fn value_str(&self) -> String {
-        self.value.to_string()
+        self.value.to_string_for(self.field())
+    }
+
+    /// Unit of the `Field` as chosen by the `preference`
+    #[must_use]
+    pub fn unit_in(&self, preference: &UnitPreference) -> Option<Unit> {
+        self.field().unit().map(|unit| preference.resolve(unit))
+    }
+
+    /// String representation of `FieldValue.value` converted into the unit of the `preference`.
+    /// Converted values are rounded to two decimals
+    #[must_use]
+    pub fn value_str_in(&self, preference: &UnitPreference) -> String {
+        match self.unit_in(preference) {
+            Some(unit) if Some(unit) != self.field().unit() => self.value_in(unit).map_or_else(
+                |_| self.value_str(),
+                |value| ((value * 100.0).round() / 100.0).to_string(),
+            ),
+            _ => self.value_str(),
+        }
     }
 
     /// Convert the payload value to byte representation
+    #[must_use]
     pub fn encode(&self) -> Vec<u8> {
         self.value.encode()
     }
 
+    /// Convert the payload value to the byte representation used in a `Frame` with `packet_type`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of `Value::encode_for` if the value cannot be encoded for `packet_type`
+    pub fn encode_for(&self, packet_type: PacketType) -> Result<Vec<u8>, BsbError> {
+        self.value.encode_for(packet_type)
+    }
+
+    /// Convert the payload value to the byte representation used in a `Frame` with `packet_type`.
+    /// A `Calibration` of the field in `calibrations` is reverted before encoding
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of `Value::encode_for` if the value cannot be encoded for `packet_type`
+    pub fn encode_for_with(
+        &self,
+        packet_type: PacketType,
+        calibrations: &CalibrationRegistry,
+    ) -> Result<Vec<u8>, BsbError> {
+        self.calibrated_value(calibrations).encode_for(packet_type)
+    }
+
+    /// The `value` with a `Calibration` of the field in `calibrations` reverted
+    fn calibrated_value(&self, calibrations: &CalibrationRegistry) -> Cow<'_, Value> {
+        match calibrations.for_field(self.field_id) {
+            Some(calibration) => {
+                let mut value = self.value.clone();
+                calibration.invert(&mut value);
+                Cow::Owned(value)
+            }
+            None => Cow::Borrowed(&self.value),
+        }
+    }
+
+    /// Check if the value can be written to the `Field`
+    ///
+    /// # Errors
+    ///
+    /// Returns `BsbError::ReadOnlyField` if the field is not writable or `BsbError::OutOfRange`
+    /// if the value is outside the limits of the field
+    pub fn validate(&self) -> Result<(), BsbError> {
+        self.validate_with(&CalibrationRegistry::new())
+    }
+
+    /// Check if the value with a `Calibration` of the field in `calibrations` reverted can be
+    /// written to the `Field`
+    ///
+    /// # Errors
+    ///
+    /// Returns `BsbError::ReadOnlyField` if the field is not writable or `BsbError::OutOfRange`
+    /// if the value is outside the limits of the field
+    pub fn validate_with(&self, calibrations: &CalibrationRegistry) -> Result<(), BsbError> {
+        let field = self.field();
+        if !field.is_writable() {
+            return Err(BsbError::ReadOnlyField);
+        }
+        field.check_range(&self.calibrated_value(calibrations))
+    }
+
+    /// Create a `Set` `Frame` from `source_address` to `destination_address` that writes this value
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of `FieldValue::validate` if the value cannot be written or
+    /// `BsbError::InvalidPayloadLength` if the encoded value does not fit into a `Frame`
+    pub fn to_set_frame(
+        &self,
+        destination_address: u8,
+        source_address: u8,
+    ) -> Result<Frame, BsbError> {
+        self.to_set_frame_with(
+            destination_address,
+            source_address,
+            &CalibrationRegistry::new(),
+        )
+    }
+
+    /// Create a `Set` `Frame` from `source_address` to `destination_address` that writes this
+    /// value with a `Calibration` of the field in `calibrations` reverted
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of `FieldValue::validate_with` if the value cannot be written or
+    /// `BsbError::InvalidPayloadLength` if the encoded value does not fit into a `Frame`
+    pub fn to_set_frame_with(
+        &self,
+        destination_address: u8,
+        source_address: u8,
+        calibrations: &CalibrationRegistry,
+    ) -> Result<Frame, BsbError> {
+        self.validate_with(calibrations)?;
+        Frame::new_set(
+            destination_address,
+            source_address,
+            self.field_id,
+            self.encode_for_with(PacketType::Set, calibrations)?,
+        )
+    }
+
+    /// Build the `Set` `Frame` for this value without sending it and return the serialized bytes
+    /// together with an annotated breakdown, e.g. to verify the encoding before writing to the bus
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of `FieldValue::validate` if the value cannot be written or
+    /// `BsbError::InvalidPayloadLength` if the encoded value does not fit into a `Frame`
+    pub fn dry_run_set(
+        &self,
+        destination_address: u8,
+        source_address: u8,
+    ) -> Result<(Vec<u8>, Vec<FrameSegment>), BsbError> {
+        let frame = self.to_set_frame(destination_address, source_address)?;
+        Ok((frame.serialize(), frame.breakdown()))
+    }
+
     /// Provide a default `FieldValue` for `Field`. The default is the Zero of this datatype
+    #[must_use]
     pub fn default_for_field(field: &'static Field) -> FieldValue {
         FieldValue {
             field_id: field.id(),
             value: Value::default_for_datatype(field.datatype()),
+            raw: None,
         }
     }
 
     /// Creates a `NamedValue` from the `FieldValue`
+    #[must_use]
     pub fn to_named_value(&self) -> NamedValue {
         NamedValue::new(self.field().name(), self.value_str())
     }
 
+    /// Creates a `NamedValue` from the `FieldValue` with the value converted into the unit of
+    /// the `preference`. Such a `NamedValue` can only be converted back with the same preference
+    #[must_use]
+    pub fn to_named_value_in(&self, preference: &UnitPreference) -> NamedValue {
+        NamedValue::new(self.field().name(), self.value_str_in(preference))
+    }
+
     /// Create a `FieldValue` from the `NameValue`
+    ///
+    /// # Errors
+    ///
+    /// Returns a `BsbError` if the field is unknown or the value cannot be parsed
     pub fn from_named_value(named_value: &NamedValue) -> Result<FieldValue, BsbError> {
-        let field = Field::by_name(named_value.name()).ok_or(BsbError::UnsupportedField)?;
-        let value = Value::from_str(&named_value.value(), field.datatype())?;
+        let field = lookup_name(named_value.name())?;
+        let value = Value::from_str_for(named_value.value(), field)
+            .map_err(|error| error.with_context(ErrorContext::new(Operation::Parse, field.id())))?;
         Ok(FieldValue {
             field_id: field.id(),
             value,
+            raw: None,
         })
     }
 }
 
+/// Look up the `Field` of `field_id`, an unknown field is reported with the `operation`
+fn lookup(field_id: u32, operation: Operation) -> Result<&'static Field, BsbError> {
+    Field::by_id(field_id).ok_or_else(|| {
+        BsbError::UnsupportedField.with_context(ErrorContext::new(operation, field_id))
+    })
+}
+
+/// Look up the `Field` by its `name`
+fn lookup_name(name: &str) -> Result<&'static Field, BsbError> {
+    Field::by_name(name).ok_or_else(|| {
+        BsbError::UnsupportedField.with_context(ErrorContext::without_field(Operation::Lookup))
+    })
+}
+
 impl Display for FieldValue {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}: {}", self.field(), self.value)
+        write!(f, "{}: {}", self.field(), self.value_str())
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{BsbError, Field, Frame, NamedValue, Value};
+    use crate::{
+        BsbError, Calibration, CalibrationRegistry, DecodeOptions, ErrorCategory, Field, Frame,
+        NamedValue, Operation, PacketType, Unit, UnitPreference, Value,
+    };
 
     use super::FieldValue;
 
@@ -130,15 +466,92 @@ mod tests {
                 value: 1.5,
                 factor: 10,
             },
+            raw: None,
         }
     }
 
     #[test]
     fn test_field_value_from_frame() {
-        let frame = Frame::new(66, 0, 7, 87890416, vec![0, 0, 15]);
+        let frame = Frame::new(66, 0, 7, 87890416, vec![0, 0, 15]).unwrap();
         let testcase = FieldValue::from_frame(&frame).unwrap();
         let want = create_test_field_value();
         assert_eq!(testcase, want);
+        assert_eq!(testcase.raw(), None);
+        // a `Ret` with the flag 1 reports an unset value
+        let frame = Frame::new(66, 0, 7, 0x053d_19f0, vec![1, 0, 0]).unwrap();
+        let testcase = FieldValue::from_frame(&frame).unwrap();
+        assert_eq!(testcase.value_str(), "---");
+        assert_eq!(testcase.to_json_value(), serde_json::Value::Null);
+        // the room temperature broadcast of a room unit carries no flag
+        let frame =
+            Frame::new(0x7f, 6, PacketType::Info.into(), 0x2d3d_0215, vec![5, 0x60]).unwrap();
+        let testcase = FieldValue::from_frame(&frame).unwrap();
+        assert_eq!(
+            testcase.to_string(),
+            "heating_circuit_1_room_temperature: 21.5"
+        );
+        assert_eq!(
+            testcase.encode_for(PacketType::Info).unwrap(),
+            frame.payload()
+        );
+    }
+
+    #[test]
+    fn test_field_value_from_frame_retain_raw() {
+        let frame = Frame::new(66, 0, 7, 0x053d_19f0, vec![0, 0, 15]).unwrap();
+        let options = DecodeOptions::default().with_retain_raw(true);
+        let testcase = FieldValue::from_frame_with(&frame, &options).unwrap();
+        assert_eq!(testcase.value(), create_test_field_value().value());
+        assert_eq!(testcase.raw(), Some(&[0, 0, 15][..]));
+        let json = serde_json::to_string(&testcase).unwrap();
+        assert!(json.ends_with(r#""raw":[0,0,15]}"#));
+        let json = serde_json::to_string(&create_test_field_value()).unwrap();
+        assert!(!json.contains("raw"));
+    }
+
+    #[test]
+    fn test_field_value_bool() {
+        let testcase = FieldValue::from_bool(0x053d_0236, true).unwrap();
+        assert_eq!(testcase.as_bool(), Some(true));
+        assert_eq!(testcase.value_str(), "1");
+        // (<value string>, <bool>)
+        let testcases = vec![
+            ("0", false),
+            ("On", true),
+            ("off", false),
+            ("YES", true),
+            ("no", false),
+        ];
+        for (value_str, want) in testcases {
+            let testcase = FieldValue::from_value_str(value_str, 0x053d_0236).unwrap();
+            assert_eq!(testcase.as_bool(), Some(want));
+        }
+        assert_eq!(create_test_field_value().as_bool(), None);
+        assert_eq!(
+            FieldValue::from_bool(0x053d_19f0, true),
+            Err(BsbError::InvalidDatatype)
+        );
+        assert!(FieldValue::from_value_str("on", 0x053d_19f0).is_err());
+    }
+
+    #[test]
+    fn test_field_value_to_json_value() {
+        // (<value string>, <field id>, <json>)
+        let testcases = vec![
+            ("1", 0x053d_0236, "1"),
+            ("22.3", 0x2d3d_058e, "22.3"),
+            ("---", 0x053d_19f0, "null"),
+            (
+                "2024-11-11T09:36:57",
+                0x0500_006c,
+                r#""2024-11-11T09:36:57""#,
+            ),
+            ("6:0-8:0", 0x053d_0a8c, r#""6:0-8:0""#),
+        ];
+        for (value_str, field_id, want) in testcases {
+            let testcase = FieldValue::from_value_str(value_str, field_id).unwrap();
+            assert_eq!(testcase.to_json_value().to_string(), want);
+        }
     }
 
     #[test]
@@ -205,6 +618,19 @@ mod tests {
         assert_eq!(testcase.value, want);
     }
 
+    #[test]
+    fn test_field_value_value_in() {
+        let testcase = create_test_field_value();
+        assert_eq!(testcase.value_in(Unit::Bar), Ok(1.5));
+        assert!((testcase.value_in(Unit::Psi).unwrap() - 21.7557).abs() < 1e-4);
+        assert_eq!(
+            testcase.value_in(Unit::Celsius),
+            Err(BsbError::IncompatibleUnit)
+        );
+        let temperature = FieldValue::from_str("outside_temperature: 20", 0x053d_0521).unwrap();
+        assert_eq!(temperature.value_in(Unit::Fahrenheit), Ok(68.0));
+    }
+
     #[test]
     fn test_field_value_to_value_str() {
         let testcase = create_test_field_value().value_str();
@@ -217,6 +643,10 @@ mod tests {
         let testcase = create_test_field_value().to_string();
         let want = "water_pressure: 1.5".to_string();
         assert_eq!(testcase, want);
+        // settings are shown with their label
+        let mode = FieldValue::from_str("heating_circuit_1_mode: automatic", 0x2d3d_0574).unwrap();
+        assert_eq!(mode.to_string(), "heating_circuit_1_mode: Automatic");
+        assert_eq!(mode.to_json_value(), 1);
     }
 
     #[test]
@@ -226,6 +656,82 @@ mod tests {
         assert_eq!(testcase, want);
     }
 
+    #[test]
+    fn test_field_value_calibration() {
+        let field_id = 0x113d_051a;
+        let calibrations = CalibrationRegistry::new()
+            .with_calibration(field_id, Calibration::with_offset(-0.5).unwrap());
+        let options = DecodeOptions::default().with_calibrations(calibrations.clone());
+        let frame = Frame::new(66, 0, 7, field_id, vec![0, 5, 192]).unwrap();
+        let testcase = FieldValue::from_frame_with(&frame, &options).unwrap();
+        assert_eq!(testcase.value_str(), "22.5");
+        assert_eq!(
+            testcase
+                .encode_for_with(PacketType::Ret, options.calibrations())
+                .unwrap(),
+            frame.payload()
+        );
+        // without the calibrations the value is decoded unchanged
+        assert_eq!(FieldValue::from_frame(&frame).unwrap().value_str(), "23");
+        // a set point is written with the calibration reverted
+        let set_point = FieldValue::from_value_str("21.5", 0x2d3d_058e).unwrap();
+        let calibrations =
+            calibrations.with_calibration(0x2d3d_058e, Calibration::with_offset(0.5).unwrap());
+        let testcase = set_point.to_set_frame_with(0, 66, &calibrations).unwrap();
+        assert_eq!(testcase.payload(), [1, 5, 0x40]);
+    }
+
+    /// Create a `FieldValue` of a writable set point
+    fn create_test_set_point() -> FieldValue {
+        FieldValue::from_value_str("21.5", 0x2d3d_058e).unwrap()
+    }
+
+    #[test]
+    fn test_field_value_to_set_frame() {
+        let testcase = create_test_set_point().to_set_frame(0, 66).unwrap();
+        let want = Frame::new_set(0, 66, 0x2d3d_058e, vec![1, 5, 0x60]).unwrap();
+        assert_eq!(testcase, want);
+        // measurements cannot be written
+        assert_eq!(
+            create_test_field_value().to_set_frame(0, 66),
+            Err(BsbError::ReadOnlyField)
+        );
+        let too_hot = FieldValue::from_value_str("36", 0x2d3d_058e).unwrap();
+        assert_eq!(too_hot.to_set_frame(0, 66), Err(BsbError::OutOfRange));
+    }
+
+    #[test]
+    fn test_field_value_encode_for() {
+        let testcase = create_test_field_value();
+        assert_eq!(
+            testcase.encode_for(PacketType::Ret).unwrap(),
+            vec![0, 0, 15]
+        );
+        assert_eq!(
+            testcase.encode_for(PacketType::Set).unwrap(),
+            vec![1, 0, 15]
+        );
+    }
+
+    #[test]
+    fn test_field_value_dry_run_set() {
+        let (bytes, breakdown) = create_test_set_point().dry_run_set(0, 66).unwrap();
+        assert_eq!(
+            bytes,
+            create_test_set_point()
+                .to_set_frame(0, 66)
+                .unwrap()
+                .serialize()
+        );
+        let concatenated = breakdown
+            .iter()
+            .flat_map(|segment| segment.bytes().to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(concatenated, bytes);
+        assert_eq!(breakdown[6].name(), "payload");
+        assert_eq!(breakdown[6].bytes(), [1, 5, 0x60]);
+    }
+
     #[test]
     fn test_field_value_default_for_field() {
         let field = Field::by_id(87890416).unwrap();
@@ -237,6 +743,7 @@ mod tests {
                 value: 0.0,
                 factor: 10,
             },
+            raw: None,
         };
         assert_eq!(testcase, want);
     }
@@ -248,12 +755,41 @@ mod tests {
         assert_eq!(testcase, want);
     }
 
+    #[test]
+    fn test_field_value_to_named_value_in() {
+        let field_value = create_test_field_value();
+        // (<unit preference>, <value>)
+        let testcases = vec![
+            (UnitPreference::default(), "1.5"),
+            (UnitPreference::default().with_pressure(Unit::Psi), "21.76"),
+            (
+                UnitPreference::default().with_temperature(Unit::Fahrenheit),
+                "1.5",
+            ),
+        ];
+        for (preference, want) in testcases {
+            let testcase = field_value.to_named_value_in(&preference);
+            assert_eq!(
+                testcase,
+                NamedValue::new("water_pressure", want.to_string())
+            );
+        }
+        let datetime = FieldValue::from_value_str("2024-11-11T09:36:57", 0x0500_006c).unwrap();
+        let preference = UnitPreference::default().with_temperature(Unit::Fahrenheit);
+        assert_eq!(datetime.value_str_in(&preference), "2024-11-11T09:36:57");
+    }
+
     #[test]
     fn test_field_value_from_named_value() {
         let named_value = NamedValue::new("water_pressure", "1.5".to_string());
         let testcase = FieldValue::from_named_value(&named_value).unwrap();
         let want = create_test_field_value();
         assert_eq!(testcase, want);
+        // a labeled setting round-trips through its label
+        let want = FieldValue::from_value_str("1", 0x2d3d_0574).unwrap();
+        let named_value = want.to_named_value();
+        assert_eq!(named_value.value(), "Automatic");
+        assert_eq!(FieldValue::from_named_value(&named_value).unwrap(), want);
     }
 
     #[test]
@@ -261,12 +797,35 @@ mod tests {
         let named_value = NamedValue::new("invalid", "1.5".to_string());
         let testcase = FieldValue::from_named_value(&named_value).expect_err("not an error");
         assert_eq!(testcase, BsbError::UnsupportedField);
+        // all constructors attach the failed operation
+        let context = testcase.context().unwrap();
+        assert_eq!(context.operation(), Operation::Lookup);
+        assert_eq!(context.field_id(), None);
+    }
+
+    #[test]
+    fn test_field_value_trigger_errors() {
+        let testcase = FieldValue::trigger(0x053d_19f0).expect_err("not an error");
+        assert_eq!(testcase, BsbError::InvalidDatatype);
+        let testcase = FieldValue::trigger(0).expect_err("not an error");
+        assert_eq!(testcase, BsbError::UnsupportedField);
     }
 
     #[test]
     fn test_field_value_from_frame_invalid() {
-        let frame = Frame::new(66, 0, 7, 222103850, vec![0, 3]);
+        let frame = Frame::new(66, 0, 7, 0x0d3d_092a, vec![0, 3]).unwrap();
         let testcase = FieldValue::from_frame(&frame).expect_err("not an error");
         assert_eq!(testcase, BsbError::InvalidSetting);
+        let context = testcase.context().unwrap();
+        assert_eq!(context.field_id(), Some(0x0d3d_092a));
+        assert_eq!(context.payload(), Some(&[0, 3][..]));
+    }
+
+    #[test]
+    fn test_field_value_from_frame_unsupported() {
+        let frame = Frame::new(66, 0, 7, 0x0000_0001, vec![0, 3]).unwrap();
+        let testcase = FieldValue::from_frame(&frame).expect_err("not an error");
+        assert_eq!(testcase, BsbError::UnsupportedField);
+        assert_eq!(testcase.category(), ErrorCategory::Unsupported);
     }
 }
@@ -0,0 +1,113 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{BsbError, Value};
+
+/// Exact representation of a `Value::Float` as the raw integer of the payload and its factor.
+/// Unlike `f32` it can be compared, serialized and round-tripped without binary float artifacts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FixedPoint {
+    raw: i16,
+    factor: u8,
+}
+
+impl FixedPoint {
+    /// Create a new `FixedPoint` representing `raw / factor`
+    ///
+    /// # Errors
+    ///
+    /// Returns `BsbError::InvalidDatatype` if the `factor` is 0
+    pub fn new(raw: i16, factor: u8) -> Result<FixedPoint, BsbError> {
+        if factor == 0 {
+            return Err(BsbError::InvalidDatatype);
+        }
+        Ok(FixedPoint { raw, factor })
+    }
+
+    /// Access `FixedPoint.raw`
+    #[must_use]
+    pub fn raw(&self) -> i16 {
+        self.raw
+    }
+
+    /// Access `FixedPoint.factor`
+    #[must_use]
+    pub fn factor(&self) -> u8 {
+        self.factor
+    }
+
+    /// Numeric value, e.g. for display
+    #[must_use]
+    pub fn as_f64(&self) -> f64 {
+        f64::from(self.raw) / f64::from(self.factor)
+    }
+
+    /// Check if both represent the same number, even with different factors
+    #[must_use]
+    pub fn same_value(&self, other: &FixedPoint) -> bool {
+        i32::from(self.raw) * i32::from(other.factor)
+            == i32::from(other.raw) * i32::from(self.factor)
+    }
+
+    /// Convert into a `Value::Float` with `flag`. Encoding it yields exactly `raw` again
+    #[must_use]
+    pub fn to_value(&self, flag: u8) -> Value {
+        // raw and factor have less significant bits than an f32
+        #[allow(clippy::cast_possible_truncation)]
+        Value::Float {
+            flag,
+            value: self.as_f64() as f32,
+            factor: self.factor,
+        }
+    }
+}
+
+impl Display for FixedPoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_f64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BsbError, Datatype, Value};
+
+    use super::FixedPoint;
+
+    #[test]
+    fn test_fixed_point_from_value() {
+        let value = Value::decode(&[0, 0x05, 0x93], Datatype::Float(64)).unwrap();
+        let testcase = value.as_fixed_point().unwrap();
+        assert_eq!(testcase, FixedPoint::new(1427, 64).unwrap());
+        assert_eq!(testcase.to_string(), "22.296875");
+        assert_eq!(testcase.to_value(0), value);
+        assert_eq!(testcase.to_value(0).encode(), vec![0, 0x05, 0x93]);
+        let setting = Value::Setting {
+            flag: 0,
+            setting: 1,
+            max: 1,
+        };
+        assert_eq!(setting.as_fixed_point(), None);
+    }
+
+    #[test]
+    fn test_fixed_point_same_value() {
+        let half = FixedPoint::new(1, 2).unwrap();
+        assert!(half.same_value(&FixedPoint::new(5, 10).unwrap()));
+        assert!(!half.same_value(&FixedPoint::new(6, 10).unwrap()));
+        assert_ne!(half, FixedPoint::new(5, 10).unwrap());
+        assert_eq!(FixedPoint::new(1, 0), Err(BsbError::InvalidDatatype));
+    }
+
+    #[test]
+    fn test_fixed_point_roundtrip() {
+        for factor in [2, 10, 64, 255] {
+            for raw in [i16::MIN, -1427, -1, 0, 1, 1427, i16::MAX] {
+                let fixed_point = FixedPoint::new(raw, factor).unwrap();
+                let testcase = fixed_point.to_value(0).as_fixed_point().unwrap();
+                assert_eq!(testcase, fixed_point);
+            }
+        }
+    }
+}
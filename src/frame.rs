@@ -1,36 +1,75 @@
-use serde::Serialize;
-use strum::FromRepr;
+use std::fmt::Display;
 
-use crate::FieldValue;
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumString, FromRepr};
+
+use crate::{BsbError, Field, FieldValue, Quirks};
 use parser::{FrameParser, ParseResult};
 use serializer::FrameSerializer;
 
+pub(crate) mod borrowed;
 pub(crate) mod parser;
 pub(crate) mod serializer;
+pub(crate) mod stream;
 
 /// BSB `SOF` (start of frame) that is used to start each frame
 pub const SOF: u8 = 0xdc;
 
-/// `Frame` contains all information that will be put on and read from the bus
-#[derive(Clone, Debug, PartialEq, Serialize)]
+/// Maximum length of a serialized `Frame` that is accepted by the parser
+pub const MAX_FRAME_LENGTH: u8 = 69;
+
+/// Maximum length of the `payload` so that a `Frame` does not exceed `MAX_FRAME_LENGTH`
+pub const MAX_PAYLOAD_LENGTH: usize = MAX_FRAME_LENGTH as usize - 4 - 4 - 2 - 1;
+
+/// `Frame` contains all information that will be put on and read from the bus.
+/// The serde representation is stable, the `field_id` is a hex string with `0x` prefix and the
+/// `payload` a hex string without separators, e.g.
+/// `{"destination_address":66,"source_address":0,"packet_type":7,"field_id":"0x053d19f0","payload":"00000f"}`
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Frame {
     destination_address: u8,
     source_address: u8,
     packet_type: u8,
+    #[serde(with = "crate::hex::field_id")]
     field_id: u32,
+    #[serde(with = "crate::hex::payload")]
     payload: Vec<u8>,
 }
 
 impl Frame {
     /// Create a new Bsb `Frame`
-    #[must_use]
+    ///
+    /// # Errors
+    ///
+    /// Returns `BsbError::InvalidPayloadLength` if the `payload` is longer than `MAX_PAYLOAD_LENGTH`
     pub fn new(
         destination_address: u8,
         source_address: u8,
         packet_type: u8,
         field_id: u32,
         payload: Vec<u8>,
+    ) -> Result<Frame, BsbError> {
+        if payload.len() > MAX_PAYLOAD_LENGTH {
+            return Err(BsbError::InvalidPayloadLength);
+        }
+        Ok(Frame::new_unchecked(
+            destination_address,
+            source_address,
+            packet_type,
+            field_id,
+            payload,
+        ))
+    }
+
+    /// Create a new Bsb `Frame` from a `payload` that is known to fit, e.g. one that was parsed
+    pub(crate) fn new_unchecked(
+        destination_address: u8,
+        source_address: u8,
+        packet_type: u8,
+        field_id: u32,
+        payload: Vec<u8>,
     ) -> Frame {
+        debug_assert!(payload.len() <= MAX_PAYLOAD_LENGTH);
         Frame {
             destination_address,
             source_address,
@@ -40,10 +79,10 @@ impl Frame {
         }
     }
 
-    /// Create a new Bsb `Frame` for a `Get` type frame
+    /// Create a new Bsb `Frame` for a `Get` type frame, a `Get` never carries a payload
     #[must_use]
     pub fn new_get(destination_address: u8, source_address: u8, field_id: u32) -> Frame {
-        Frame::new(
+        Frame::new_unchecked(
             destination_address,
             source_address,
             PacketType::Get as u8,
@@ -53,32 +92,115 @@ impl Frame {
     }
 
     /// Create a new Bsb `Frame` for a `Set` type frame
-    #[must_use]
+    ///
+    /// # Errors
+    ///
+    /// Returns `BsbError::InvalidPayloadLength` if the `payload` is empty or longer than `MAX_PAYLOAD_LENGTH`
     pub fn new_set(
         destination_address: u8,
         source_address: u8,
         field_id: u32,
         payload: Vec<u8>,
-    ) -> Frame {
-        Frame::new(
+    ) -> Result<Frame, BsbError> {
+        // a `Set` always carries the value to write
+        if payload.is_empty() || payload.len() > MAX_PAYLOAD_LENGTH {
+            return Err(BsbError::InvalidPayloadLength);
+        }
+        Ok(Frame::new_unchecked(
             destination_address,
             source_address,
             PacketType::Set as u8,
             field_id,
             payload,
-        )
+        ))
+    }
+
+    /// Return the `Frame` with a changed `source_address`, e.g. to forward it to another bus segment.
+    /// The checksum is calculated on serialization and therefore always matches the new address
+    #[must_use]
+    pub fn with_source(mut self, source_address: u8) -> Frame {
+        self.source_address = source_address;
+        self
+    }
+
+    /// Return the `Frame` with a changed `destination_address`, e.g. to forward it to another bus segment.
+    /// The checksum is calculated on serialization and therefore always matches the new address
+    #[must_use]
+    pub fn with_destination(mut self, destination_address: u8) -> Frame {
+        self.destination_address = destination_address;
+        self
     }
 
     /// Parse the `input` slice into `Ok(remaining_bytes, Frame)`, `Incomplete` or `Error`
     #[must_use]
     pub fn parse(input: &[u8]) -> ParseResult<'_> {
-        FrameParser::parse(input)
+        FrameParser::parse(input, Quirks::empty())
+    }
+
+    /// Parse the `input` slice like `Frame::parse` honoring the device `quirks`
+    #[must_use]
+    pub fn parse_with(input: &[u8], quirks: Quirks) -> ParseResult<'_> {
+        FrameParser::parse(input, quirks)
+    }
+
+    /// Parse a `Frame` from its JSON representation, this is the reverse of `Frame::to_json`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the JSON is not a valid `Frame`, e.g. the payload is too long
+    pub fn from_json(json: &str) -> serde_json::Result<Frame> {
+        serde_json::from_str(json)
+    }
+
+    /// JSON representation of the `Frame` as documented on `Frame`
+    ///
+    /// # Panics
+    ///
+    /// Does not panic, a `Frame` can always be represented as JSON
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("a Frame can always be represented as JSON")
     }
 
     /// Serialize the `Frame` into a `Vec<u8>`
     #[must_use]
     pub fn serialize(&self) -> Vec<u8> {
-        FrameSerializer::serialize(self)
+        FrameSerializer::serialize(self, Quirks::empty())
+    }
+
+    /// Serialize the `Frame` like `Frame::serialize` honoring the device `quirks`
+    #[must_use]
+    pub fn serialize_with(&self, quirks: Quirks) -> Vec<u8> {
+        FrameSerializer::serialize(self, quirks)
+    }
+
+    /// Serialize the `Frame` into an existing `buffer`, e.g. a buffer from a `BufferPool`
+    pub fn serialize_into(&self, buffer: &mut Vec<u8>) {
+        FrameSerializer::serialize_into(self, buffer, Quirks::empty());
+    }
+
+    /// Serialize the `Frame` and split the bytes into named `FrameSegment`s, e.g. to
+    /// show exactly what would be sent on the bus
+    #[must_use]
+    pub fn breakdown(&self) -> Vec<FrameSegment> {
+        let bytes = self.serialize();
+        let payload_end = bytes.len() - 2;
+        [
+            ("sof", 0..1),
+            ("source_address", 1..2),
+            ("destination_address", 2..3),
+            ("length", 3..4),
+            ("packet_type", 4..5),
+            ("field_id", 5..9),
+            ("payload", 9..payload_end),
+            ("checksum", payload_end..bytes.len()),
+        ]
+        .into_iter()
+        .map(|(name, range)| FrameSegment {
+            name,
+            bytes: bytes[range].to_vec(),
+        })
+        .collect()
     }
 
     /// Access `Frame.destination_address`
@@ -111,34 +233,160 @@ impl Frame {
         &self.payload
     }
 
+    /// Access a mutable `payload` reference to change the payload bytes in place
+    #[must_use]
+    pub fn payload_mut(&mut self) -> &mut [u8] {
+        &mut self.payload
+    }
+
+    /// Replace the `payload`
+    ///
+    /// # Errors
+    ///
+    /// Returns `BsbError::InvalidPayloadLength` if the `payload` is longer than `MAX_PAYLOAD_LENGTH`
+    pub fn set_payload(&mut self, payload: Vec<u8>) -> Result<(), BsbError> {
+        if payload.len() > MAX_PAYLOAD_LENGTH {
+            return Err(BsbError::InvalidPayloadLength);
+        }
+        self.payload = payload;
+        Ok(())
+    }
+
+    /// Check if the `Frame` is about the `field`
+    #[must_use]
+    pub fn matches_field(&self, field: &Field) -> bool {
+        self.field_id == field.id()
+    }
+
+    /// Check if the `Frame` was sent from `address`
+    #[must_use]
+    pub fn is_from(&self, address: u8) -> bool {
+        self.source_address == address
+    }
+
+    /// Check if the `Frame` is sent to `address`
+    #[must_use]
+    pub fn is_to(&self, address: u8) -> bool {
+        self.destination_address == address
+    }
+
+    /// Check if the `Frame` has the `packet_type`
+    #[must_use]
+    pub fn is_packet_type(&self, packet_type: PacketType) -> bool {
+        self.packet_type == packet_type as u8
+    }
+
+    /// Check if the `Frame` answers the `request`: a `Ret` or `Error` for a `Get` and an `Ack`,
+    /// `Nack` or `Error` for a `Set` of the same field sent back to the requester
+    #[must_use]
+    pub fn is_response_to(&self, request: &Frame) -> bool {
+        let answers = match PacketType::from_repr(request.packet_type) {
+            Some(PacketType::Get) => &[PacketType::Ret, PacketType::Error][..],
+            Some(PacketType::Set) => &[PacketType::Ack, PacketType::Nack, PacketType::Error][..],
+            _ => &[],
+        };
+        self.field_id == request.field_id
+            && self.source_address == request.destination_address
+            && self.destination_address == request.source_address
+            && answers.iter().any(|&answer| self.is_packet_type(answer))
+    }
+
+    /// Consume the `Frame` and return the `payload`, e.g. to return it to a `BufferPool`
+    #[must_use]
+    pub fn into_payload(self) -> Vec<u8> {
+        self.payload
+    }
+
     /// Decode the `payload` if the field is known
+    #[must_use]
     pub fn try_decode(&self) -> Option<FieldValue> {
         FieldValue::from_frame(self).ok()
     }
 }
 
-/// `PacketType` of the `Frame`
+/// `FrameSegment` is a named part of a serialized `Frame`, e.g. the field id or the checksum
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameSegment {
+    name: &'static str,
+    bytes: Vec<u8>,
+}
+
+impl FrameSegment {
+    /// Access `FrameSegment.name`
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Access `FrameSegment.bytes`
+    #[must_use]
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl Display for FrameSegment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let bytes = self
+            .bytes
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        write!(f, "{}: [{bytes}]", self.name)
+    }
+}
+
+/// `PacketType` of the `Frame`.
+/// It is displayed and parsed by its name, e.g. "Get" or "Ret".
+/// The discriminants are the packet type bytes on the bus
 #[repr(u8)]
-#[derive(FromRepr)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromRepr, Display, EnumString)]
+#[non_exhaustive]
 pub enum PacketType {
-    Unknown0,
-    Unknown1,
-    Info,
-    Set,
-    Ack,
-    Nack,
-    Get,
-    Ret,
-    Error,
+    Unknown0 = 0,
+    Unknown1 = 1,
+    Info = 2,
+    Set = 3,
+    Ack = 4,
+    Nack = 5,
+    Get = 6,
+    Ret = 7,
+    Error = 8,
+}
+
+impl TryFrom<u8> for PacketType {
+    type Error = BsbError;
+
+    /// Convert the `packet_type` byte of a `Frame` into a `PacketType`
+    fn try_from(value: u8) -> Result<Self, BsbError> {
+        PacketType::from_repr(value).ok_or(BsbError::InvalidPacketType)
+    }
+}
+
+impl From<PacketType> for u8 {
+    /// Convert a `PacketType` into the `packet_type` byte of a `Frame`
+    fn from(packet_type: PacketType) -> Self {
+        packet_type as u8
+    }
+}
+
+/// Swap the first two bytes of the `field_id` as it is done on the bus for e.g. `Set` and `Get`
+pub(crate) fn swap_field_id(field_id: u32) -> u32 {
+    (field_id & 0x0000_ffff) | ((field_id >> 8) & 0x00ff_0000) | ((field_id << 8) & 0xff00_0000)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{parser::ParseResult, Frame};
+    use std::{collections::HashSet, str::FromStr as _};
+
+    use crate::{BsbError, Field, Quirks};
+
+    use super::{parser::ParseResult, Frame, PacketType, MAX_PAYLOAD_LENGTH};
 
     /// Create a test frame for all tests
     fn create_frame() -> Frame {
-        Frame::new(1, 2, 3, 4, [5].to_vec())
+        Frame::new(1, 2, 3, 4, [5].to_vec()).unwrap()
     }
 
     /// Create a serialized version of a frame for all tests
@@ -184,10 +432,214 @@ mod tests {
         assert_eq!(create_frame().payload(), [5]);
     }
 
+    #[test]
+    fn test_with_source_destination() {
+        let testcase = create_frame().with_source(10).with_destination(0);
+        assert_eq!(testcase.source_address(), 10);
+        assert_eq!(testcase.destination_address(), 0);
+        assert_eq!(testcase.payload(), create_frame().payload());
+        // the checksum of the rewritten frame is valid
+        let ParseResult::Ok { frame, .. } = Frame::parse(&testcase.serialize()) else {
+            panic!("not a frame")
+        };
+        assert_eq!(frame, testcase);
+    }
+
+    #[test]
+    fn test_parse_serialize_with_quirks() {
+        let frame = Frame::new(66, 0, PacketType::Ret as u8, 0x053d_19f0, vec![0, 0, 15]).unwrap();
+        let testcase = frame.serialize_with(Quirks::SWAPPED_FIELD_ID_ON_RET);
+        assert_eq!(testcase[5..9], [0x3d, 0x05, 0x19, 0xf0]);
+        let ParseResult::Ok { frame: parsed, .. } =
+            Frame::parse_with(&testcase, Quirks::SWAPPED_FIELD_ID_ON_RET)
+        else {
+            panic!("not a frame")
+        };
+        assert_eq!(parsed, frame);
+        // without the quirk the field id stays swapped
+        let ParseResult::Ok { frame: parsed, .. } = Frame::parse(&testcase) else {
+            panic!("not a frame")
+        };
+        assert_eq!(parsed.field_id(), 0x3d05_19f0);
+    }
+
+    #[test]
+    fn test_is_response_to() {
+        let get = Frame::new_get(0, 66, 0x053d_19f0);
+        let set = Frame::new_set(0, 66, 0x053d_19f0, vec![1, 0, 15]).unwrap();
+        let ret = Frame::new(66, 0, PacketType::Ret as u8, 0x053d_19f0, vec![0, 0, 15]).unwrap();
+        let ack = Frame::new(66, 0, PacketType::Ack as u8, 0x053d_19f0, vec![]).unwrap();
+        assert!(ret.is_response_to(&get));
+        assert!(!ret.is_response_to(&set));
+        assert!(ack.is_response_to(&set));
+        assert!(!ack.is_response_to(&get));
+        // another field or requester
+        assert!(!ret.clone().with_destination(10).is_response_to(&get));
+        assert!(
+            !Frame::new(66, 0, PacketType::Ret as u8, 0x053d_0236, vec![0, 1])
+                .unwrap()
+                .is_response_to(&get)
+        );
+        // a response is not a request
+        assert!(!get.is_response_to(&ret));
+    }
+
+    #[test]
+    fn test_breakdown() {
+        let testcase = Frame::new_get(0, 66, 0x053d_19f0)
+            .breakdown()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        let want = vec![
+            "sof: [dc]",
+            "source_address: [c2]",
+            "destination_address: [00]",
+            "length: [0b]",
+            "packet_type: [06]",
+            "field_id: [3d 05 19 f0]",
+            "payload: []",
+            "checksum: [24 3e]",
+        ];
+        assert_eq!(testcase, want);
+    }
+
+    #[test]
+    fn test_new_payload_length() {
+        assert!(Frame::new(0, 66, 7, 0x053d_0236, vec![0; MAX_PAYLOAD_LENGTH]).is_ok());
+        let testcase = Frame::new(0, 66, 7, 0x053d_0236, vec![0; 300]);
+        assert_eq!(testcase, Err(BsbError::InvalidPayloadLength));
+    }
+
+    #[test]
+    fn test_new_set_payload_length() {
+        assert!(Frame::new_set(0, 66, 0x053d_0236, vec![1, 0]).is_ok());
+        // (<payload length>, <error>)
+        let testcases = vec![
+            (0, BsbError::InvalidPayloadLength),
+            (MAX_PAYLOAD_LENGTH + 1, BsbError::InvalidPayloadLength),
+        ];
+        for (length, error) in testcases {
+            let testcase = Frame::new_set(0, 66, 0x053d_0236, vec![0; length]);
+            assert_eq!(testcase, Err(error));
+        }
+    }
+
+    #[test]
+    fn test_payload_mut() {
+        let mut testcase = create_frame();
+        testcase.payload_mut()[0] = 6;
+        assert_eq!(testcase.payload(), [6]);
+    }
+
+    #[test]
+    fn test_set_payload() {
+        let mut testcase = create_frame();
+        testcase.set_payload(vec![0, 0, 15]).unwrap();
+        assert_eq!(testcase.payload(), [0, 0, 15]);
+        let error = testcase
+            .set_payload(vec![0; MAX_PAYLOAD_LENGTH + 1])
+            .expect_err("not an error");
+        assert_eq!(error, BsbError::InvalidPayloadLength);
+        assert_eq!(testcase.payload(), [0, 0, 15]);
+        testcase.set_payload(vec![0; MAX_PAYLOAD_LENGTH]).unwrap();
+        let ParseResult::Ok { frame, .. } = Frame::parse(&testcase.serialize()) else {
+            panic!("not a frame")
+        };
+        assert_eq!(frame, testcase);
+    }
+
+    #[test]
+    fn test_matches_field() {
+        let frame = Frame::new(66, 0, 7, 0x053d_19f0, vec![0, 0, 15]).unwrap();
+        assert!(frame.matches_field(Field::by_name("water_pressure").unwrap()));
+        assert!(!frame.matches_field(Field::by_name("outside_temperature").unwrap()));
+    }
+
+    #[test]
+    fn test_is_from_to() {
+        let frame = create_frame();
+        assert!(frame.is_from(2));
+        assert!(!frame.is_from(1));
+        assert!(frame.is_to(1));
+        assert!(!frame.is_to(2));
+    }
+
+    #[test]
+    fn test_is_packet_type() {
+        let frame = create_frame();
+        assert!(frame.is_packet_type(PacketType::Set));
+        assert!(!frame.is_packet_type(PacketType::Get));
+    }
+
+    #[test]
+    fn test_frame_hash() {
+        let mut set = HashSet::new();
+        set.insert(create_frame());
+        set.insert(create_frame());
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn test_serialize_into() {
+        let mut buffer = Vec::new();
+        create_frame().serialize_into(&mut buffer);
+        assert_eq!(buffer, create_serialized());
+    }
+
+    #[test]
+    fn test_frame_json() {
+        let frame = Frame::new(66, 0, PacketType::Ret.into(), 0x053d_19f0, vec![0, 0, 15]).unwrap();
+        let want = r#"{"destination_address":66,"source_address":0,"packet_type":7,"field_id":"0x053d19f0","payload":"00000f"}"#;
+        assert_eq!(frame.to_json(), want);
+        assert_eq!(Frame::from_json(want).unwrap(), frame);
+        // (<json>, <valid>)
+        let testcases = vec![
+            (want.replace("0x053d19f0", "0x053D19F0"), true),
+            (want.replace("0x053d19f0", "053d19f0"), false),
+            (want.replace("00000f", "00000"), false),
+            (
+                want.replace("00000f", &"00".repeat(MAX_PAYLOAD_LENGTH)),
+                true,
+            ),
+            (
+                want.replace("00000f", &"00".repeat(MAX_PAYLOAD_LENGTH + 1)),
+                false,
+            ),
+        ];
+        for (json, valid) in testcases {
+            assert_eq!(Frame::from_json(&json).is_ok(), valid, "{json}");
+        }
+    }
+
+    #[test]
+    fn test_into_payload() {
+        assert_eq!(create_frame().into_payload(), vec![5]);
+    }
+
     #[test]
     fn test_decode() {
-        let frame = Frame::new(66, 0, 7, 87890416, vec![0, 0, 15]);
+        let frame = Frame::new(66, 0, 7, 87890416, vec![0, 0, 15]).unwrap();
         let testcase = frame.try_decode().unwrap();
         assert_eq!(testcase.value_str(), "1.5");
     }
+
+    #[test]
+    fn test_packet_type_to_string() {
+        assert_eq!(PacketType::Get.to_string(), "Get");
+        assert_eq!(PacketType::Ret.to_string(), "Ret");
+    }
+
+    #[test]
+    fn test_packet_type_from_str() {
+        assert_eq!(PacketType::from_str("Set").unwrap(), PacketType::Set);
+        assert!(PacketType::from_str("invalid").is_err());
+    }
+
+    #[test]
+    fn test_packet_type_try_from() {
+        assert_eq!(PacketType::try_from(7), Ok(PacketType::Ret));
+        assert_eq!(PacketType::try_from(9), Err(BsbError::InvalidPacketType));
+        assert_eq!(u8::from(PacketType::Ret), 7);
+    }
 }
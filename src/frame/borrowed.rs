@@ -0,0 +1,151 @@
+use serde::Serialize;
+
+use crate::{Frame, PacketType, Quirks};
+
+use super::parser::{FrameParser, ParseResult};
+
+/// `FrameRef` is a `Frame` that borrows its `payload` from the parsed input buffer instead of
+/// allocating it, e.g. to sniff the bus continuously on a small gateway.
+/// It is serialized in the same representation as `Frame`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+pub struct FrameRef<'a> {
+    destination_address: u8,
+    source_address: u8,
+    packet_type: u8,
+    #[serde(serialize_with = "crate::hex::field_id::serialize")]
+    field_id: u32,
+    #[serde(serialize_with = "crate::hex::payload::serialize")]
+    payload: &'a [u8],
+}
+
+impl<'a> FrameRef<'a> {
+    /// Create a new `FrameRef`
+    #[must_use]
+    pub fn new(
+        destination_address: u8,
+        source_address: u8,
+        packet_type: u8,
+        field_id: u32,
+        payload: &'a [u8],
+    ) -> FrameRef<'a> {
+        FrameRef {
+            destination_address,
+            source_address,
+            packet_type,
+            field_id,
+            payload,
+        }
+    }
+
+    /// Parse the `input` slice like `Frame::parse` without copying the payload
+    #[must_use]
+    pub fn parse(input: &'a [u8]) -> ParseResult<'a, FrameRef<'a>> {
+        FrameParser::parse_ref(input, Quirks::empty())
+    }
+
+    /// Parse the `input` slice like `FrameRef::parse` honoring the device `quirks`
+    #[must_use]
+    pub fn parse_with(input: &'a [u8], quirks: Quirks) -> ParseResult<'a, FrameRef<'a>> {
+        FrameParser::parse_ref(input, quirks)
+    }
+
+    /// Access `FrameRef.destination_address`
+    #[must_use]
+    pub fn destination_address(&self) -> u8 {
+        self.destination_address
+    }
+
+    /// Access `FrameRef.source_address`
+    #[must_use]
+    pub fn source_address(&self) -> u8 {
+        self.source_address
+    }
+
+    /// Access `FrameRef.packet_type`
+    #[must_use]
+    pub fn packet_type(&self) -> u8 {
+        self.packet_type
+    }
+
+    /// Access `FrameRef.field_id`
+    #[must_use]
+    pub fn field_id(&self) -> u32 {
+        self.field_id
+    }
+
+    /// Access `FrameRef.payload`
+    #[must_use]
+    pub fn payload(&self) -> &'a [u8] {
+        self.payload
+    }
+
+    /// Check if the `FrameRef` has the `packet_type`
+    #[must_use]
+    pub fn is_packet_type(&self, packet_type: PacketType) -> bool {
+        self.packet_type == packet_type as u8
+    }
+
+    /// Copy the payload into an owned `Frame`
+    #[must_use]
+    pub fn to_owned(&self) -> Frame {
+        // the parser limits the payload to `MAX_PAYLOAD_LENGTH`
+        Frame::new_unchecked(
+            self.destination_address,
+            self.source_address,
+            self.packet_type,
+            self.field_id,
+            self.payload.to_vec(),
+        )
+    }
+}
+
+impl From<FrameRef<'_>> for Frame {
+    fn from(frame: FrameRef<'_>) -> Self {
+        frame.to_owned()
+    }
+}
+
+impl<'a> From<&'a Frame> for FrameRef<'a> {
+    fn from(frame: &'a Frame) -> Self {
+        FrameRef::new(
+            frame.destination_address(),
+            frame.source_address(),
+            frame.packet_type(),
+            frame.field_id(),
+            frame.payload(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Frame, PacketType, ParseErrorKind, ParseResult};
+
+    use super::FrameRef;
+
+    #[test]
+    fn test_frame_ref_parse() {
+        let want = Frame::new(66, 0, PacketType::Ret.into(), 0x053d_19f0, vec![0, 0, 15]).unwrap();
+        let raw = [want.serialize(), vec![0xdc]].concat();
+        let ParseResult::Ok { rest, frame } = FrameRef::parse(&raw) else {
+            panic!("frame not parsed");
+        };
+        assert_eq!(rest, [0xdc]);
+        // the payload points into the input buffer
+        assert!(raw.as_ptr_range().contains(&frame.payload().as_ptr()));
+        assert!(frame.is_packet_type(PacketType::Ret));
+        assert_eq!(frame.to_owned(), want);
+        assert_eq!(FrameRef::from(&want), frame);
+        assert_eq!(serde_json::to_string(&frame).unwrap(), want.to_json());
+        assert!(matches!(FrameRef::parse(rest), ParseResult::Incomplete));
+        let mut broken = want.serialize();
+        broken[13] ^= 0xff;
+        assert!(matches!(
+            FrameRef::parse(&broken),
+            ParseResult::Failure {
+                error: ParseErrorKind::ChecksumError,
+                ..
+            }
+        ));
+    }
+}
@@ -7,11 +7,18 @@ use nom_language::error::{VerboseError, VerboseErrorKind};
 use strum::{EnumString, IntoStaticStr};
 use thiserror::Error;
 
-use crate::frame::SOF;
+use crate::frame::{MAX_FRAME_LENGTH, SOF};
 
-use super::{Frame, PacketType};
+use crate::Quirks;
 
-#[derive(Debug, Error, EnumString, IntoStaticStr)]
+use super::{borrowed::FrameRef, swap_field_id, Frame};
+
+/// Length of a frame without payload: SOF, addresses, length, packet type, field id and CRC
+const MIN_FRAME_LENGTH: u8 = 4 + 1 + 4 + 2;
+
+/// Kind of a `Frame` parse failure. The variant names are used as nom context strings
+#[derive(Debug, Clone, Copy, PartialEq, Error, EnumString, IntoStaticStr)]
+#[non_exhaustive]
 pub enum ParseErrorKind {
     #[error("checksum error")]
     ChecksumError,
@@ -19,12 +26,27 @@ pub enum ParseErrorKind {
     InvalidLength,
 }
 
-pub enum ParseResult<'a> {
+impl ParseErrorKind {
+    /// A stable numeric code, identical to `BsbError::code` of `BsbError::Parse`
+    #[must_use]
+    pub fn code(&self) -> u16 {
+        match self {
+            ParseErrorKind::ChecksumError => 13,
+            ParseErrorKind::InvalidLength => 14,
+        }
+    }
+}
+
+/// Result of parsing a `Frame` or with `FrameRef::parse` a borrowed `FrameRef`
+pub enum ParseResult<'a, F = Frame> {
     /// Successfully parsed frame and unparsed rest
-    Ok { rest: &'a [u8], frame: Frame },
+    Ok { rest: &'a [u8], frame: F },
     /// Not enough data, please provide more bytes
     Incomplete,
-    /// Unrecoverable Error, broken data and unparsed rest
+    /// Unrecoverable Error, the skipped `broken_data` and the unparsed `rest`.
+    /// `rest` starts at the next SOF after the SOF of the broken frame (or is empty), so a
+    /// streaming consumer can always continue parsing with `rest` and never hits the same
+    /// broken frame again. `broken_data` and `rest` together are the complete input
     Failure {
         rest: &'a [u8],
         broken_data: &'a [u8],
@@ -38,36 +60,72 @@ pub struct FrameParser {}
 
 impl FrameParser {
     /// Parse the `input` slice into `Ok(remaining_bytes, Frame)`, `Incomplete` or `Error`
+    /// honoring the device `quirks`
     #[must_use]
-    pub fn parse(input: &[u8]) -> ParseResult<'_> {
-        match Self::frame_parser(input) {
+    pub fn parse(input: &[u8], quirks: Quirks) -> ParseResult<'_> {
+        match Self::parse_ref(input, quirks) {
+            ParseResult::Ok { rest, frame } => ParseResult::Ok {
+                rest,
+                frame: frame.to_owned(),
+            },
+            ParseResult::Incomplete => ParseResult::Incomplete,
+            ParseResult::Failure {
+                rest,
+                broken_data,
+                error,
+            } => ParseResult::Failure {
+                rest,
+                broken_data,
+                error,
+            },
+        }
+    }
+
+    /// Parse the `input` slice like `FrameParser::parse` into a `FrameRef` that borrows the payload
+    #[must_use]
+    pub fn parse_ref(input: &[u8], quirks: Quirks) -> ParseResult<'_, FrameRef<'_>> {
+        match Self::frame_ref_parser(input, quirks) {
             Ok((rest, frame)) => ParseResult::Ok { rest, frame },
             Err(error) => match error {
                 nom::Err::Incomplete(_n) => ParseResult::Incomplete,
                 // treat recoverable errors and failures the same
                 nom::Err::Error(error) | nom::Err::Failure(error) => {
-                    let (rest, error) = error.errors.last().unwrap();
-                    let error = match error {
-                        // unfortunately errors can only be reported with context strings... but this code is backed with enums
-                        VerboseErrorKind::Context(context) => {
-                            ParseErrorKind::try_from(*context).unwrap()
-                        }
-                        // the next two parsers cannot happen due to parser construction
-                        VerboseErrorKind::Char(_) | VerboseErrorKind::Nom(_) => unimplemented!(),
-                    };
+                    let rest = resync(input);
                     ParseResult::Failure {
                         rest,
-                        broken_data: input,
-                        error,
+                        broken_data: &input[..input.len() - rest.len()],
+                        error: Self::failure_kind(&error),
                     }
                 }
             },
         }
     }
 
+    /// Find the `ParseErrorKind` of a nom `error`.
+    /// All failures of the frame parser carry a context, anything else is reported as
+    /// `InvalidLength` because the bytes do not form a frame
+    fn failure_kind(error: &VerboseError<&[u8]>) -> ParseErrorKind {
+        error
+            .errors
+            .iter()
+            .rev()
+            .find_map(|(_, kind)| match kind {
+                // unfortunately errors can only be reported with context strings... but this code is backed with enums
+                VerboseErrorKind::Context(context) => ParseErrorKind::try_from(*context).ok(),
+                VerboseErrorKind::Char(_) | VerboseErrorKind::Nom(_) => None,
+            })
+            .unwrap_or(ParseErrorKind::InvalidLength)
+    }
+
     /// Parse a bsb frame with this nom based parser and throw away any garbage at the beginning.
     /// Returns the remaining/unparsed bytes and the `Frame` if successfull or a `VerboseError`
-    fn frame_parser(data: &[u8]) -> NomParseResult<&[u8], Frame> {
+    #[cfg(test)]
+    fn frame_parser(data: &[u8], quirks: Quirks) -> NomParseResult<&[u8], Frame> {
+        Self::frame_ref_parser(data, quirks).map(|(rest, frame)| (rest, frame.to_owned()))
+    }
+
+    /// Parse a bsb frame like `FrameParser::frame_parser` but borrow the payload from `data`
+    fn frame_ref_parser(data: &[u8], quirks: Quirks) -> NomParseResult<&[u8], FrameRef<'_>> {
         // Find the message beginning with the SYNCBYTE and drop bytes until this SOF
         let (message, _) = take_till(|b| b == SOF)(data)?;
         let (input, _) = tag(&[SOF][..]).parse(message)?;
@@ -75,27 +133,26 @@ impl FrameParser {
         let (input, destination_address) = u8(input)?;
         let (input, header_length) = context(
             ParseErrorKind::InvalidLength.into(),
-            // At least 11 (required for minimum message) but max 70 (arbitrary max length)
+            // At least 11 (required for minimum message) but max 69 (arbitrary max length)
             verify(u8, |&header_length| {
-                (4 + 4 + 2 + 1..70).contains(&header_length)
+                (MIN_FRAME_LENGTH..=MAX_FRAME_LENGTH).contains(&header_length)
             }),
         )
         .parse(input)?;
-        let payload_len = header_length - 4 - 4 - 2 - 1; // -4 header -4 field id -2 CRC -1 SOF byte
+        // the length is verified, saturate anyway so hostile input can never underflow
+        let payload_len = header_length.saturating_sub(MIN_FRAME_LENGTH);
         let (input, packet_type) = u8(input)?;
         let (input, field_id) = map(be_u32, |field_id| {
-            if [PacketType::Set as u8, PacketType::Get as u8].contains(&packet_type) {
+            if quirks.swaps_field_id(packet_type) {
                 // For Set and Get the first two field_id bytes are reversed
-                (field_id & 0x0000_ffff)
-                    | ((field_id >> 8) & 0x00ff_0000)
-                    | ((field_id << 8) & 0xff00_0000)
+                swap_field_id(field_id)
             } else {
                 field_id
             }
         })
         .parse(input)?;
         let (input, payload) = take(payload_len)(input)?;
-        let (_, message_without_checksum) = take(header_length - 2)(message)?;
+        let (_, message_without_checksum) = take(header_length.saturating_sub(2))(message)?;
         let calculated_crc = crc16::State::<crc16::XMODEM>::calculate(message_without_checksum);
         let (input, _crc) = context(
             ParseErrorKind::ChecksumError.into(),
@@ -105,30 +162,49 @@ impl FrameParser {
 
         Ok((
             input,
-            Frame::new(
+            FrameRef::new(
                 destination_address,
                 source_address,
                 packet_type,
                 field_id,
-                payload.to_vec(),
+                payload,
             ),
         ))
     }
 }
 
+/// Skip the garbage and the SOF of a broken frame at the beginning of `input` and return the
+/// bytes from the next SOF on, or an empty slice if there is no further SOF
+#[must_use]
+pub fn resync(input: &[u8]) -> &[u8] {
+    let next = input
+        .iter()
+        .position(|&byte| byte == SOF)
+        .and_then(|start| {
+            input[start + 1..]
+                .iter()
+                .position(|&byte| byte == SOF)
+                .map(|next| start + 1 + next)
+        })
+        .unwrap_or(input.len());
+    &input[next..]
+}
+
 #[cfg(test)]
 mod tests {
     use nom_language::error::VerboseErrorKind;
 
     use crate::frame::parser::ParseResult;
 
-    use super::{Frame, FrameParser};
+    use crate::Quirks;
+
+    use super::{resync, Frame, FrameParser, ParseErrorKind};
 
     #[test]
     fn test_parse_get_message() {
         let data = &[220, 194, 0, 11, 6, 61, 5, 25, 240, 36, 62];
-        let want = Frame::new(0, 66, 6, 87890416, vec![]);
-        let (rest, broetje) = FrameParser::frame_parser(data).unwrap();
+        let want = Frame::new(0, 66, 6, 87890416, vec![]).unwrap();
+        let (rest, broetje) = FrameParser::frame_parser(data, Quirks::empty()).unwrap();
         assert_eq!(want, broetje);
         assert!(rest.is_empty());
     }
@@ -136,8 +212,8 @@ mod tests {
     #[test]
     fn test_parse_ret_message() {
         let data = &[220, 128, 66, 14, 7, 5, 61, 25, 240, 0, 0, 15, 29, 116];
-        let want = Frame::new(66, 0, 7, 87890416, vec![0, 0, 15]);
-        let (rest, broetje) = FrameParser::frame_parser(data).unwrap();
+        let want = Frame::new(66, 0, 7, 87890416, vec![0, 0, 15]).unwrap();
+        let (rest, broetje) = FrameParser::frame_parser(data, Quirks::empty()).unwrap();
         assert_eq!(want, broetje);
         assert!(rest.is_empty());
     }
@@ -145,7 +221,7 @@ mod tests {
     #[test]
     fn test_parse_two_correct_frames() {
         let test_data: &[u8; 11] = &[220, 194, 0, 11, 6, 61, 5, 25, 240, 36, 62];
-        let test_frame = Frame::new(0, 66, 6, 87890416, vec![]);
+        let test_frame = Frame::new(0, 66, 6, 87890416, vec![]).unwrap();
         let testcase = vec![test_data.to_vec(), test_data.to_vec()]
             .into_iter()
             .flatten()
@@ -166,7 +242,7 @@ mod tests {
     #[test]
     fn test_parser_frame_too_short_minimum() {
         let data = &[220, 1, 2, 11, 4, 5, 6, 7, 8, 9];
-        let error = FrameParser::frame_parser(data).expect_err("not an error");
+        let error = FrameParser::frame_parser(data, Quirks::empty()).expect_err("not an error");
         assert_eq!(
             error,
             nom::Err::Incomplete(nom::Needed::Size(std::num::NonZeroUsize::new(1).unwrap()))
@@ -176,7 +252,7 @@ mod tests {
     #[test]
     fn test_parse_frame_too_short() {
         let data = &[220, 0, 0, 15, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
-        let error = FrameParser::frame_parser(data).expect_err("not an error");
+        let error = FrameParser::frame_parser(data, Quirks::empty()).expect_err("not an error");
         assert_eq!(
             error,
             nom::Err::Incomplete(nom::Needed::Size(std::num::NonZeroUsize::new(1).unwrap()))
@@ -186,7 +262,8 @@ mod tests {
     #[test]
     fn test_parse_header_length_invalid_low() {
         let data = &[220, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
-        let nom::Err::Error(result) = FrameParser::frame_parser(data).expect_err("not an error")
+        let nom::Err::Error(result) =
+            FrameParser::frame_parser(data, Quirks::empty()).expect_err("not an error")
         else {
             panic!()
         };
@@ -199,7 +276,8 @@ mod tests {
     #[test]
     fn test_parse_header_length_invalid_high() {
         let data = &[220, 0, 0, 70, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
-        let nom::Err::Error(result) = FrameParser::frame_parser(data).expect_err("not an error")
+        let nom::Err::Error(result) =
+            FrameParser::frame_parser(data, Quirks::empty()).expect_err("not an error")
         else {
             panic!()
         };
@@ -212,7 +290,7 @@ mod tests {
     #[test]
     fn test_parse_no_sof() {
         let data = &[0xBB, 0, 0, 11, 0, 0, 0, 0, 0, 0, 0];
-        let error = FrameParser::frame_parser(data).expect_err("not an error");
+        let error = FrameParser::frame_parser(data, Quirks::empty()).expect_err("not an error");
         assert_eq!(
             error,
             nom::Err::Incomplete(nom::Needed::Size(std::num::NonZeroUsize::new(1).unwrap()))
@@ -222,8 +300,8 @@ mod tests {
     #[test]
     fn test_parse_leading_garbage_then_ok() {
         let data = &[0, 1, 2, 3, 220, 194, 0, 11, 6, 61, 5, 25, 240, 36, 62];
-        let want = Frame::new(0, 66, 6, 87890416, vec![]);
-        let (rest, broetje) = FrameParser::frame_parser(data).unwrap();
+        let want = Frame::new(0, 66, 6, 87890416, vec![]).unwrap();
+        let (rest, broetje) = FrameParser::frame_parser(data, Quirks::empty()).unwrap();
         assert_eq!(want, broetje);
         assert!(rest.is_empty());
     }
@@ -231,7 +309,8 @@ mod tests {
     #[test]
     fn test_parse_frame_crc_error() {
         let data = &[220, 0, 0, 14, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
-        let nom::Err::Error(result) = FrameParser::frame_parser(data).expect_err("not an error")
+        let nom::Err::Error(result) =
+            FrameParser::frame_parser(data, Quirks::empty()).expect_err("not an error")
         else {
             panic!()
         };
@@ -240,4 +319,67 @@ mod tests {
             VerboseErrorKind::Context("ChecksumError")
         );
     }
+
+    #[test]
+    fn test_parse_never_panics() {
+        // every length byte with every truncation of the frame, with and without quirks
+        for length in 0..=u8::MAX {
+            let mut data = vec![220, 0x80, 66, length, 3, 5, 61, 25, 240];
+            data.resize(usize::from(length) + 4, 0xff);
+            for end in 0..=data.len() {
+                for quirks in [Quirks::empty(), Quirks::all()] {
+                    let _ = FrameParser::parse(&data[..end], quirks);
+                    let _ = FrameParser::parse_ref(&data[..end], quirks);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_failure_rest() {
+        let frame = &[220, 194, 0, 11, 6, 61, 5, 25, 240, 36, 62];
+        // (<data>, <error>, <broken data length>)
+        let testcases = vec![
+            (
+                [&[1, 220, 0, 0, 70][..], frame].concat(),
+                ParseErrorKind::InvalidLength,
+                5,
+            ),
+            // the broken frame contains the SOF of the next frame in its payload
+            (
+                [&[220, 0, 0, 14, 0, 0, 0, 0, 0][..], frame].concat(),
+                ParseErrorKind::ChecksumError,
+                9,
+            ),
+        ];
+        for (data, want, length) in testcases {
+            let ParseResult::Failure {
+                rest,
+                broken_data,
+                error,
+            } = Frame::parse(&data)
+            else {
+                panic!("not a failure")
+            };
+            assert_eq!(error, want);
+            assert_eq!(broken_data, &data[..length]);
+            // parsing continues at the next SOF
+            assert_eq!(rest, frame);
+        }
+    }
+
+    #[test]
+    fn test_resync() {
+        // (<input>, <rest>)
+        let testcases: Vec<(&[u8], &[u8])> = vec![
+            (&[1, 220, 2, 220, 3], &[220, 3]),
+            (&[220, 220], &[220]),
+            (&[220, 1, 2], &[]),
+            (&[1, 2], &[]),
+            (&[], &[]),
+        ];
+        for (input, want) in testcases {
+            assert_eq!(resync(input), want);
+        }
+    }
 }
@@ -5,17 +5,28 @@ use cookie_factory::{
     sequence::tuple,
 };
 
-use super::{Frame, SOF};
+use crate::Quirks;
+
+use super::{swap_field_id, Frame, SOF};
 
 pub struct FrameSerializer {}
 
 impl FrameSerializer {
-    /// Serialize the `Frame` into a `Vec<u8>`
+    /// Serialize the `Frame` into a `Vec<u8>` honoring the device `quirks`
     #[must_use]
-    pub fn serialize(frame: &Frame) -> Vec<u8> {
+    pub fn serialize(frame: &Frame, quirks: Quirks) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        Self::serialize_into(frame, &mut buffer, quirks);
+        buffer
+    }
+
+    /// Serialize the `Frame` into an existing `buffer` that is cleared before.
+    /// This allows to reuse the allocation of the `buffer`
+    pub fn serialize_into(frame: &Frame, buffer: &mut Vec<u8>, quirks: Quirks) {
         let header_length = frame.payload.len() + 4 + 4 + 2 + 1;
         // prepare buffer with correct length
-        let mut buffer = vec![0; header_length];
+        buffer.clear();
+        buffer.resize(header_length, 0);
         // generate the message without checksum
         let (_, pos) = gen(
             tuple((
@@ -24,15 +35,13 @@ impl FrameSerializer {
                 be_u8(frame.destination_address),
                 be_u8(header_length.try_into().unwrap()),
                 be_u8(frame.packet_type),
-                be_u32(if frame.packet_type == 3 || frame.packet_type == 6 {
+                be_u32(if quirks.swaps_field_id(frame.packet_type) {
                     // for sets (3) and gets (6) these id bytes are swapped
-                    (frame.field_id & 0x0000_ffff)
-                        | ((frame.field_id >> 8) & 0x00ff_0000)
-                        | ((frame.field_id << 8) & 0xff00_0000)
+                    swap_field_id(frame.field_id)
                 } else {
                     frame.field_id
                 }),
-                slice(frame.payload.clone()),
+                slice(&frame.payload),
             )),
             buffer.as_mut_slice(),
         )
@@ -42,19 +51,19 @@ impl FrameSerializer {
         let crc = crc16::State::<crc16::XMODEM>::calculate(&buffer[0..pos]);
         // and append it
         let (_, _) = gen(be_u16(crc), &mut buffer[pos..]).unwrap();
-
-        buffer
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::Quirks;
+
     use super::{Frame, FrameSerializer};
 
     #[test]
     fn test_frame_serialize() {
-        let frame = Frame::new(66, 0, 7, 87890416, vec![0, 0, 15]);
-        let testcase = FrameSerializer::serialize(&frame);
+        let frame = Frame::new(66, 0, 7, 87890416, vec![0, 0, 15]).unwrap();
+        let testcase = FrameSerializer::serialize(&frame, Quirks::empty());
         let want = vec![220, 128, 66, 14, 7, 5, 61, 25, 240, 0, 0, 15, 29, 116];
         assert_eq!(want, testcase);
     }
@@ -62,16 +71,25 @@ mod tests {
     #[test]
     fn test_frame_serialize_get_request() {
         let frame = Frame::new_get(0, 66, 87890416);
-        let testcase = FrameSerializer::serialize(&frame);
+        let testcase = FrameSerializer::serialize(&frame, Quirks::empty());
         let want = vec![220, 194, 0, 11, 6, 61, 5, 25, 240, 36, 62];
         assert_eq!(want, testcase);
     }
 
     #[test]
     fn test_frame_serialize_set_request() {
-        let frame = Frame::new_set(0, 66, 87884342, vec![1, 0]);
-        let testcase = FrameSerializer::serialize(&frame);
+        let frame = Frame::new_set(0, 66, 87884342, vec![1, 0]).unwrap();
+        let testcase = FrameSerializer::serialize(&frame, Quirks::empty());
         let want = vec![220, 194, 0, 13, 3, 61, 5, 2, 54, 1, 0, 70, 13];
         assert_eq!(want, testcase);
     }
+
+    #[test]
+    fn test_frame_serialize_into_reused_buffer() {
+        let frame = Frame::new_get(0, 66, 0x053d_19f0);
+        let mut buffer = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+        FrameSerializer::serialize_into(&frame, &mut buffer, Quirks::empty());
+        let want = vec![220, 194, 0, 11, 6, 61, 5, 25, 240, 36, 62];
+        assert_eq!(want, buffer);
+    }
 }
@@ -0,0 +1,160 @@
+use std::sync::Arc;
+
+use crate::{BufferPool, Frame, ParseResult, Quirks, SOF};
+
+/// `FrameStream` accumulates the bytes received from the bus and yields the parsed `Frame`s.
+/// Incomplete frames are kept until more bytes arrive, broken frames and garbage between
+/// frames are skipped, so callers don't need to manage a buffer themselves
+#[derive(Debug, Default)]
+pub struct FrameStream {
+    buffer: Vec<u8>,
+    quirks: Quirks,
+    errors: usize,
+    pool: Option<Arc<BufferPool>>,
+}
+
+impl FrameStream {
+    /// Create a new empty `FrameStream`
+    #[must_use]
+    pub fn new() -> FrameStream {
+        FrameStream::default()
+    }
+
+    /// Return the `FrameStream` parsing frames with the device `quirks`
+    #[must_use]
+    pub fn with_quirks(mut self, quirks: Quirks) -> FrameStream {
+        self.quirks = quirks;
+        self
+    }
+
+    /// Return the `FrameStream` with its receive buffer taken from the shared `pool`.
+    /// The buffer is returned to the `pool` when the `FrameStream` is dropped
+    #[must_use]
+    pub fn with_pool(mut self, pool: Arc<BufferPool>) -> FrameStream {
+        let mut buffer = pool.take();
+        buffer.append(&mut self.buffer);
+        self.buffer = buffer;
+        self.pool = Some(pool);
+        self
+    }
+
+    /// Append `bytes` received from the bus
+    pub fn push_bytes(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Parse the next complete `Frame` from the received bytes.
+    /// Returns `None` if more bytes are needed
+    pub fn next_frame(&mut self) -> Option<Frame> {
+        loop {
+            // garbage before the next SOF can never become part of a frame
+            let start = self
+                .buffer
+                .iter()
+                .position(|&byte| byte == SOF)
+                .unwrap_or(self.buffer.len());
+            self.buffer.drain(..start);
+            if self.buffer.is_empty() {
+                return None;
+            }
+            match Frame::parse_with(&self.buffer, self.quirks) {
+                ParseResult::Ok { rest, frame } => {
+                    let end = self.buffer.len() - rest.len();
+                    self.buffer.drain(..end);
+                    return Some(frame);
+                }
+                ParseResult::Incomplete => return None,
+                ParseResult::Failure { rest, .. } => {
+                    // continue at the next SOF after the broken frame
+                    self.errors += 1;
+                    let end = self.buffer.len() - rest.len();
+                    self.buffer.drain(..end);
+                }
+            }
+        }
+    }
+
+    /// Number of bytes that wait for the rest of their frame
+    #[must_use]
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Number of broken frames that were skipped
+    #[must_use]
+    pub fn errors(&self) -> usize {
+        self.errors
+    }
+}
+
+impl Drop for FrameStream {
+    fn drop(&mut self) {
+        if let Some(pool) = &self.pool {
+            pool.give(std::mem::take(&mut self.buffer));
+        }
+    }
+}
+
+impl Iterator for FrameStream {
+    type Item = Frame;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_frame()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{BufferPool, Frame};
+
+    use super::FrameStream;
+
+    #[test]
+    fn test_frame_stream_push_bytes() {
+        let first = Frame::new(66, 0, 7, 0x053d_19f0, vec![0, 0, 15]).unwrap();
+        let second = Frame::new_get(0, 66, 0x053d_19f0);
+        let mut broken = first.serialize();
+        broken[13] ^= 0xff;
+        let raw = [
+            vec![0x00, 0x01],
+            first.serialize(),
+            broken,
+            vec![0x02],
+            second.serialize(),
+        ]
+        .concat();
+        let mut stream = FrameStream::new();
+        // feed the bytes in small chunks like a serial port does
+        let mut testcase = Vec::new();
+        for chunk in raw.chunks(5) {
+            stream.push_bytes(chunk);
+            testcase.extend(&mut stream);
+        }
+        assert_eq!(testcase, [first, second]);
+        assert_eq!(stream.errors(), 1);
+        assert_eq!(stream.buffered(), 0);
+        // an incomplete frame is kept
+        stream.push_bytes(&[0x00, 0xdc, 0x80]);
+        assert_eq!(stream.next_frame(), None);
+        assert_eq!(stream.buffered(), 2);
+    }
+
+    #[test]
+    fn test_frame_stream_with_pool() {
+        let pool = Arc::new(BufferPool::new(1));
+        pool.give(Vec::with_capacity(64));
+        let mut stream = FrameStream::new().with_pool(Arc::clone(&pool));
+        assert!(pool.is_empty());
+        stream.push_bytes(&Frame::new_get(0, 66, 0x053d_19f0).serialize());
+        assert_eq!(
+            stream.next_frame(),
+            Some(Frame::new_get(0, 66, 0x053d_19f0))
+        );
+        // the receive buffer is returned on drop
+        drop(stream);
+        assert_eq!(pool.len(), 1);
+        assert!(pool.take().capacity() >= 64);
+    }
+}
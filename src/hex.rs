@@ -0,0 +1,103 @@
+use std::fmt::Write as _;
+
+/// Encode `bytes` as lowercase hex string without separators, e.g. "00000f"
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{byte:02x}");
+        hex
+    })
+}
+
+/// Decode a hex string like "00000f" into bytes, whitespace between the bytes is ignored.
+/// Returns `None` if the string contains other characters or an odd number of digits
+pub(crate) fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    let digits = hex
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| c.to_digit(16).and_then(|digit| u8::try_from(digit).ok()))
+        .collect::<Option<Vec<_>>>()?;
+    if digits.len() % 2 != 0 {
+        return None;
+    }
+    Some(
+        digits
+            .chunks(2)
+            .map(|pair| pair[0] << 4 | pair[1])
+            .collect(),
+    )
+}
+
+/// Serde representation of a field id as hex string, e.g. "0x053d19f0"
+pub(crate) mod field_id {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    pub(crate) fn serialize<S: Serializer>(
+        field_id: &u32,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("0x{field_id:08x}"))
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        let digits = hex
+            .strip_prefix("0x")
+            .ok_or_else(|| D::Error::custom("field id without 0x prefix"))?;
+        u32::from_str_radix(digits, 16).map_err(D::Error::custom)
+    }
+}
+
+/// Serde representation of a payload as hex string without separators, e.g. "00000f"
+pub(crate) mod payload {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    use crate::MAX_PAYLOAD_LENGTH;
+
+    use super::{from_hex, to_hex};
+
+    pub(crate) fn serialize<S: Serializer>(
+        payload: impl AsRef<[u8]>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&to_hex(payload.as_ref()))
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<u8>, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        let payload = from_hex(&hex).ok_or_else(|| D::Error::custom("invalid hex payload"))?;
+        // a longer payload cannot be serialized into a frame
+        if payload.len() > MAX_PAYLOAD_LENGTH {
+            return Err(D::Error::custom("payload too long"));
+        }
+        Ok(payload)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_hex, to_hex};
+
+    #[test]
+    fn test_to_hex() {
+        assert_eq!(to_hex(&[0, 0x0f, 0xdc]), "000fdc");
+        assert_eq!(to_hex(&[]), "");
+    }
+
+    #[test]
+    fn test_from_hex() {
+        // (<hex>, <bytes>)
+        let testcases = vec![
+            ("000fdc", Some(vec![0, 0x0f, 0xdc])),
+            ("00 0F dc", Some(vec![0, 0x0f, 0xdc])),
+            ("", Some(vec![])),
+            ("0", None),
+            ("0g", None),
+        ];
+        for (hex, want) in testcases {
+            assert_eq!(from_hex(hex), want);
+        }
+    }
+}
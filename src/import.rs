@@ -0,0 +1,254 @@
+//! Import of the parameter definitions of the BSB-LAN project into the field database.
+//!
+//! BSB-LAN defines its parameters in the `cmdtbl` of `BSB_LAN_defs.h`, one C initializer per
+//! parameter, e.g.
+//! `{0x2D3D0574, VT_ENUM, 700, STR700, sizeof(ENUM700), ENUM700, DEFAULT_FLAG, DEV_ALL}, // Betriebsart`.
+//! The importer reads the field id, the value type, the program number and the flags of these
+//! lines and maps the value types onto a `Datatype`. The result can be appended to
+//! `bsb-fields.csv`.
+
+use std::io::Write;
+
+use crate::{Datatype, Field, Prognr, Unit};
+
+/// A parameter of BSB-LAN that could be mapped onto a `Datatype`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedField {
+    id: u32,
+    prognr: Prognr,
+    datatype: Datatype,
+    unit: Option<Unit>,
+    writable: bool,
+    description: Option<String>,
+}
+
+impl ImportedField {
+    /// Access `ImportedField.id`
+    #[must_use]
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Access `ImportedField.prognr`
+    #[must_use]
+    pub fn prognr(&self) -> Prognr {
+        self.prognr
+    }
+
+    /// Access `ImportedField.datatype`
+    #[must_use]
+    pub fn datatype(&self) -> Datatype {
+        self.datatype
+    }
+
+    /// Access `ImportedField.unit`
+    #[must_use]
+    pub fn unit(&self) -> Option<Unit> {
+        self.unit
+    }
+
+    /// Access `ImportedField.writable`, `false` for parameters flagged as read-only
+    #[must_use]
+    pub fn is_writable(&self) -> bool {
+        self.writable
+    }
+
+    /// Access `ImportedField.description`, the comment of the definition if there is one
+    #[must_use]
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// Name of the field in the database, e.g. `parameter_8700_1`
+    #[must_use]
+    pub fn name(&self) -> String {
+        format!("parameter_{}", self.prognr.to_string().replace('.', "_"))
+    }
+
+    /// Row of the field in the format of `bsb-fields.csv`
+    #[must_use]
+    pub fn csv_row(&self) -> String {
+        format!(
+            "0x{:08x},{},{},{},parameter/{},{},,,{},",
+            self.id,
+            self.name(),
+            self.prognr,
+            self.datatype,
+            self.prognr,
+            self.unit
+                .map(|unit| format!("{unit:?}"))
+                .unwrap_or_default(),
+            self.writable,
+        )
+    }
+}
+
+/// Result of an import of BSB-LAN parameter definitions
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BsbLanImport {
+    fields: Vec<ImportedField>,
+    unmapped: Vec<(u32, String)>,
+}
+
+impl BsbLanImport {
+    /// Parse the `cmdtbl` entries of the BSB-LAN definitions in `defs`.
+    /// Only the first definition of each program number is imported, later definitions are
+    /// variants for other devices. Lines that are no parameter definitions are ignored
+    #[must_use]
+    pub fn parse(defs: &str) -> BsbLanImport {
+        let mut import = BsbLanImport::default();
+        for (id, value_type, field) in defs.lines().filter_map(parse_line) {
+            match field {
+                Some(field) => {
+                    if import
+                        .fields
+                        .iter()
+                        .all(|known| known.id != field.id && known.prognr != field.prognr)
+                    {
+                        import.fields.push(field);
+                    }
+                }
+                None => import.unmapped.push((id, value_type)),
+            }
+        }
+        import
+    }
+
+    /// Access `BsbLanImport.fields`, the parameters with a known `Datatype`
+    #[must_use]
+    pub fn fields(&self) -> &[ImportedField] {
+        &self.fields
+    }
+
+    /// Access `BsbLanImport.unmapped`, the field ids and value types of parameters whose value
+    /// type has no `Datatype` yet
+    #[must_use]
+    pub fn unmapped(&self) -> &[(u32, String)] {
+        &self.unmapped
+    }
+
+    /// Write the rows of all imported fields that are not in the field database yet,
+    /// in the format of `bsb-fields.csv` without header. Returns the number of written rows
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> std::io::Result<usize> {
+        let mut count = 0;
+        for field in &self.fields {
+            if Field::by_id(field.id).is_some() {
+                continue;
+            }
+            writeln!(writer, "{}", field.csv_row())?;
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+/// Map a BSB-LAN value type onto a `Datatype` and `Unit`
+fn map_value_type(value_type: &str) -> Option<(Datatype, Option<Unit>)> {
+    let mapped = match value_type {
+        "VT_ONOFF" | "VT_YESNO" | "VT_CLOSEDOPEN" => (Datatype::Setting(1), None),
+        // the number of states is defined by the enum texts, allow all of them
+        "VT_ENUM" => (Datatype::Setting(u8::MAX), None),
+        "VT_TEMP" => (Datatype::Float(64), Some(Unit::Celsius)),
+        "VT_SLOPE" => (Datatype::Float(50), None),
+        "VT_PRESSURE_WORD" => (Datatype::Float(10), Some(Unit::Bar)),
+        "VT_PERCENT_WORD" => (Datatype::Float(2), Some(Unit::Percent)),
+        "VT_UINT" | "VT_HOURS_WORD" | "VT_MINUTES_WORD" | "VT_SECONDS_WORD" => {
+            (Datatype::Number, None)
+        }
+        "VT_SINT" => (Datatype::SignedNumber, None),
+        "VT_DATETIME" => (Datatype::DateTime, None),
+        "VT_TIMEPROG" => (Datatype::Schedule, None),
+        "VT_STRING" => (Datatype::String, None),
+        _ => return None,
+    };
+    Some(mapped)
+}
+
+/// Parse a `cmdtbl` line into the field id, the value type and the `ImportedField` if the
+/// value type can be mapped. Returns `None` for lines that are no parameter definitions
+fn parse_line(line: &str) -> Option<(u32, String, Option<ImportedField>)> {
+    let (definition, comment) = line.split_once("//").unwrap_or((line, ""));
+    let definition = definition.trim().strip_prefix('{')?;
+    let (definition, _) = definition.split_once('}')?;
+    let tokens = definition.split(',').map(str::trim).collect::<Vec<_>>();
+    let id = u32::from_str_radix(tokens.first()?.strip_prefix("0x")?, 16).ok()?;
+    let type_index = tokens.iter().position(|token| token.starts_with("VT_"))?;
+    let value_type = tokens[type_index];
+    let prognr = tokens[type_index + 1..]
+        .iter()
+        .find_map(|token| token.parse::<Prognr>().ok())?;
+    let field = map_value_type(value_type).map(|(datatype, unit)| ImportedField {
+        id,
+        prognr,
+        datatype,
+        unit,
+        writable: !tokens.iter().any(|token| token.contains("FL_RONLY")),
+        description: Some(comment.trim().to_string()).filter(|comment| !comment.is_empty()),
+    });
+    Some((id, value_type.to_string(), field))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Datatype, Prognr, Unit};
+
+    use super::BsbLanImport;
+
+    const DEFS: &str = r"
+#define STR700 STR700_TEXT
+{0x2D3D0574,  VT_ENUM,          700,   STR700,  sizeof(ENUM700),  ENUM700,  DEFAULT_FLAG, DEV_ALL}, // Betriebsart
+{0x2E3D0574,  VT_ENUM,          700,   STR700,  sizeof(ENUM700),  ENUM700,  DEFAULT_FLAG, DEV_064_ALL}, // Betriebsart
+{0x053D0521,  VT_TEMP,          8700,  STR8700, 0,                NULL,     FL_RONLY,     DEV_ALL}, // Außentemperatur
+{0x053D0522,  VT_TEMP,          8700.1, STR8700_2, 0,             NULL,     FL_RONLY,     DEV_ALL},
+{0x053D0523,  VT_LPBADDR,       6600,  STR6600, 0,                NULL,     DEFAULT_FLAG, DEV_ALL}, // LPB-Adresse
+";
+
+    #[test]
+    fn test_bsb_lan_import_parse() {
+        let import = BsbLanImport::parse(DEFS);
+        // (<id>, <prognr>, <datatype>, <unit>, <writable>)
+        let want = vec![
+            (0x2d3d_0574, "700", Datatype::Setting(255), None, true),
+            (
+                0x053d_0521,
+                "8700",
+                Datatype::Float(64),
+                Some(Unit::Celsius),
+                false,
+            ),
+            (
+                0x053d_0522,
+                "8700.1",
+                Datatype::Float(64),
+                Some(Unit::Celsius),
+                false,
+            ),
+        ];
+        assert_eq!(import.fields().len(), want.len());
+        for (field, (id, prognr, datatype, unit, writable)) in import.fields().iter().zip(want) {
+            assert_eq!(field.id(), id);
+            assert_eq!(field.prognr(), prognr.parse::<Prognr>().unwrap());
+            assert_eq!(field.datatype(), datatype);
+            assert_eq!(field.unit(), unit);
+            assert_eq!(field.is_writable(), writable);
+        }
+        assert_eq!(import.fields()[0].description(), Some("Betriebsart"));
+        assert_eq!(import.unmapped(), [(0x053d_0523, "VT_LPBADDR".to_string())]);
+    }
+
+    #[test]
+    fn test_bsb_lan_import_write_csv() {
+        let import = BsbLanImport::parse(DEFS);
+        let mut csv = Vec::new();
+        // 700 and 8700 are already in the field database
+        assert_eq!(import.write_csv(&mut csv).unwrap(), 1);
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "0x053d0522,parameter_8700_1,8700.1,Float(64),parameter/8700.1,Celsius,,,false,\n"
+        );
+    }
+}
@@ -1,21 +1,87 @@
 #![warn(clippy::pedantic)]
+// the tests keep the field ids as decimal literals and the tables in their original layout
+#![cfg_attr(
+    test,
+    allow(
+        clippy::decimal_bitwise_operands,
+        clippy::doc_markdown,
+        clippy::explicit_into_iter_loop,
+        clippy::needless_borrow,
+        clippy::semicolon_if_nothing_returned,
+        clippy::type_complexity,
+        clippy::unreadable_literal
+    )
+)]
 
+pub mod binlog;
+pub mod bus;
+mod calibration;
+mod capabilities;
+#[cfg(feature = "codec")]
+mod codec;
+#[cfg(feature = "config")]
+pub mod config;
+pub mod convert;
 mod datatypes;
+mod decode_options;
 mod error;
+mod event;
 mod field;
 mod field_value;
+mod fixed_point;
 mod frame;
+mod hex;
+pub mod import;
+pub mod log;
+#[cfg(feature = "lpb")]
+pub mod lpb;
+#[cfg(feature = "metrics")]
+pub mod metrics;
 mod named_value;
+pub mod opentherm;
+mod pool;
+#[cfg(feature = "pps")]
+pub mod pps;
+mod quirks;
+mod rounding;
+mod roundtrip;
+mod schedule;
+pub mod session;
+mod storage;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod unit;
+mod unknown_fields;
 mod value;
 
 // re-export these datastructures as public API
+pub use calibration::{Calibration, CalibrationRegistry};
+pub use capabilities::{Capability, CapabilityCache};
+#[cfg(feature = "codec")]
+pub use codec::BsbCodec;
 pub use datatypes::Datatype;
-pub use error::BsbError;
-pub use field::Field;
+pub use decode_options::{DecodeOptions, WeekdayCheck};
+pub use error::{BsbError, ErrorCategory, ErrorContext, Operation};
+pub use event::DecodedEvent;
+pub use field::{Field, Prognr};
 pub use field_value::FieldValue;
+pub use fixed_point::FixedPoint;
+pub use frame::borrowed::FrameRef;
+pub use frame::parser::resync;
 pub use frame::parser::ParseErrorKind;
 pub use frame::parser::ParseResult;
+pub use frame::stream::FrameStream;
 pub use frame::Frame;
+pub use frame::FrameSegment;
 pub use frame::PacketType;
+pub use frame::{MAX_FRAME_LENGTH, MAX_PAYLOAD_LENGTH, SOF};
 pub use named_value::NamedValue;
+pub use pool::BufferPool;
+pub use quirks::Quirks;
+pub use rounding::Rounding;
+pub use roundtrip::{verify_roundtrip, RoundtripIssue};
+pub use schedule::{DaySchedule, WeekSchedule};
+pub use storage::{EventQuery, MemoryStorage, Storage, StoredEvent};
+pub use unit::{Unit, UnitPreference};
+pub use unknown_fields::{UnknownField, UnknownFieldCollector, UnknownFieldValue};
 pub use value::Value;
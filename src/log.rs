@@ -0,0 +1,245 @@
+//! Human-readable telegram log format for long captures and bug reports.
+//!
+//! Each line holds one frame: the RFC 3339 timestamp in UTC, the serialized frame as hex and
+//! optionally the decoded value as a comment, e.g.
+//! `2024-01-01T12:00:00.000000Z dc80420e07053d19f000000f1d74 # water_pressure: 1.5`.
+//! Empty lines and lines starting with `#` are ignored when reading.
+//!
+//! Raw captures that may contain broken frames can be written as a classic hexdump with
+//! `hexdump` and read back with `parse_hexdump`.
+
+use std::{
+    fmt::Write as _,
+    io::{BufRead, Error, ErrorKind, Lines, Write},
+};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+
+use crate::{
+    hex::{from_hex, to_hex},
+    FieldValue, Frame, ParseResult,
+};
+
+/// Number of bytes in one line of a hexdump
+const HEXDUMP_WIDTH: usize = 16;
+
+/// Writes timestamped frames as lines of the telegram log format
+#[derive(Debug)]
+pub struct LogWriter<W: Write> {
+    writer: W,
+    decode: bool,
+}
+
+impl<W: Write> LogWriter<W> {
+    /// Create a new `LogWriter` that adds the decoded value of known fields as comment
+    pub fn new(writer: W) -> LogWriter<W> {
+        LogWriter {
+            writer,
+            decode: true,
+        }
+    }
+
+    /// Return the `LogWriter` writing only timestamps and frames, without decoded values
+    #[must_use]
+    pub fn without_decoding(mut self) -> LogWriter<W> {
+        self.decode = false;
+        self
+    }
+
+    /// Append a `frame` received at `timestamp`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails
+    pub fn append(&mut self, timestamp: DateTime<Utc>, frame: &Frame) -> std::io::Result<()> {
+        let timestamp = timestamp.to_rfc3339_opts(SecondsFormat::Micros, true);
+        write!(self.writer, "{timestamp} {}", to_hex(&frame.serialize()))?;
+        match FieldValue::from_frame(frame) {
+            Ok(field_value) if self.decode => writeln!(self.writer, " # {field_value}"),
+            _ => writeln!(self.writer),
+        }
+    }
+
+    /// Flush the writer
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if flushing fails
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Flush and return the writer
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if flushing fails
+    pub fn finish(mut self) -> std::io::Result<W> {
+        self.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Iterator over the timestamped frames of a telegram log.
+/// A broken line yields an error with `ErrorKind::InvalidData` and reading continues with the
+/// next line, so a single undecodable telegram does not hide the rest of the capture
+#[derive(Debug)]
+pub struct LogReader<R: BufRead> {
+    lines: Lines<R>,
+    line_number: usize,
+}
+
+impl<R: BufRead> LogReader<R> {
+    /// Create a new `LogReader`
+    pub fn new(reader: R) -> LogReader<R> {
+        LogReader {
+            lines: reader.lines(),
+            line_number: 0,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for LogReader<R> {
+    type Item = std::io::Result<(DateTime<Utc>, Frame)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(error) => return Some(Err(error)),
+            };
+            self.line_number += 1;
+            // strip the comment with the decoded value
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            return Some(
+                parse_line(line)
+                    .map_err(|message| invalid(&format!("line {}: {message}", self.line_number))),
+            );
+        }
+    }
+}
+
+/// Parse a line without comment into the timestamp and the `Frame`
+fn parse_line(line: &str) -> Result<(DateTime<Utc>, Frame), &'static str> {
+    let (timestamp, hex) = line.split_once(' ').ok_or("missing frame")?;
+    let timestamp = DateTime::parse_from_rfc3339(timestamp)
+        .map_err(|_| "invalid timestamp")?
+        .with_timezone(&Utc);
+    let bytes = from_hex(hex).ok_or("invalid hex")?;
+    match Frame::parse(&bytes) {
+        ParseResult::Ok { rest: [], frame } => Ok((timestamp, frame)),
+        _ => Err("invalid frame"),
+    }
+}
+
+/// Format raw `bytes` as hexdump with the offset, 16 bytes per line and their ASCII
+/// representation, e.g. `00000000  dc 80 42 0e  |..B.|`
+#[must_use]
+pub fn hexdump(bytes: &[u8]) -> String {
+    let mut dump = String::new();
+    for (line, chunk) in bytes.chunks(HEXDUMP_WIDTH).enumerate() {
+        let hex = chunk
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let ascii = chunk
+            .iter()
+            .map(|&byte| {
+                if byte.is_ascii_graphic() || byte == b' ' {
+                    char::from(byte)
+                } else {
+                    '.'
+                }
+            })
+            .collect::<String>();
+        let _ = writeln!(
+            dump,
+            "{:08x}  {hex:<width$}  |{ascii}|",
+            line * HEXDUMP_WIDTH,
+            width = HEXDUMP_WIDTH * 3 - 1
+        );
+    }
+    dump
+}
+
+/// Parse a hexdump created by `hexdump` back into the raw bytes
+///
+/// # Errors
+///
+/// Returns an error with `ErrorKind::InvalidData` if a line is not part of a hexdump
+pub fn parse_hexdump(dump: &str) -> std::io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    for (number, line) in dump.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        // the hex bytes are between the offset and the ASCII column
+        let hex = line
+            .split_once("  ")
+            .map(|(_, rest)| rest.split('|').next().unwrap_or_default())
+            .and_then(from_hex)
+            .ok_or_else(|| invalid(&format!("line {}: invalid hexdump", number + 1)))?;
+        bytes.extend(hex);
+    }
+    Ok(bytes)
+}
+
+/// Create an error for invalid log content
+fn invalid(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, ErrorKind};
+
+    use chrono::DateTime;
+
+    use crate::{convert::frames_from_capture, Frame, PacketType};
+
+    use super::{hexdump, parse_hexdump, LogReader, LogWriter};
+
+    #[test]
+    fn test_log_roundtrip() {
+        let timestamp = DateTime::from_timestamp(1_704_110_400, 250_000_000).unwrap();
+        let known = Frame::new(66, 0, PacketType::Ret.into(), 0x053d_19f0, vec![0, 0, 15]).unwrap();
+        let unknown = Frame::new(66, 0, PacketType::Ret.into(), 0x1234_5678, vec![0, 1]).unwrap();
+        let mut writer = LogWriter::new(Vec::new());
+        writer.append(timestamp, &known).unwrap();
+        writer.append(timestamp, &unknown).unwrap();
+        let log = String::from_utf8(writer.finish().unwrap()).unwrap();
+        assert_eq!(
+            log.lines().next().unwrap(),
+            "2024-01-01T12:00:00.250000Z dc80420e07053d19f000000f1d74 # water_pressure: 1.5"
+        );
+        // comments, empty lines and a broken telegram between the frames
+        let log = format!("# capture\n\n2024-01-01T12:00:00Z dc80420e\n{log}");
+        let testcase = LogReader::new(Cursor::new(log)).collect::<Vec<_>>();
+        assert_eq!(testcase.len(), 3);
+        let error = testcase[0].as_ref().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert_eq!(error.to_string(), "line 3: invalid frame");
+        assert_eq!(testcase[1].as_ref().unwrap(), &(timestamp, known));
+        assert_eq!(testcase[2].as_ref().unwrap(), &(timestamp, unknown));
+    }
+
+    #[test]
+    fn test_hexdump() {
+        let frame = Frame::new(66, 0, PacketType::Ret.into(), 0x053d_19f0, vec![0, 0, 15]).unwrap();
+        let raw = [vec![0x41, 0x42], frame.serialize(), vec![0xdc]].concat();
+        let dump = hexdump(&raw);
+        assert_eq!(
+            dump,
+            "00000000  41 42 dc 80 42 0e 07 05 3d 19 f0 00 00 0f 1d 74  |AB..B...=......t|\n\
+             00000010  dc                                               |.|\n"
+        );
+        let testcase = parse_hexdump(&dump).unwrap();
+        assert_eq!(testcase, raw);
+        assert_eq!(frames_from_capture(&testcase), [frame]);
+        assert!(parse_hexdump("no hexdump").is_err());
+    }
+}
@@ -0,0 +1,252 @@
+//! Experimental framing of the LPB (Local Process Bus) between Siemens controllers.
+//!
+//! LPB telegrams carry the same packet types, field ids and payloads as BSB telegrams, so a
+//! parsed `LpbFrame` is converted into a `Frame` and decoded with `FieldValue::from_frame`.
+//! Only the framing differs:
+//!
+//! `[0x78, <length>, <destination>, <source>, <control 4 bytes>, <packet type>, <field id 4 bytes>, <payload>…, <checksum 2 bytes>]`
+//!
+//! The length counts all bytes after the SOF, the addresses are not inverted and the checksum
+//! is the 16 bit sum of all bytes from the length to the end of the payload. The meaning of the
+//! control bytes is unknown, they are kept to serialize a parsed frame identically.
+
+use crate::{Frame, ParseErrorKind, ParseResult, MAX_PAYLOAD_LENGTH};
+
+/// LPB `SOF` (start of frame) that is used to start each frame
+pub const LPB_SOF: u8 = 0x78;
+
+/// Control bytes of new frames
+pub const DEFAULT_CONTROL: [u8; 4] = [0x20, 0, 0, 0];
+
+/// Length of a frame without payload: SOF, length, addresses, control, packet type,
+/// field id and checksum
+const MIN_FRAME_LENGTH: usize = 1 + 1 + 2 + 4 + 1 + 4 + 2;
+
+/// LPB address of the `device` (1-16) in the `segment` (0-15)
+#[must_use]
+pub fn address(segment: u8, device: u8) -> u8 {
+    (segment << 4) | (device.wrapping_sub(1) & 0x0f)
+}
+
+/// Segment and device of an LPB `address`, the reverse of `address`
+#[must_use]
+pub fn segment_and_device(address: u8) -> (u8, u8) {
+    (address >> 4, (address & 0x0f) + 1)
+}
+
+/// `LpbFrame` is a `Frame` together with the LPB control bytes
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LpbFrame {
+    frame: Frame,
+    control: [u8; 4],
+}
+
+impl LpbFrame {
+    /// Create a new `LpbFrame` with the `DEFAULT_CONTROL` bytes
+    #[must_use]
+    pub fn new(frame: Frame) -> LpbFrame {
+        LpbFrame {
+            frame,
+            control: DEFAULT_CONTROL,
+        }
+    }
+
+    /// Return the `LpbFrame` with different `control` bytes
+    #[must_use]
+    pub fn with_control(mut self, control: [u8; 4]) -> LpbFrame {
+        self.control = control;
+        self
+    }
+
+    /// Access `LpbFrame.frame`
+    #[must_use]
+    pub fn frame(&self) -> &Frame {
+        &self.frame
+    }
+
+    /// Access `LpbFrame.control`
+    #[must_use]
+    pub fn control(&self) -> [u8; 4] {
+        self.control
+    }
+
+    /// Consume the `LpbFrame` and return the `Frame`
+    #[must_use]
+    pub fn into_frame(self) -> Frame {
+        self.frame
+    }
+
+    /// Parse the `input` slice into `Ok(remaining_bytes, LpbFrame)`, `Incomplete` or `Failure`.
+    /// Garbage before the SOF is skipped, on `Failure` the `rest` starts at the next SOF
+    #[must_use]
+    pub fn parse(input: &[u8]) -> ParseResult<'_, LpbFrame> {
+        let Some(start) = input.iter().position(|&byte| byte == LPB_SOF) else {
+            return ParseResult::Incomplete;
+        };
+        let message = &input[start..];
+        let Some(&length) = message.get(1) else {
+            return ParseResult::Incomplete;
+        };
+        let frame_length = usize::from(length) + 1;
+        if !(MIN_FRAME_LENGTH..=MIN_FRAME_LENGTH + MAX_PAYLOAD_LENGTH).contains(&frame_length) {
+            return failure(input, start, ParseErrorKind::InvalidLength);
+        }
+        if message.len() < frame_length {
+            return ParseResult::Incomplete;
+        }
+        let (message, rest) = message.split_at(frame_length);
+        let (content, crc) = message.split_at(frame_length - 2);
+        if u16::from_be_bytes([crc[0], crc[1]]) != checksum(&content[1..]) {
+            return failure(input, start, ParseErrorKind::ChecksumError);
+        }
+        let frame = Frame::new_unchecked(
+            content[2],
+            content[3],
+            content[8],
+            u32::from_be_bytes([content[9], content[10], content[11], content[12]]),
+            content[13..].to_vec(),
+        );
+        ParseResult::Ok {
+            rest,
+            frame: LpbFrame {
+                frame,
+                control: [content[4], content[5], content[6], content[7]],
+            },
+        }
+    }
+
+    /// Serialize the `LpbFrame` into a `Vec<u8>`
+    ///
+    /// # Panics
+    ///
+    /// Does not panic, `Frame::new` limits the payload to `MAX_PAYLOAD_LENGTH`
+    #[must_use]
+    pub fn serialize(&self) -> Vec<u8> {
+        let frame = &self.frame;
+        let length = u8::try_from(MIN_FRAME_LENGTH - 1 + frame.payload().len())
+            .expect("the payload of an LPB frame is at most MAX_PAYLOAD_LENGTH bytes");
+        let mut bytes = vec![
+            LPB_SOF,
+            length,
+            frame.destination_address(),
+            frame.source_address(),
+        ];
+        bytes.extend_from_slice(&self.control);
+        bytes.push(frame.packet_type());
+        bytes.extend_from_slice(&frame.field_id().to_be_bytes());
+        bytes.extend_from_slice(frame.payload());
+        let crc = checksum(&bytes[1..]);
+        bytes.extend_from_slice(&crc.to_be_bytes());
+        bytes
+    }
+}
+
+impl From<Frame> for LpbFrame {
+    fn from(frame: Frame) -> Self {
+        LpbFrame::new(frame)
+    }
+}
+
+impl From<LpbFrame> for Frame {
+    fn from(frame: LpbFrame) -> Self {
+        frame.into_frame()
+    }
+}
+
+/// Checksum of an LPB frame, the 16 bit sum of the `bytes`
+fn checksum(bytes: &[u8]) -> u16 {
+    bytes
+        .iter()
+        .fold(0u16, |sum, &byte| sum.wrapping_add(u16::from(byte)))
+}
+
+/// Create a `ParseResult::Failure` for the broken frame at `start` that continues at the next SOF
+fn failure(input: &[u8], start: usize, error: ParseErrorKind) -> ParseResult<'_, LpbFrame> {
+    let next = input[start + 1..]
+        .iter()
+        .position(|&byte| byte == LPB_SOF)
+        .map_or(input.len(), |next| start + 1 + next);
+    let (broken_data, rest) = input.split_at(next);
+    ParseResult::Failure {
+        rest,
+        broken_data,
+        error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{FieldValue, Frame, PacketType, ParseErrorKind, ParseResult};
+
+    use super::{address, segment_and_device, LpbFrame};
+
+    #[test]
+    fn test_lpb_frame_roundtrip() {
+        let frame = Frame::new(
+            address(0, 1),
+            address(0, 2),
+            PacketType::Ret.into(),
+            0x053d_19f0,
+            vec![0, 0, 15],
+        )
+        .unwrap();
+        let lpb = LpbFrame::new(frame).with_control([0x14, 0xc0, 2, 0]);
+        let bytes = lpb.serialize();
+        assert_eq!(
+            bytes,
+            [
+                0x78, 0x11, 0x00, 0x01, 0x14, 0xc0, 0x02, 0x00, 0x07, 0x05, 0x3d, 0x19, 0xf0, 0x00,
+                0x00, 0x0f, 0x02, 0x49
+            ]
+        );
+        let raw = [&[0x00][..], &bytes, &[0x78]].concat();
+        let ParseResult::Ok {
+            rest,
+            frame: parsed,
+        } = LpbFrame::parse(&raw)
+        else {
+            panic!("not a frame")
+        };
+        assert_eq!(rest, [0x78]);
+        assert_eq!(parsed, lpb);
+        // the value is decoded like a BSB frame
+        let field_value = FieldValue::from_frame(&parsed.into_frame()).unwrap();
+        assert_eq!(field_value.to_string(), "water_pressure: 1.5");
+        assert!(matches!(
+            LpbFrame::parse(&bytes[..10]),
+            ParseResult::Incomplete
+        ));
+    }
+
+    #[test]
+    fn test_lpb_frame_parse_failure() {
+        let valid = LpbFrame::new(Frame::new_get(0, 1, 0x053d_19f0)).serialize();
+        let mut broken = valid.clone();
+        broken[9] ^= 0xff;
+        // (<data>, <error>)
+        let testcases = vec![
+            (
+                [broken.as_slice(), &valid].concat(),
+                ParseErrorKind::ChecksumError,
+            ),
+            (
+                [&[0x78, 0x02][..], &valid].concat(),
+                ParseErrorKind::InvalidLength,
+            ),
+        ];
+        for (data, want) in testcases {
+            let ParseResult::Failure { rest, error, .. } = LpbFrame::parse(&data) else {
+                panic!("not a failure")
+            };
+            assert_eq!(error, want);
+            assert_eq!(rest, valid);
+        }
+    }
+
+    #[test]
+    fn test_lpb_address() {
+        assert_eq!(address(0, 1), 0x00);
+        assert_eq!(address(1, 3), 0x12);
+        assert_eq!(segment_and_device(0x12), (1, 3));
+    }
+}
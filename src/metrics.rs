@@ -0,0 +1,219 @@
+//! Prometheus metrics of decoded values and parser statistics.
+//!
+//! `Metrics` collects the latest numeric value of each field and source address as gauge and
+//! counts parsed and broken frames. `Metrics::render` returns the Prometheus text exposition
+//! format that can be served on a `/metrics` endpoint by any HTTP server.
+
+use std::{collections::BTreeMap, fmt::Write as _};
+
+use crate::{FieldValue, Frame, PacketType, ParseErrorKind, ParseResult, Value};
+
+/// Prefix of all metric names
+const DEFAULT_PREFIX: &str = "bsb";
+
+/// Latest sample of a field gauge
+#[derive(Debug, Clone, PartialEq)]
+struct Gauge {
+    help: &'static str,
+    value: f64,
+}
+
+/// Collector of field gauges and parser statistics
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metrics {
+    prefix: String,
+    gauges: BTreeMap<(String, u8), Gauge>,
+    frames: u64,
+    incomplete: u64,
+    parse_errors: BTreeMap<&'static str, u64>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Metrics {
+            prefix: DEFAULT_PREFIX.to_string(),
+            gauges: BTreeMap::new(),
+            frames: 0,
+            incomplete: 0,
+            parse_errors: BTreeMap::new(),
+        }
+    }
+}
+
+impl Metrics {
+    /// Create a new `Metrics` collector with the metric name prefix "bsb"
+    #[must_use]
+    pub fn new() -> Metrics {
+        Metrics::default()
+    }
+
+    /// Return the `Metrics` with a different metric name `prefix`
+    #[must_use]
+    pub fn with_prefix(mut self, prefix: &str) -> Metrics {
+        self.prefix = sanitize(prefix);
+        self
+    }
+
+    /// Count the result of a parser run and record the value of a parsed `Frame`
+    pub fn observe(&mut self, result: &ParseResult<'_>) {
+        match result {
+            ParseResult::Ok { frame, .. } => self.record_frame(frame),
+            ParseResult::Incomplete => self.incomplete += 1,
+            ParseResult::Failure { error, .. } => self.record_parse_error(*error),
+        }
+    }
+
+    /// Count a parsed `frame` and record its value if it is a `Ret` or `Info` of a known field
+    pub fn record_frame(&mut self, frame: &Frame) {
+        self.frames += 1;
+        if !frame.is_packet_type(PacketType::Ret) && !frame.is_packet_type(PacketType::Info) {
+            return;
+        }
+        if let Ok(field_value) = FieldValue::from_frame(frame) {
+            self.record_field_value(frame.source_address(), &field_value);
+        }
+    }
+
+    /// Count a frame that could not be parsed
+    pub fn record_parse_error(&mut self, error: ParseErrorKind) {
+        *self.parse_errors.entry(error.into()).or_default() += 1;
+    }
+
+    /// Record the `field_value` received from `source_address` as gauge. Values without a
+    /// numeric representation, e.g. a sensor error, remove the gauge
+    pub fn record_field_value(&mut self, source_address: u8, field_value: &FieldValue) {
+        let field = field_value.field();
+        let key = (
+            format!("{}_{}", self.prefix, sanitize(field.path())),
+            source_address,
+        );
+        match sample(field_value.value()) {
+            Some(value) => {
+                self.gauges.insert(
+                    key,
+                    Gauge {
+                        help: field.name(),
+                        value,
+                    },
+                );
+            }
+            None => {
+                self.gauges.remove(&key);
+            }
+        }
+    }
+
+    /// Render all metrics in the Prometheus text exposition format
+    #[must_use]
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let mut last_name = None;
+        for ((name, source), gauge) in &self.gauges {
+            if last_name != Some(name) {
+                let _ = writeln!(out, "# HELP {name} {}", gauge.help);
+                let _ = writeln!(out, "# TYPE {name} gauge");
+                last_name = Some(name);
+            }
+            let _ = writeln!(out, "{name}{{source=\"{source}\"}} {}", gauge.value);
+        }
+        let prefix = &self.prefix;
+        let _ = writeln!(out, "# HELP {prefix}_frames_total Parsed frames");
+        let _ = writeln!(out, "# TYPE {prefix}_frames_total counter");
+        let _ = writeln!(out, "{prefix}_frames_total {}", self.frames);
+        let _ = writeln!(
+            out,
+            "# HELP {prefix}_incomplete_total Parser runs that needed more bytes"
+        );
+        let _ = writeln!(out, "# TYPE {prefix}_incomplete_total counter");
+        let _ = writeln!(out, "{prefix}_incomplete_total {}", self.incomplete);
+        let _ = writeln!(
+            out,
+            "# HELP {prefix}_parse_errors_total Broken frames by error"
+        );
+        let _ = writeln!(out, "# TYPE {prefix}_parse_errors_total counter");
+        for (error, count) in &self.parse_errors {
+            let _ = writeln!(
+                out,
+                "{prefix}_parse_errors_total{{error=\"{error}\"}} {count}"
+            );
+        }
+        out
+    }
+}
+
+/// Numeric sample of a `Value`. Floats use their shortest representation to avoid
+/// artifacts like 22.299999237060547
+fn sample(value: &Value) -> Option<f64> {
+    match value {
+        Value::Float { value, .. } => value.to_string().parse().ok(),
+        _ => value.as_f64(),
+    }
+}
+
+/// Replace all characters that are not allowed in a metric name with `_`
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Frame, PacketType, ParseErrorKind};
+
+    use super::Metrics;
+
+    #[test]
+    fn test_metrics_render() {
+        let mut metrics = Metrics::new();
+        let pressure =
+            Frame::new(66, 0, PacketType::Ret.into(), 0x053d_19f0, vec![0, 0, 15]).unwrap();
+        let mut raw = pressure.serialize();
+        metrics.observe(&Frame::parse(&raw));
+        metrics.observe(&Frame::parse(&raw[..5]));
+        raw[13] ^= 0xff;
+        metrics.observe(&Frame::parse(&raw));
+        metrics.record_parse_error(ParseErrorKind::InvalidLength);
+        // a room temperature of about 22.3 degrees from another device
+        metrics.record_frame(
+            &Frame::new(
+                0x7f,
+                6,
+                PacketType::Info.into(),
+                0x2d3d_0215,
+                vec![0x05, 0x93],
+            )
+            .unwrap(),
+        );
+        // requests carry no value
+        metrics.record_frame(&Frame::new_get(0, 66, 0x053d_0521));
+        let want = "\
+# HELP bsb_heating_circuit_1_room_temperature heating_circuit_1_room_temperature
+# TYPE bsb_heating_circuit_1_room_temperature gauge
+bsb_heating_circuit_1_room_temperature{source=\"6\"} 22.296875
+# HELP bsb_system_water_pressure water_pressure
+# TYPE bsb_system_water_pressure gauge
+bsb_system_water_pressure{source=\"0\"} 1.5
+# HELP bsb_frames_total Parsed frames
+# TYPE bsb_frames_total counter
+bsb_frames_total 3
+# HELP bsb_incomplete_total Parser runs that needed more bytes
+# TYPE bsb_incomplete_total counter
+bsb_incomplete_total 1
+# HELP bsb_parse_errors_total Broken frames by error
+# TYPE bsb_parse_errors_total counter
+bsb_parse_errors_total{error=\"ChecksumError\"} 1
+bsb_parse_errors_total{error=\"InvalidLength\"} 1
+";
+        assert_eq!(metrics.render(), want);
+        // a sensor error removes the gauge
+        metrics.record_frame(
+            &Frame::new(66, 0, PacketType::Ret.into(), 0x053d_19f0, vec![0, 0x80, 0]).unwrap(),
+        );
+        assert!(!metrics.render().contains("bsb_system_water_pressure"));
+        assert!(Metrics::new()
+            .with_prefix("boiler-1")
+            .render()
+            .contains("boiler_1_frames_total 0"));
+    }
+}
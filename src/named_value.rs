@@ -1,34 +1,42 @@
-use std::fmt::Display;
+use std::{borrow::Cow, fmt::Display};
 
 use serde::{Deserialize, Serialize};
 
 use crate::FieldValue;
 
 /// `NamedValue` is optimized to contain all information necessary
-/// for display purposes but can recover the original representation
-#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+/// for display purposes but can recover the original representation.
+/// The name is borrowed from the field database or owned if deserialized, e.g. from MQTT
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 pub struct NamedValue {
-    name: &'static str,
+    name: Cow<'static, str>,
     value: String,
 }
 
 impl NamedValue {
     /// Create a new `NamedValue`
-    pub fn new(name: &'static str, value: String) -> NamedValue {
-        NamedValue { name, value }
+    #[must_use]
+    pub fn new(name: impl Into<Cow<'static, str>>, value: String) -> NamedValue {
+        NamedValue {
+            name: name.into(),
+            value,
+        }
     }
 
     /// Access `NamedValue.name`
-    pub fn name(&self) -> &'static str {
-        self.name
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
     }
 
     /// Access `NamedValue.value`
+    #[must_use]
     pub fn value(&self) -> &str {
         &self.value
     }
 
     /// Create a `FieldValue` from the `NamedValue`
+    #[must_use]
     pub fn from_field_value(field_value: &FieldValue) -> NamedValue {
         field_value.to_named_value()
     }
@@ -55,9 +63,21 @@ mod tests {
         assert_eq!(testcase, want);
     }
 
+    #[test]
+    fn test_named_value_serde_roundtrip() {
+        let json = serde_json::to_string(&create_test_named_value()).unwrap();
+        assert_eq!(json, r#"{"name":"test","value":"1.5"}"#);
+        // the json buffer is not static, so the name has to be owned
+        let testcase: NamedValue = serde_json::from_str(&json).unwrap();
+        assert_eq!(testcase, create_test_named_value());
+        let owned = NamedValue::new(String::from("test"), "1.5".to_string());
+        assert_eq!(owned, testcase);
+    }
+
     #[test]
     fn test_named_value_access_name() {
-        let testcase = create_test_named_value().name();
+        let named_value = create_test_named_value();
+        let testcase = named_value.name();
         let want = "test";
         assert_eq!(testcase, want);
     }
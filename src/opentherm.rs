@@ -0,0 +1,139 @@
+//! Experimental translation between OpenTherm data ids and BSB fields.
+//!
+//! Only data ids with an `f8.8` value (signed fixed point with 8 fractional bits) and a matching
+//! field in the field database are supported.
+
+use crate::{BsbError, Datatype, Field, FieldValue, Value};
+
+/// OpenTherm data id together with the id of the matching BSB field
+struct Mapping {
+    data_id: u8,
+    field_id: u32,
+}
+
+/// The supported subset of OpenTherm data ids
+const MAPPINGS: &[Mapping] = &[
+    // TrSet: room setpoint
+    Mapping {
+        data_id: 16,
+        field_id: 0x2d3d_058e,
+    },
+    // CH water pressure
+    Mapping {
+        data_id: 18,
+        field_id: 0x053d_19f0,
+    },
+    // Tboiler: boiler flow water temperature
+    Mapping {
+        data_id: 25,
+        field_id: 0x0d3d_0519,
+    },
+    // Tdhw: DHW temperature
+    Mapping {
+        data_id: 26,
+        field_id: 0x313d_052f,
+    },
+    // Toutside: outside temperature
+    Mapping {
+        data_id: 27,
+        field_id: 0x053d_0521,
+    },
+    // Tret: return water temperature
+    Mapping {
+        data_id: 28,
+        field_id: 0x113d_051a,
+    },
+];
+
+/// Number of fractional steps of an `f8.8` value
+const F88_FACTOR: f64 = 256.0;
+
+/// Find the BSB `Field` for an OpenTherm `data_id`
+#[must_use]
+pub fn field_for_data_id(data_id: u8) -> Option<&'static Field> {
+    MAPPINGS
+        .iter()
+        .find(|mapping| mapping.data_id == data_id)
+        .and_then(|mapping| Field::by_id(mapping.field_id))
+}
+
+/// Find the OpenTherm data id for a BSB `field_id`
+#[must_use]
+pub fn data_id_for_field(field_id: u32) -> Option<u8> {
+    MAPPINGS
+        .iter()
+        .find(|mapping| mapping.field_id == field_id)
+        .map(|mapping| mapping.data_id)
+}
+
+/// Translate the `f8.8` `data_value` of an OpenTherm `data_id` into a `FieldValue`
+///
+/// # Errors
+///
+/// Returns `BsbError::UnsupportedField` if the `data_id` is not supported
+pub fn to_field_value(data_id: u8, data_value: u16) -> Result<FieldValue, BsbError> {
+    let field = field_for_data_id(data_id).ok_or(BsbError::UnsupportedField)?;
+    let Datatype::Float(factor) = field.datatype() else {
+        return Err(BsbError::InvalidDatatype);
+    };
+    let value = f64::from(i16::from_be_bytes(data_value.to_be_bytes())) / F88_FACTOR;
+    // an f8.8 value always fits into an f32
+    #[allow(clippy::cast_possible_truncation)]
+    let value = Value::Float {
+        flag: 0,
+        value: value as f32,
+        factor,
+    };
+    FieldValue::new(field.id(), value)
+}
+
+/// Translate a `FieldValue` into an OpenTherm data id and its `f8.8` data value
+///
+/// # Errors
+///
+/// Returns `BsbError::UnsupportedField` if the field has no OpenTherm data id or
+/// `BsbError::InvalidFieldValue` if the value is not a number in the `f8.8` range
+pub fn to_data_value(field_value: &FieldValue) -> Result<(u8, u16), BsbError> {
+    let data_id = data_id_for_field(field_value.field_id()).ok_or(BsbError::UnsupportedField)?;
+    let value = field_value
+        .value()
+        .as_f64()
+        .ok_or(BsbError::InvalidFieldValue)?;
+    let raw = (value * F88_FACTOR).round();
+    if !(f64::from(i16::MIN)..=f64::from(i16::MAX)).contains(&raw) {
+        return Err(BsbError::InvalidFieldValue);
+    }
+    // the range is checked above
+    #[allow(clippy::cast_possible_truncation)]
+    let raw = raw as i16;
+    Ok((data_id, u16::from_be_bytes(raw.to_be_bytes())))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BsbError, FieldValue};
+
+    use super::{to_data_value, to_field_value};
+
+    #[test]
+    fn test_opentherm_to_field_value() {
+        // (<data id>, <data value>, <field value string>)
+        let testcases = vec![
+            (16, 0x1580, "heating_circuit_1_set_point_comfort: 21.5"),
+            (18, 0x0180, "water_pressure: 1.5"),
+            (27, 0xfd00, "outside_temperature: -3"),
+        ];
+        for (data_id, data_value, want) in testcases {
+            let testcase = to_field_value(data_id, data_value).unwrap();
+            assert_eq!(testcase.to_string(), want);
+            assert_eq!(to_data_value(&testcase).unwrap(), (data_id, data_value));
+        }
+    }
+
+    #[test]
+    fn test_opentherm_errors() {
+        assert_eq!(to_field_value(0, 0), Err(BsbError::UnsupportedField));
+        let field_value = FieldValue::from_value_str("1", 0x053d_0236).unwrap();
+        assert_eq!(to_data_value(&field_value), Err(BsbError::UnsupportedField));
+    }
+}
@@ -0,0 +1,109 @@
+use std::sync::Mutex;
+
+/// `BufferPool` keeps a bounded number of byte buffers (e.g. `Frame` payloads or serialization
+/// scratch space) for reuse, so long running applications don't allocate for every `Frame`
+#[derive(Debug)]
+pub struct BufferPool {
+    buffers: Mutex<Vec<Vec<u8>>>,
+    max_buffers: usize,
+}
+
+impl BufferPool {
+    /// Create a new empty `BufferPool` that keeps at most `max_buffers` buffers
+    #[must_use]
+    pub fn new(max_buffers: usize) -> BufferPool {
+        BufferPool {
+            buffers: Mutex::new(Vec::with_capacity(max_buffers)),
+            max_buffers,
+        }
+    }
+
+    /// Take an empty buffer from the pool or allocate a new one if the pool is empty
+    #[must_use]
+    pub fn take(&self) -> Vec<u8> {
+        self.lock().pop().unwrap_or_default()
+    }
+
+    /// Return a `buffer` to the pool. The buffer is cleared but keeps its allocation.
+    /// If the pool is already full, the buffer is dropped
+    pub fn give(&self, mut buffer: Vec<u8>) {
+        let mut buffers = self.lock();
+        if buffers.len() < self.max_buffers {
+            buffer.clear();
+            buffers.push(buffer);
+        }
+    }
+
+    /// Number of buffers that are currently available in the pool
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Check if there are no buffers available in the pool
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Access the buffers, a poisoned lock is recovered as the buffers are always in a valid state
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Vec<u8>>> {
+        self.buffers
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+impl Default for BufferPool {
+    /// Create a `BufferPool` that keeps up to 16 buffers
+    fn default() -> Self {
+        BufferPool::new(16)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Frame;
+
+    use super::BufferPool;
+
+    #[test]
+    fn test_pool_take_empty() {
+        let pool = BufferPool::new(2);
+        let testcase = pool.take();
+        assert!(testcase.is_empty());
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn test_pool_give_take_reuses_allocation() {
+        let pool = BufferPool::new(2);
+        let buffer = Vec::with_capacity(64);
+        pool.give(buffer);
+        assert_eq!(pool.len(), 1);
+        let testcase = pool.take();
+        assert!(testcase.is_empty());
+        assert!(testcase.capacity() >= 64);
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn test_pool_bounded() {
+        let pool = BufferPool::new(1);
+        pool.give(vec![1]);
+        pool.give(vec![2]);
+        assert_eq!(pool.len(), 1);
+    }
+
+    #[test]
+    fn test_pool_serialize_and_recycle_payload() {
+        let pool = BufferPool::default();
+        let frame = Frame::new(66, 0, 7, 0x053d_19f0, vec![0, 0, 15]).unwrap();
+        let mut buffer = pool.take();
+        frame.serialize_into(&mut buffer);
+        assert_eq!(buffer, frame.serialize());
+        pool.give(buffer);
+        pool.give(frame.into_payload());
+        assert_eq!(pool.len(), 2);
+    }
+}
@@ -0,0 +1,293 @@
+//! Experimental decoding of the PPS (Punkt-zu-Punkt-Schnittstelle) of older room units,
+//! e.g. QAA50 and QAA70.
+//!
+//! PPS telegrams have no start of frame and a fixed length of 10 bytes:
+//!
+//! `[<address>, <command>, <data 7 bytes>, <checksum>]`
+//!
+//! The checksum makes the sum of all bytes zero (modulo 256). The command selects one of a fixed
+//! set of registers, its value is stored big-endian in the last two data bytes and decoded into
+//! the same `Value`s as BSB payloads.
+
+use crate::{BsbError, Datatype, ParseErrorKind, ParseResult, Value};
+
+/// Length of every PPS telegram
+pub const PPS_FRAME_LENGTH: usize = 10;
+
+/// Address of telegrams sent by the heater
+pub const HEATER: u8 = 0x17;
+
+/// Address of telegrams sent by the room unit
+pub const ROOM_UNIT: u8 = 0x1d;
+
+/// The registers of the PPS with a known meaning
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum PpsRegister {
+    /// Room temperature set point in comfort mode
+    ComfortSetPoint,
+    /// Room temperature set point in reduced mode
+    ReducedSetPoint,
+    /// Warm water temperature set point
+    WarmwaterSetPoint,
+    /// Room temperature measured by the room unit
+    RoomTemperature,
+    /// Operating mode (0 = automatic, 1 = reduced, 2 = comfort)
+    OperatingMode,
+    /// Presence button (0 = absent, 1 = present)
+    Presence,
+}
+
+impl PpsRegister {
+    /// All registers with a known meaning
+    const ALL: [PpsRegister; 6] = [
+        PpsRegister::ComfortSetPoint,
+        PpsRegister::ReducedSetPoint,
+        PpsRegister::WarmwaterSetPoint,
+        PpsRegister::RoomTemperature,
+        PpsRegister::OperatingMode,
+        PpsRegister::Presence,
+    ];
+
+    /// Find the `PpsRegister` of a `command` byte
+    #[must_use]
+    pub fn from_command(command: u8) -> Option<PpsRegister> {
+        Self::ALL
+            .into_iter()
+            .find(|register| register.command() == command)
+    }
+
+    /// Command byte of the `PpsRegister`
+    #[must_use]
+    pub fn command(&self) -> u8 {
+        match self {
+            PpsRegister::ComfortSetPoint => 0x08,
+            PpsRegister::ReducedSetPoint => 0x09,
+            PpsRegister::WarmwaterSetPoint => 0x0b,
+            PpsRegister::RoomTemperature => 0x28,
+            PpsRegister::OperatingMode => 0x48,
+            PpsRegister::Presence => 0x4c,
+        }
+    }
+
+    /// `Datatype` of the value of the `PpsRegister`
+    #[must_use]
+    pub fn datatype(&self) -> Datatype {
+        match self {
+            PpsRegister::ComfortSetPoint
+            | PpsRegister::ReducedSetPoint
+            | PpsRegister::WarmwaterSetPoint
+            | PpsRegister::RoomTemperature => Datatype::Float(64),
+            PpsRegister::OperatingMode => Datatype::Setting(2),
+            PpsRegister::Presence => Datatype::Setting(1),
+        }
+    }
+}
+
+/// A telegram of the PPS
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PpsFrame {
+    address: u8,
+    command: u8,
+    data: [u8; 7],
+}
+
+impl PpsFrame {
+    /// Create a new `PpsFrame`
+    #[must_use]
+    pub fn new(address: u8, command: u8, data: [u8; 7]) -> PpsFrame {
+        PpsFrame {
+            address,
+            command,
+            data,
+        }
+    }
+
+    /// Create a `PpsFrame` from `address` carrying the `value` of `register`
+    ///
+    /// # Errors
+    ///
+    /// Returns `BsbError::InvalidDatatype` if the `value` does not match the datatype of the
+    /// `register`
+    pub fn from_value(
+        address: u8,
+        register: PpsRegister,
+        value: &Value,
+    ) -> Result<PpsFrame, BsbError> {
+        if value.datatype() != register.datatype() {
+            return Err(BsbError::InvalidDatatype);
+        }
+        // the BSB payload without the flag
+        let payload = value.encode();
+        let mut data = [0; 7];
+        let start = data.len() - (payload.len() - 1);
+        data[start..].copy_from_slice(&payload[1..]);
+        Ok(PpsFrame::new(address, register.command(), data))
+    }
+
+    /// Access `PpsFrame.address`
+    #[must_use]
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    /// Access `PpsFrame.command`
+    #[must_use]
+    pub fn command(&self) -> u8 {
+        self.command
+    }
+
+    /// Access `PpsFrame.data`
+    #[must_use]
+    pub fn data(&self) -> [u8; 7] {
+        self.data
+    }
+
+    /// The `PpsRegister` of the command if its meaning is known
+    #[must_use]
+    pub fn register(&self) -> Option<PpsRegister> {
+        PpsRegister::from_command(self.command)
+    }
+
+    /// Decode the value of the register
+    ///
+    /// # Errors
+    ///
+    /// Returns `BsbError::UnsupportedField` if the register is not known or a decoding error
+    pub fn value(&self) -> Result<Value, BsbError> {
+        let register = self.register().ok_or(BsbError::UnsupportedField)?;
+        // decode the last two data bytes like a BSB payload with the flag 0
+        let payload = match register.datatype() {
+            Datatype::Setting(_) => vec![0, self.data[6]],
+            _ => vec![0, self.data[5], self.data[6]],
+        };
+        Value::decode(&payload, register.datatype())
+    }
+
+    /// Parse the `input` slice into `Ok(remaining_bytes, PpsFrame)`, `Incomplete` or `Failure`.
+    /// Telegrams have no start of frame, on `Failure` the `rest` starts at the next byte so the
+    /// next parse can find the beginning of a telegram
+    #[must_use]
+    pub fn parse(input: &[u8]) -> ParseResult<'_, PpsFrame> {
+        if input.len() < PPS_FRAME_LENGTH {
+            return ParseResult::Incomplete;
+        }
+        let (telegram, rest) = input.split_at(PPS_FRAME_LENGTH);
+        if checksum(telegram) != 0 {
+            let (broken_data, rest) = input.split_at(1);
+            return ParseResult::Failure {
+                rest,
+                broken_data,
+                error: ParseErrorKind::ChecksumError,
+            };
+        }
+        let mut data = [0; 7];
+        data.copy_from_slice(&telegram[2..9]);
+        ParseResult::Ok {
+            rest,
+            frame: PpsFrame::new(telegram[0], telegram[1], data),
+        }
+    }
+
+    /// Serialize the `PpsFrame` into the bytes of the telegram
+    #[must_use]
+    pub fn serialize(&self) -> [u8; PPS_FRAME_LENGTH] {
+        let mut telegram = [0; PPS_FRAME_LENGTH];
+        telegram[0] = self.address;
+        telegram[1] = self.command;
+        telegram[2..9].copy_from_slice(&self.data);
+        telegram[9] = checksum(&telegram[..9]).wrapping_neg();
+        telegram
+    }
+}
+
+/// Sum of all `bytes` modulo 256
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes
+        .iter()
+        .fold(0, |sum: u8, &byte| sum.wrapping_add(byte))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BsbError, ParseErrorKind, ParseResult, Value};
+
+    use super::{PpsFrame, PpsRegister, HEATER, ROOM_UNIT};
+
+    #[test]
+    fn test_pps_frame_value() {
+        // (<register>, <value>)
+        let testcases = vec![
+            (
+                PpsRegister::RoomTemperature,
+                Value::Float {
+                    flag: 0,
+                    value: 21.5,
+                    factor: 64,
+                },
+            ),
+            (
+                PpsRegister::OperatingMode,
+                Value::Setting {
+                    flag: 0,
+                    setting: 2,
+                    max: 2,
+                },
+            ),
+        ];
+        for (register, value) in testcases {
+            let frame = PpsFrame::from_value(ROOM_UNIT, register, &value).unwrap();
+            assert_eq!(frame.register(), Some(register));
+            let bytes = frame.serialize();
+            let ParseResult::Ok { rest, frame } = PpsFrame::parse(&bytes) else {
+                panic!("not a frame")
+            };
+            assert!(rest.is_empty());
+            assert_eq!(frame.value().unwrap(), value);
+        }
+        let room_temperature = PpsFrame::from_value(
+            ROOM_UNIT,
+            PpsRegister::RoomTemperature,
+            &Value::Float {
+                flag: 0,
+                value: 21.5,
+                factor: 64,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            room_temperature.serialize(),
+            [0x1d, 0x28, 0, 0, 0, 0, 0, 0x05, 0x60, 0x56]
+        );
+        assert_eq!(
+            PpsFrame::new(HEATER, 0x7f, [0; 7]).value(),
+            Err(BsbError::UnsupportedField)
+        );
+        assert_eq!(
+            PpsFrame::from_value(
+                HEATER,
+                PpsRegister::Presence,
+                &Value::Number { flag: 0, value: 1 }
+            ),
+            Err(BsbError::InvalidDatatype)
+        );
+    }
+
+    #[test]
+    fn test_pps_frame_parse_resync() {
+        let telegram = PpsFrame::new(HEATER, 0x08, [0, 0, 0, 0, 0, 0x05, 0x60]).serialize();
+        let data = [&[0xff][..], &telegram].concat();
+        let ParseResult::Failure { rest, error, .. } = PpsFrame::parse(&data) else {
+            panic!("not a failure")
+        };
+        assert_eq!(error, ParseErrorKind::ChecksumError);
+        let ParseResult::Ok { frame, .. } = PpsFrame::parse(rest) else {
+            panic!("not a frame")
+        };
+        assert_eq!(frame.register(), Some(PpsRegister::ComfortSetPoint));
+        assert!(matches!(
+            PpsFrame::parse(&telegram[..9]),
+            ParseResult::Incomplete
+        ));
+    }
+}
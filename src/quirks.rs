@@ -0,0 +1,48 @@
+use bitflags::bitflags;
+
+use crate::PacketType;
+
+bitflags! {
+    /// Deviations of controller families from the standard BSB protocol.
+    /// They are honored by `Frame::parse_with`, `Frame::serialize_with`, `Value::decode_with`
+    /// (via `DecodeOptions::with_quirks`) and `Value::encode_with`
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct Quirks: u8 {
+        /// The first two field id bytes are not only swapped in `Set` and `Get` but also in `Ret` frames
+        const SWAPPED_FIELD_ID_ON_RET = 1;
+        /// Value payloads have no leading flag byte (schedules never have one)
+        const NO_FLAG_BYTE = 1 << 1;
+    }
+}
+
+impl Quirks {
+    /// Check if the first two field id bytes are swapped in frames with `packet_type`
+    pub(crate) fn swaps_field_id(self, packet_type: u8) -> bool {
+        packet_type == PacketType::Set as u8
+            || packet_type == PacketType::Get as u8
+            || (self.contains(Quirks::SWAPPED_FIELD_ID_ON_RET)
+                && packet_type == PacketType::Ret as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::PacketType;
+
+    use super::Quirks;
+
+    #[test]
+    fn test_quirks_swaps_field_id() {
+        // (<quirks>, <packet type>, <swapped>)
+        let testcases = vec![
+            (Quirks::empty(), PacketType::Set, true),
+            (Quirks::empty(), PacketType::Get, true),
+            (Quirks::empty(), PacketType::Ret, false),
+            (Quirks::SWAPPED_FIELD_ID_ON_RET, PacketType::Ret, true),
+            (Quirks::SWAPPED_FIELD_ID_ON_RET, PacketType::Info, false),
+        ];
+        for (quirks, packet_type, want) in testcases {
+            assert_eq!(quirks.swaps_field_id(packet_type as u8), want);
+        }
+    }
+}
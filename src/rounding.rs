@@ -0,0 +1,54 @@
+/// `Rounding` defines how a `Value::Float` is rounded to the raw integer of the payload
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rounding {
+    /// Round to the nearest integer, ties to the even integer
+    #[default]
+    HalfEven,
+    /// Round to the nearest integer, ties away from zero
+    HalfAwayFromZero,
+    /// Drop the fractional part
+    TowardZero,
+    /// Round toward negative infinity
+    Down,
+    /// Round toward positive infinity
+    Up,
+}
+
+impl Rounding {
+    /// Round `value` to an integer
+    #[must_use]
+    pub fn apply(self, value: f64) -> f64 {
+        match self {
+            Rounding::HalfEven => value.round_ties_even(),
+            Rounding::HalfAwayFromZero => value.round(),
+            Rounding::TowardZero => value.trunc(),
+            Rounding::Down => value.floor(),
+            Rounding::Up => value.ceil(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Rounding;
+
+    #[test]
+    // integers are exactly representable
+    #[allow(clippy::float_cmp)]
+    fn test_rounding_apply() {
+        // (<rounding>, <value>, <rounded>)
+        let testcases = vec![
+            (Rounding::HalfEven, 2.5, 2.0),
+            (Rounding::HalfEven, 3.5, 4.0),
+            (Rounding::HalfEven, -2.5, -2.0),
+            (Rounding::HalfAwayFromZero, 2.5, 3.0),
+            (Rounding::HalfAwayFromZero, -2.5, -3.0),
+            (Rounding::TowardZero, -2.7, -2.0),
+            (Rounding::Down, -2.2, -3.0),
+            (Rounding::Up, 2.2, 3.0),
+        ];
+        for (rounding, value, want) in testcases {
+            assert_eq!(rounding.apply(value), want);
+        }
+    }
+}
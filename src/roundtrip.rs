@@ -0,0 +1,144 @@
+use crate::{frame::SOF, BsbError, Field, Frame, ParseErrorKind, ParseResult, Value};
+
+/// A component that does not reproduce the original bytes in `verify_roundtrip`.
+/// `offset` is the position of the frame in the raw capture
+#[derive(Debug, PartialEq)]
+pub enum RoundtripIssue {
+    /// The frame could not be parsed at all
+    Parse {
+        offset: usize,
+        error: ParseErrorKind,
+    },
+    /// The serialized `Frame` differs from the original frame bytes
+    Frame {
+        offset: usize,
+        original: Vec<u8>,
+        serialized: Vec<u8>,
+    },
+    /// The payload of a known field cannot be decoded
+    Decode {
+        offset: usize,
+        field_id: u32,
+        error: BsbError,
+    },
+    /// The encoded `Value` differs from the original payload
+    Value {
+        offset: usize,
+        field_id: u32,
+        original: Vec<u8>,
+        encoded: Vec<u8>,
+    },
+}
+
+/// Parse all frames of a raw capture, serialize them again and decode/encode the payloads of
+/// known fields. Every component that loses information is reported as `RoundtripIssue`,
+/// e.g. to validate the crate against captures of new device families
+#[must_use]
+pub fn verify_roundtrip(raw: &[u8]) -> Vec<RoundtripIssue> {
+    let mut issues = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = raw[offset..].iter().position(|&byte| byte == SOF) {
+        let start = offset + start;
+        match Frame::parse(&raw[start..]) {
+            ParseResult::Ok { rest, frame } => {
+                let end = raw.len() - rest.len();
+                verify_frame(&raw[start..end], start, &frame, &mut issues);
+                offset = end;
+            }
+            ParseResult::Incomplete => break,
+            ParseResult::Failure { rest, error, .. } => {
+                issues.push(RoundtripIssue::Parse {
+                    offset: start,
+                    error,
+                });
+                // continue with the next SOF
+                offset = raw.len() - rest.len();
+            }
+        }
+    }
+    issues
+}
+
+/// Compare the `frame` parsed at `offset` with its `original` bytes
+fn verify_frame(original: &[u8], offset: usize, frame: &Frame, issues: &mut Vec<RoundtripIssue>) {
+    let serialized = frame.serialize();
+    if serialized != original {
+        issues.push(RoundtripIssue::Frame {
+            offset,
+            original: original.to_vec(),
+            serialized,
+        });
+    }
+    let Some(field) = Field::by_id(frame.field_id()) else {
+        return;
+    };
+    // requests like `Get` carry no value
+    if frame.payload().is_empty() {
+        return;
+    }
+    match Value::decode(frame.payload(), field.datatype()) {
+        Ok(value) => {
+            let encoded = value.encode();
+            if encoded != frame.payload() {
+                issues.push(RoundtripIssue::Value {
+                    offset,
+                    field_id: frame.field_id(),
+                    original: frame.payload().to_vec(),
+                    encoded,
+                });
+            }
+        }
+        Err(error) => issues.push(RoundtripIssue::Decode {
+            offset,
+            field_id: frame.field_id(),
+            error,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BsbError, Frame, ParseErrorKind};
+
+    use super::{verify_roundtrip, RoundtripIssue};
+
+    #[test]
+    fn test_verify_roundtrip_without_issues() {
+        let raw = [
+            &[0x00][..],
+            &[220, 194, 0, 11, 6, 61, 5, 25, 240, 36, 62],
+            &[220, 128, 66, 14, 7, 5, 61, 25, 240, 0, 0, 15, 29, 116],
+        ]
+        .concat();
+        assert_eq!(verify_roundtrip(&raw), vec![]);
+    }
+
+    #[test]
+    fn test_verify_roundtrip_issues() {
+        // a broken checksum, a schedule with padding after the terminator and an invalid setting
+        let broken = [220, 128, 66, 14, 7, 5, 61, 25, 240, 0, 0, 15, 29, 0];
+        let schedule =
+            Frame::new(66, 0, 7, 0x053d_0a8c, vec![6, 0, 8, 0, 0x98, 0, 0x18, 0, 0]).unwrap();
+        let setting = Frame::new(66, 0, 7, 0x053d_0236, vec![0, 5]).unwrap();
+        let raw = [broken.to_vec(), schedule.serialize(), setting.serialize()].concat();
+        let testcase = verify_roundtrip(&raw);
+        let want = vec![
+            RoundtripIssue::Parse {
+                offset: 0,
+                error: ParseErrorKind::ChecksumError,
+            },
+            RoundtripIssue::Value {
+                offset: 14,
+                field_id: 0x053d_0a8c,
+                original: vec![6, 0, 8, 0, 0x98, 0, 0x18, 0, 0],
+                encoded: vec![6, 0, 8, 0, 0x98, 0, 0x18, 0],
+            },
+            RoundtripIssue::Decode {
+                offset: 34,
+                field_id: 0x053d_0236,
+                error: BsbError::InvalidSetting,
+            },
+        ];
+        assert_eq!(testcase, want);
+    }
+}
@@ -0,0 +1,339 @@
+use chrono::{NaiveTime, Timelike as _, Weekday};
+
+use crate::{BsbError, Datatype, Frame, PacketType, Value};
+
+/// Minutes of a day
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+/// Minutes of a week
+const MINUTES_PER_WEEK: u32 = 7 * MINUTES_PER_DAY;
+
+/// The periods of one day in which a `Value::Schedule` is on, in minutes since midnight
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct DaySchedule {
+    periods: Vec<(u32, u32)>,
+}
+
+impl DaySchedule {
+    /// Create a `DaySchedule` from a `Value::Schedule`
+    ///
+    /// # Errors
+    ///
+    /// Returns `BsbError::InvalidDatatype` if the `value` is not a schedule
+    pub fn from_value(value: &Value) -> Result<DaySchedule, BsbError> {
+        let Value::Schedule(ranges) = value else {
+            return Err(BsbError::InvalidDatatype);
+        };
+        let minutes = |hour: u8, minute: u8| u32::from(hour) * 60 + u32::from(minute);
+        Ok(DaySchedule {
+            periods: ranges
+                .iter()
+                .map(|&(sh, sm, eh, em)| (minutes(sh, sm), minutes(eh, em).min(MINUTES_PER_DAY)))
+                .collect(),
+        })
+    }
+
+    /// Convert the `DaySchedule` back into a `Value::Schedule`, the end of the day is `24:00`
+    #[must_use]
+    pub fn to_value(&self) -> Value {
+        let time = |minute: u32| {
+            (
+                u8::try_from(minute / 60).unwrap_or(24),
+                u8::try_from(minute % 60).unwrap_or(0),
+            )
+        };
+        Value::Schedule(
+            self.periods
+                .iter()
+                .map(|&(start, end)| {
+                    let (sh, sm) = time(start);
+                    let (eh, em) = time(end);
+                    (sh, sm, eh, em)
+                })
+                .collect(),
+        )
+    }
+
+    /// Check if the schedule is on at `minute` since midnight
+    fn is_active_at_minute(&self, minute: u32) -> bool {
+        self.periods
+            .iter()
+            .any(|&(start, end)| start <= minute && minute < end)
+    }
+
+    /// Check if the schedule is on at `time`
+    #[must_use]
+    pub fn is_active_at(&self, time: NaiveTime) -> bool {
+        self.is_active_at_minute(time.hour() * 60 + time.minute())
+    }
+}
+
+/// The `DaySchedule`s of a week, e.g. from the schedule fields of each weekday
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct WeekSchedule {
+    days: [DaySchedule; 7],
+}
+
+impl WeekSchedule {
+    /// Create a new `WeekSchedule` that is never on
+    #[must_use]
+    pub fn new() -> WeekSchedule {
+        WeekSchedule::default()
+    }
+
+    /// Return the `WeekSchedule` with `schedule` for `weekday`
+    #[must_use]
+    pub fn with_day(mut self, weekday: Weekday, schedule: DaySchedule) -> WeekSchedule {
+        self.days[weekday.num_days_from_monday() as usize] = schedule;
+        self
+    }
+
+    /// Return the `WeekSchedule` with `schedule` for all weekdays
+    #[must_use]
+    pub fn every_day(schedule: &DaySchedule) -> WeekSchedule {
+        WeekSchedule {
+            days: std::array::from_fn(|_| schedule.clone()),
+        }
+    }
+
+    /// Access the `DaySchedule` of `weekday`
+    #[must_use]
+    pub fn day(&self, weekday: Weekday) -> &DaySchedule {
+        &self.days[weekday.num_days_from_monday() as usize]
+    }
+
+    /// Field id of the schedule of `weekday`. The bus has one schedule field per weekday with
+    /// consecutive field ids starting with monday at `monday_field_id`, e.g. `0x053d0a8c` for
+    /// the heating circuit 1
+    #[must_use]
+    pub fn field_id(monday_field_id: u32, weekday: Weekday) -> u32 {
+        monday_field_id + weekday.num_days_from_monday()
+    }
+
+    /// Create the `Get` frames requesting the schedules of all weekdays, monday first
+    #[must_use]
+    pub fn get_frames(
+        destination_address: u8,
+        source_address: u8,
+        monday_field_id: u32,
+    ) -> [Frame; 7] {
+        [0, 1, 2, 3, 4, 5, 6]
+            .map(|day| Frame::new_get(destination_address, source_address, monday_field_id + day))
+    }
+
+    /// Assemble a `WeekSchedule` from the `Ret` frames answering the `get_frames`.
+    /// Frames of other fields or packet types are ignored
+    ///
+    /// # Errors
+    ///
+    /// Returns `BsbError::InvalidSchedule` if the schedule of a weekday is missing or
+    /// cannot be decoded
+    pub fn from_frames<'a>(
+        monday_field_id: u32,
+        frames: impl IntoIterator<Item = &'a Frame>,
+    ) -> Result<WeekSchedule, BsbError> {
+        let mut days: [Option<DaySchedule>; 7] = Default::default();
+        for frame in frames {
+            let Some(day) = frame.field_id().checked_sub(monday_field_id) else {
+                continue;
+            };
+            if day >= 7 || !frame.is_packet_type(PacketType::Ret) {
+                continue;
+            }
+            let value = Value::decode(frame.payload(), Datatype::Schedule)?;
+            days[day as usize] = Some(DaySchedule::from_value(&value)?);
+        }
+        let mut week = WeekSchedule::new();
+        for (schedule, day) in week.days.iter_mut().zip(days) {
+            *schedule = day.ok_or(BsbError::InvalidSchedule)?;
+        }
+        Ok(week)
+    }
+
+    /// Create the `Set` frames writing the schedules of all weekdays, monday first
+    ///
+    /// # Errors
+    ///
+    /// Returns `BsbError::InvalidPayloadLength` if a schedule does not fit into a frame
+    pub fn set_frames(
+        &self,
+        destination_address: u8,
+        source_address: u8,
+        monday_field_id: u32,
+    ) -> Result<Vec<Frame>, BsbError> {
+        self.days
+            .iter()
+            .zip(monday_field_id..)
+            .map(|(day, field_id)| {
+                Frame::new_set(
+                    destination_address,
+                    source_address,
+                    field_id,
+                    day.to_value().encode_for(PacketType::Set)?,
+                )
+            })
+            .collect()
+    }
+
+    /// Check if the schedule is on at `time` on `weekday`
+    #[must_use]
+    pub fn is_active_at(&self, weekday: Weekday, time: NaiveTime) -> bool {
+        self.day(weekday).is_active_at(time)
+    }
+
+    /// Find the next time after `time` on `weekday` at which the schedule switches.
+    /// Returns the weekday and time of the switch and if it switches on,
+    /// or `None` if the schedule never switches
+    ///
+    /// # Panics
+    ///
+    /// Does not panic, all minutes of a week are a valid weekday and time
+    #[must_use]
+    pub fn next_switch(
+        &self,
+        weekday: Weekday,
+        time: NaiveTime,
+    ) -> Option<(Weekday, NaiveTime, bool)> {
+        let now =
+            weekday.num_days_from_monday() * MINUTES_PER_DAY + time.hour() * 60 + time.minute();
+        let mut candidates = self
+            .days
+            .iter()
+            .zip(0..)
+            .flat_map(|(day, index)| {
+                day.periods
+                    .iter()
+                    .flat_map(move |&(start, end)| [start, end])
+                    .map(move |minute| (index * MINUTES_PER_DAY + minute) % MINUTES_PER_WEEK)
+            })
+            .collect::<Vec<_>>();
+        // order the candidates by their distance to now, a switch at now is in the past
+        candidates.sort_by_key(|&minute| (minute + MINUTES_PER_WEEK - now - 1) % MINUTES_PER_WEEK);
+        candidates.into_iter().find_map(|minute| {
+            let before =
+                self.is_active_at_week_minute((minute + MINUTES_PER_WEEK - 1) % MINUTES_PER_WEEK);
+            let after = self.is_active_at_week_minute(minute);
+            (before != after).then(|| {
+                let day = minute / MINUTES_PER_DAY;
+                let minute = minute % MINUTES_PER_DAY;
+                let weekday = u8::try_from(day)
+                    .ok()
+                    .and_then(|day| Weekday::try_from(day).ok())
+                    .expect("days of a week are a valid weekday");
+                let time = NaiveTime::from_hms_opt(minute / 60, minute % 60, 0)
+                    .expect("minutes of a day are a valid time");
+                (weekday, time, after)
+            })
+        })
+    }
+
+    /// Check if the schedule is on at `minute` since monday midnight
+    fn is_active_at_week_minute(&self, minute: u32) -> bool {
+        self.days[(minute / MINUTES_PER_DAY) as usize].is_active_at_minute(minute % MINUTES_PER_DAY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveTime, Weekday};
+
+    use crate::{BsbError, Datatype, Frame, PacketType, Value};
+
+    use super::{DaySchedule, WeekSchedule};
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    fn create_day_schedule(s: &str) -> DaySchedule {
+        DaySchedule::from_value(&Value::from_str(s, Datatype::Schedule).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_day_schedule_is_active_at() {
+        let schedule = create_day_schedule("6:0-8:0,16:30-24:0");
+        // (<time>, <active>)
+        let testcases = vec![
+            (time(5, 59), false),
+            (time(6, 0), true),
+            (time(8, 0), false),
+            (time(16, 30), true),
+            (time(23, 59), true),
+        ];
+        for (time, want) in testcases {
+            assert_eq!(schedule.is_active_at(time), want);
+        }
+        assert_eq!(
+            DaySchedule::from_value(&Value::Number { flag: 0, value: 1 }),
+            Err(BsbError::InvalidDatatype)
+        );
+    }
+
+    #[test]
+    fn test_week_schedule_next_switch() {
+        let workday = create_day_schedule("6:0-8:0,16:30-24:0");
+        let week = WeekSchedule::every_day(&workday)
+            .with_day(Weekday::Sat, create_day_schedule("0:0-24:0"))
+            .with_day(Weekday::Sun, DaySchedule::default());
+        assert!(week.is_active_at(Weekday::Sat, time(3, 0)));
+        // (<weekday>, <time>, <next switch>)
+        let testcases = vec![
+            (Weekday::Mon, time(5, 0), (Weekday::Mon, time(6, 0), true)),
+            (Weekday::Mon, time(6, 0), (Weekday::Mon, time(8, 0), false)),
+            (Weekday::Mon, time(20, 0), (Weekday::Tue, time(0, 0), false)),
+            // friday evening runs into the saturday without a switch
+            (Weekday::Fri, time(20, 0), (Weekday::Sun, time(0, 0), false)),
+            (Weekday::Sun, time(12, 0), (Weekday::Mon, time(6, 0), true)),
+        ];
+        for (weekday, time, want) in testcases {
+            assert_eq!(week.next_switch(weekday, time), Some(want));
+        }
+        assert_eq!(
+            WeekSchedule::new().next_switch(Weekday::Mon, time(0, 0)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_week_schedule_frames() {
+        let week = WeekSchedule::every_day(&create_day_schedule("6:0-8:0,16:30-24:0"))
+            .with_day(Weekday::Sun, create_day_schedule("8:0-22:0"));
+        let gets = WeekSchedule::get_frames(0, 66, 0x053d_0a8c);
+        assert_eq!(gets[0], Frame::new_get(0, 66, 0x053d_0a8c));
+        assert_eq!(gets[6].field_id(), 0x053d_0a92);
+        assert_eq!(
+            WeekSchedule::field_id(0x053d_0a8c, Weekday::Sun),
+            0x053d_0a92
+        );
+        let sets = week.set_frames(0, 66, 0x053d_0a8c).unwrap();
+        assert_eq!(sets.len(), 7);
+        assert_eq!(
+            sets[6].payload(),
+            [8, 0, 22, 0, 0x98, 0, 0x18, 0].as_slice()
+        );
+        // the answers arrive in any order between unrelated frames
+        let mut responses = gets
+            .iter()
+            .zip(&sets)
+            .map(|(get, set)| {
+                Frame::new(
+                    66,
+                    0,
+                    PacketType::Ret.into(),
+                    get.field_id(),
+                    set.payload().to_vec(),
+                )
+                .unwrap()
+            })
+            .rev()
+            .collect::<Vec<_>>();
+        responses.push(Frame::new(66, 0, PacketType::Ret.into(), 0x053d_0a93, vec![]).unwrap());
+        responses.push(Frame::new_get(0, 66, 0x053d_0a8c));
+        let testcase = WeekSchedule::from_frames(0x053d_0a8c, &responses).unwrap();
+        assert_eq!(testcase, week);
+        assert_eq!(
+            WeekSchedule::from_frames(0x053d_0a8c, &responses[1..]),
+            Err(BsbError::InvalidSchedule)
+        );
+    }
+}
@@ -0,0 +1,296 @@
+//! Correlation of requests with their responses, independent of the transport and the runtime
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    task::{Context, Poll, Waker},
+    time::{Duration, Instant},
+};
+
+use thiserror::Error;
+
+use crate::{
+    bus::{Transaction, TransactionId, TransactionTracker},
+    Frame, PacketType,
+};
+
+/// Errors of a request in a `Session`
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum SessionError {
+    #[error("the device did not acknowledge the request")]
+    Nack(Frame),
+    #[error("the device answered with an error")]
+    Rejected(Frame),
+    #[error("no response in time")]
+    Timeout,
+    #[error("the session was closed")]
+    Closed,
+}
+
+/// Result of a request that is shared between the `Session` and the `ResponseFuture`
+#[derive(Debug, Default)]
+struct Slot {
+    result: Option<Result<Frame, SessionError>>,
+    waker: Option<Waker>,
+}
+
+/// Access a `Slot`, a poisoned lock is recovered as the slot is always valid
+fn lock(slot: &Mutex<Slot>) -> MutexGuard<'_, Slot> {
+    slot.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Store the `result` in the `slot` and wake up the waiting `ResponseFuture`
+fn complete(slot: &Mutex<Slot>, result: Result<Frame, SessionError>) {
+    let mut slot = lock(slot);
+    slot.result = Some(result);
+    if let Some(waker) = slot.waker.take() {
+        waker.wake();
+    }
+}
+
+/// `Future` of the response to a request of a `Session`.
+/// It resolves with the `Ret` or `Ack` frame or a `SessionError`
+#[derive(Debug)]
+pub struct ResponseFuture {
+    id: TransactionId,
+    slot: Arc<Mutex<Slot>>,
+}
+
+impl ResponseFuture {
+    /// Access `ResponseFuture.id` of the request
+    #[must_use]
+    pub fn id(&self) -> TransactionId {
+        self.id
+    }
+}
+
+impl Future for ResponseFuture {
+    type Output = Result<Frame, SessionError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut slot = lock(&self.slot);
+        if let Some(result) = slot.result.take() {
+            return Poll::Ready(result);
+        }
+        slot.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+/// A request that waits for its response
+#[derive(Debug)]
+struct Pending {
+    deadline: Instant,
+    slot: Arc<Mutex<Slot>>,
+}
+
+/// `Session` correlates sent `Get` and `Set` requests with the received `Ret`, `Ack`, `Nack` and
+/// `Error` frames and resolves the `ResponseFuture` of each request.
+/// The caller writes the requests to the bus, feeds every received frame into the session and
+/// calls `Session::time_out` regularly, e.g. at `Session::next_deadline`.
+/// All methods take the current time so the session can be driven by any clock and runtime
+#[derive(Debug, Default)]
+pub struct Session {
+    tracker: TransactionTracker,
+    pending: BTreeMap<TransactionId, Pending>,
+}
+
+impl Session {
+    /// Create a new `Session` without pending requests
+    #[must_use]
+    pub fn new() -> Session {
+        Session::default()
+    }
+
+    /// Track a `request` that was sent at `now` and fails with `SessionError::Timeout` if it is
+    /// not answered within `timeout`
+    pub fn request(&mut self, request: Frame, timeout: Duration, now: Instant) -> ResponseFuture {
+        let id = self.tracker.begin(request, now);
+        let slot = Arc::new(Mutex::new(Slot::default()));
+        self.pending.insert(
+            id,
+            Pending {
+                deadline: now + timeout,
+                slot: Arc::clone(&slot),
+            },
+        );
+        ResponseFuture { id, slot }
+    }
+
+    /// Record that the pending request `id` was sent again at `now` and restart its `timeout`.
+    /// The `ResponseFuture` of the request stays valid. Returns `false` if the request is not
+    /// pending anymore, e.g. because it was answered in the meantime
+    pub fn retried(&mut self, id: TransactionId, timeout: Duration, now: Instant) -> bool {
+        let Some(pending) = self.pending.get_mut(&id) else {
+            return false;
+        };
+        pending.deadline = now + timeout;
+        self.tracker.retried(id, now)
+    }
+
+    /// Match a received `frame` with the oldest pending request it answers and resolve it.
+    /// Returns `None` for frames that answer no pending request, e.g. duplicate `Ret` frames
+    /// or interleaved traffic of other devices
+    pub fn receive(&mut self, frame: &Frame, now: Instant) -> Option<TransactionId> {
+        let id = self.tracker.answer(frame, now)?;
+        self.tracker.take_finished();
+        let pending = self.pending.remove(&id)?;
+        let result = match PacketType::from_repr(frame.packet_type()) {
+            Some(PacketType::Nack) => Err(SessionError::Nack(frame.clone())),
+            Some(PacketType::Error) => Err(SessionError::Rejected(frame.clone())),
+            _ => Ok(frame.clone()),
+        };
+        complete(&pending.slot, result);
+        Some(id)
+    }
+
+    /// Fail all pending requests whose deadline passed at `now`. Returns their ids
+    pub fn time_out(&mut self, now: Instant) -> Vec<TransactionId> {
+        let expired = self
+            .pending
+            .iter()
+            .filter(|(_, pending)| pending.deadline <= now)
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        for id in &expired {
+            self.expire(*id, now);
+        }
+        expired
+    }
+
+    /// Fail the pending request `id` with `SessionError::Timeout` at `now` regardless of its
+    /// deadline, e.g. when the caller gives up. Returns `false` if the request is not pending
+    pub fn expire(&mut self, id: TransactionId, now: Instant) -> bool {
+        let Some(pending) = self.pending.remove(&id) else {
+            return false;
+        };
+        self.tracker.expire(id, now);
+        self.tracker.take_finished();
+        complete(&pending.slot, Err(SessionError::Timeout));
+        true
+    }
+
+    /// Earliest deadline of all pending requests, `None` if there is no pending request
+    #[must_use]
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.pending.values().map(|pending| pending.deadline).min()
+    }
+
+    /// Number of requests that wait for a response
+    #[must_use]
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Check if no request waits for a response
+    #[must_use]
+    pub fn is_idle(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Iterate over the `Transaction`s of all requests that wait for a response, oldest first
+    pub fn in_flight(&self) -> impl Iterator<Item = &Transaction> {
+        self.tracker.in_flight()
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        // nobody can answer the pending requests anymore
+        for pending in self.pending.values() {
+            complete(&pending.slot, Err(SessionError::Closed));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::{bus::TransactionState, Frame, PacketType};
+
+    use super::{Session, SessionError};
+
+    #[tokio::test]
+    async fn test_session_receive() {
+        let now = Instant::now();
+        let timeout = Duration::from_secs(1);
+        let mut session = Session::new();
+        let get = session.request(Frame::new_get(0, 66, 0x053d_19f0), timeout, now);
+        let set = session.request(
+            Frame::new_set(0, 66, 0x2d3d_058e, vec![1, 5, 0x60]).unwrap(),
+            timeout,
+            now,
+        );
+        assert_eq!(session.pending(), 2);
+        // (<frame>, <answered>)
+        let ret = Frame::new(66, 0, PacketType::Ret.into(), 0x053d_19f0, vec![0, 0, 15]).unwrap();
+        let nack = Frame::new(66, 0, PacketType::Nack.into(), 0x2d3d_058e, vec![]).unwrap();
+        let testcases = vec![
+            // traffic of another device
+            (
+                Frame::new(66, 10, PacketType::Ret.into(), 0x053d_19f0, vec![0, 0, 15]).unwrap(),
+                None,
+            ),
+            (ret.clone(), Some(get.id())),
+            // duplicate answer
+            (ret.clone(), None),
+            (nack.clone(), Some(set.id())),
+        ];
+        for (frame, want) in testcases {
+            assert_eq!(session.receive(&frame, now), want);
+        }
+        assert!(session.is_idle());
+        assert_eq!(get.await, Ok(ret));
+        assert_eq!(set.await, Err(SessionError::Nack(nack)));
+    }
+
+    #[tokio::test]
+    async fn test_session_time_out() {
+        let now = Instant::now();
+        let mut session = Session::new();
+        let short = session.request(
+            Frame::new_get(0, 66, 0x053d_19f0),
+            Duration::from_millis(100),
+            now,
+        );
+        let long = session.request(
+            Frame::new_get(0, 66, 0x053d_0236),
+            Duration::from_secs(1),
+            now,
+        );
+        assert_eq!(
+            session.next_deadline(),
+            Some(now + Duration::from_millis(100))
+        );
+        assert_eq!(
+            session.time_out(now + Duration::from_millis(500)),
+            [short.id()]
+        );
+        assert_eq!(short.await, Err(SessionError::Timeout));
+        // a late answer is not matched anymore
+        let late = Frame::new(66, 0, PacketType::Ret.into(), 0x053d_19f0, vec![0, 0, 15]).unwrap();
+        assert_eq!(session.receive(&late, now + Duration::from_secs(2)), None);
+        drop(session);
+        assert_eq!(long.await, Err(SessionError::Closed));
+    }
+
+    #[tokio::test]
+    async fn test_session_retried() {
+        let now = Instant::now();
+        let timeout = Duration::from_secs(1);
+        let mut session = Session::new();
+        let get = session.request(Frame::new_get(0, 66, 0x053d_19f0), timeout, now);
+        let later = now + Duration::from_secs(2);
+        assert!(session.retried(get.id(), timeout, later));
+        assert_eq!(session.next_deadline(), Some(later + timeout));
+        let transaction = session.in_flight().next().unwrap();
+        assert_eq!(transaction.attempts(), 2);
+        assert_eq!(transaction.state(), TransactionState::Retried);
+        assert!(session.expire(get.id(), later));
+        assert!(!session.retried(get.id(), timeout, later));
+        assert_eq!(session.in_flight().count(), 0);
+        assert_eq!(get.await, Err(SessionError::Timeout));
+    }
+}
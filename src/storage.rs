@@ -0,0 +1,248 @@
+use std::convert::Infallible;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::DecodedEvent;
+
+/// A `DecodedEvent` together with the time it was received
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredEvent {
+    timestamp: DateTime<Utc>,
+    event: DecodedEvent,
+}
+
+impl StoredEvent {
+    /// Create a new `StoredEvent` for an `event` received at `timestamp`
+    #[must_use]
+    pub fn new(timestamp: DateTime<Utc>, event: DecodedEvent) -> StoredEvent {
+        StoredEvent { timestamp, event }
+    }
+
+    /// Access `StoredEvent.timestamp`
+    #[must_use]
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        self.timestamp
+    }
+
+    /// Access `StoredEvent.event`
+    #[must_use]
+    pub fn event(&self) -> &DecodedEvent {
+        &self.event
+    }
+}
+
+/// Filter for `Storage::query`. Without any restriction all events match
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct EventQuery {
+    field_id: Option<u32>,
+    bus: Option<String>,
+    from: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+}
+
+impl EventQuery {
+    /// Create a new `EventQuery` that matches all events
+    #[must_use]
+    pub fn new() -> EventQuery {
+        EventQuery::default()
+    }
+
+    /// Return the `EventQuery` restricted to events of `field_id`
+    #[must_use]
+    pub fn with_field_id(mut self, field_id: u32) -> EventQuery {
+        self.field_id = Some(field_id);
+        self
+    }
+
+    /// Return the `EventQuery` restricted to events tagged with `bus`
+    #[must_use]
+    pub fn with_bus(mut self, bus: impl Into<String>) -> EventQuery {
+        self.bus = Some(bus.into());
+        self
+    }
+
+    /// Return the `EventQuery` restricted to events received at or after `from`
+    #[must_use]
+    pub fn with_from(mut self, from: DateTime<Utc>) -> EventQuery {
+        self.from = Some(from);
+        self
+    }
+
+    /// Return the `EventQuery` restricted to events received before `until`
+    #[must_use]
+    pub fn with_until(mut self, until: DateTime<Utc>) -> EventQuery {
+        self.until = Some(until);
+        self
+    }
+
+    /// Access `EventQuery.field_id`
+    #[must_use]
+    pub fn field_id(&self) -> Option<u32> {
+        self.field_id
+    }
+
+    /// Access `EventQuery.bus`
+    #[must_use]
+    pub fn bus(&self) -> Option<&str> {
+        self.bus.as_deref()
+    }
+
+    /// Access `EventQuery.from`
+    #[must_use]
+    pub fn from(&self) -> Option<DateTime<Utc>> {
+        self.from
+    }
+
+    /// Access `EventQuery.until`
+    #[must_use]
+    pub fn until(&self) -> Option<DateTime<Utc>> {
+        self.until
+    }
+
+    /// Check if the `stored` event matches this query, e.g. for backends that filter in memory
+    #[must_use]
+    pub fn matches(&self, stored: &StoredEvent) -> bool {
+        self.field_id
+            .is_none_or(|field_id| stored.event.field_id() == format!("0x{field_id:08x}"))
+            && self
+                .bus
+                .as_deref()
+                .is_none_or(|bus| stored.event.bus() == Some(bus))
+            && self.from.is_none_or(|from| stored.timestamp >= from)
+            && self.until.is_none_or(|until| stored.timestamp < until)
+    }
+}
+
+/// Archive for decoded events. Implementations can be plugged into applications
+/// without changing how the events are produced
+pub trait Storage {
+    /// Error of the storage backend
+    type Error: std::error::Error;
+
+    /// Append an `event` received at `timestamp`
+    ///
+    /// # Errors
+    ///
+    /// Returns `Self::Error` if the event cannot be stored
+    fn append(&mut self, timestamp: DateTime<Utc>, event: DecodedEvent) -> Result<(), Self::Error>;
+
+    /// Retrieve all events that match the `query` ordered by their timestamp
+    ///
+    /// # Errors
+    ///
+    /// Returns `Self::Error` if the events cannot be retrieved
+    fn query(&self, query: &EventQuery) -> Result<Vec<StoredEvent>, Self::Error>;
+}
+
+/// `Storage` that keeps all events in memory, e.g. for tests or short running captures
+#[derive(Debug, Default, Clone)]
+pub struct MemoryStorage {
+    events: Vec<StoredEvent>,
+}
+
+impl MemoryStorage {
+    /// Create a new empty `MemoryStorage`
+    #[must_use]
+    pub fn new() -> MemoryStorage {
+        MemoryStorage::default()
+    }
+
+    /// Number of stored events
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Check if no event is stored
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+impl Storage for MemoryStorage {
+    type Error = Infallible;
+
+    fn append(&mut self, timestamp: DateTime<Utc>, event: DecodedEvent) -> Result<(), Self::Error> {
+        // keep the events ordered even if they are appended out of order
+        let position = self
+            .events
+            .partition_point(|stored| stored.timestamp <= timestamp);
+        self.events
+            .insert(position, StoredEvent::new(timestamp, event));
+        Ok(())
+    }
+
+    fn query(&self, query: &EventQuery) -> Result<Vec<StoredEvent>, Self::Error> {
+        Ok(self
+            .events
+            .iter()
+            .filter(|stored| query.matches(stored))
+            .cloned()
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Utc};
+
+    use crate::{DecodedEvent, Frame};
+
+    use super::{EventQuery, MemoryStorage, Storage as _};
+
+    /// Create a `DecodedEvent` for `field_id`
+    fn create_event(field_id: u32) -> DecodedEvent {
+        DecodedEvent::from_frame(&Frame::new(66, 0, 7, field_id, vec![0, 0, 15]).unwrap())
+    }
+
+    /// Create a timestamp `seconds` after the unix epoch
+    fn create_timestamp(seconds: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(seconds, 0).unwrap()
+    }
+
+    #[test]
+    fn test_memory_storage_query() {
+        let mut storage = MemoryStorage::new();
+        storage
+            .append(create_timestamp(20), create_event(0x053d_19f0))
+            .unwrap();
+        storage
+            .append(create_timestamp(10), create_event(0x053d_19f0))
+            .unwrap();
+        storage
+            .append(
+                create_timestamp(15),
+                create_event(0x1234_5678).with_bus("lpb"),
+            )
+            .unwrap();
+        assert_eq!(storage.len(), 3);
+        // (<query>, <timestamps of the result>)
+        let testcases = vec![
+            (EventQuery::new(), vec![10, 15, 20]),
+            (EventQuery::new().with_field_id(0x053d_19f0), vec![10, 20]),
+            (
+                EventQuery::new().with_from(create_timestamp(15)),
+                vec![15, 20],
+            ),
+            (EventQuery::new().with_until(create_timestamp(15)), vec![10]),
+            (EventQuery::new().with_bus("lpb"), vec![15]),
+            (
+                EventQuery::new()
+                    .with_field_id(0x1234_5678)
+                    .with_until(create_timestamp(15)),
+                vec![],
+            ),
+        ];
+        for (query, want) in testcases {
+            let testcase = storage
+                .query(&query)
+                .unwrap()
+                .iter()
+                .map(|stored| stored.timestamp().timestamp())
+                .collect::<Vec<_>>();
+            assert_eq!(testcase, want);
+        }
+    }
+}
@@ -0,0 +1,465 @@
+//! Generators for property based tests of the parser and the codecs.
+//!
+//! `arbitrary::Arbitrary` is implemented for `Datatype`, `Value`, `FieldValue` and `Frame`
+//! (e.g. for fuzzing) and `proptest` strategies are provided by the functions in this module.
+//! All generated values are valid, i.e. they survive an encode/decode or serialize/parse round trip.
+//! `FrameGenerator` creates reproducible streams of valid and corrupted frames.
+
+use arbitrary::{Arbitrary, Unstructured};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use proptest::{
+    collection::vec,
+    prelude::{any, prop_oneof, Just, Strategy},
+    sample::select,
+};
+
+use crate::{
+    value::is_nullable, Datatype, Field, FieldValue, Frame, Value, MAX_FRAME_LENGTH,
+    MAX_PAYLOAD_LENGTH,
+};
+
+/// Maximum number of time ranges of a generated schedule
+const MAX_SCHEDULE_RANGES: usize = 3;
+
+/// Maximum length of a generated string, the flag and the terminator are part of the payload
+const MAX_STRING_LENGTH: usize = MAX_PAYLOAD_LENGTH - 2;
+
+/// Create a `String` value from ASCII `bytes` without null bytes
+fn string(flag: u8, bytes: Vec<u8>) -> Value {
+    Value::String {
+        flag,
+        value: String::from_utf8(bytes).expect("ASCII is valid UTF-8"),
+    }
+}
+
+/// Create a `NaiveDateTime` from the number of days since 1900-01-01 and the seconds of the day
+fn naive_datetime(days: u32, seconds: u32) -> NaiveDateTime {
+    let date = NaiveDate::from_ymd_opt(1900, 1, 1).unwrap() + chrono::Days::new(u64::from(days));
+    let time = NaiveTime::from_num_seconds_from_midnight_opt(seconds, 0).unwrap();
+    NaiveDateTime::new(date, time)
+}
+
+/// Create a `DateTime` from the number of days since 1900-01-01 and the seconds of the day
+fn datetime(days: u32, seconds: u32, flag: u8, trailing_flag: u8) -> Value {
+    Value::DateTime {
+        flag,
+        datetime: naive_datetime(days, seconds),
+        trailing_flag,
+    }
+}
+
+/// Number of days from 1900-01-01 that can be encoded in a `DateTime` payload (year 1900 - 2155)
+const MAX_DATETIME_DAYS: u32 = 93_500;
+
+/// Seconds of a day
+const MAX_SECONDS: u32 = 86_399;
+
+impl<'a> Arbitrary<'a> for Datatype {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(match u.int_in_range(0..=8)? {
+            0 => Datatype::Setting(u.arbitrary()?),
+            1 => Datatype::Number,
+            2 => Datatype::SignedNumber,
+            3 => Datatype::Float(u.int_in_range(1..=u8::MAX)?),
+            4 => Datatype::DateTime,
+            5 => Datatype::Schedule,
+            6 => Datatype::Trigger(u.arbitrary()?),
+            7 => Datatype::String,
+            _ => Datatype::ErrorRecord,
+        })
+    }
+}
+
+/// Create an arbitrary `Value` of `datatype`
+fn arbitrary_value(u: &mut Unstructured<'_>, datatype: Datatype) -> arbitrary::Result<Value> {
+    let flag = u.arbitrary()?;
+    Ok(match datatype {
+        Datatype::Setting(max) => Value::Setting {
+            flag,
+            setting: u.int_in_range(0..=max)?,
+            max,
+        },
+        Datatype::Number => Value::Number {
+            flag,
+            value: u.arbitrary()?,
+        },
+        Datatype::SignedNumber => Value::SignedNumber {
+            flag,
+            value: u.arbitrary()?,
+        },
+        // the raw value `i16::MIN` is the sensor error `Value::Invalid`
+        Datatype::Float(factor) if u.ratio(1, 16)? => Value::Invalid { flag, factor },
+        Datatype::Float(factor) => Value::Float {
+            flag,
+            value: f32::from(u.int_in_range(-i16::MAX..=i16::MAX)?) / f32::from(factor),
+            factor,
+        },
+        Datatype::DateTime => datetime(
+            u.int_in_range(0..=MAX_DATETIME_DAYS)?,
+            u.int_in_range(0..=MAX_SECONDS)?,
+            flag,
+            u.arbitrary()?,
+        ),
+        Datatype::Schedule => {
+            let mut ranges = Vec::new();
+            for _ in 0..u.int_in_range(0..=MAX_SCHEDULE_RANGES)? {
+                ranges.push((
+                    u.int_in_range(0..=24)?,
+                    u.int_in_range(0..=59)?,
+                    u.int_in_range(0..=24)?,
+                    u.int_in_range(0..=59)?,
+                ));
+            }
+            Value::Schedule(ranges)
+        }
+        Datatype::Trigger(token) => Value::Trigger { flag, token },
+        Datatype::String => {
+            let mut bytes = Vec::new();
+            for _ in 0..u.int_in_range(0..=MAX_STRING_LENGTH)? {
+                bytes.push(u.int_in_range(1..=0x7f)?);
+            }
+            string(flag, bytes)
+        }
+        Datatype::ErrorRecord => Value::ErrorRecord {
+            flag,
+            code: u.arbitrary()?,
+            datetime: naive_datetime(
+                u.int_in_range(0..=MAX_DATETIME_DAYS)?,
+                u.int_in_range(0..=MAX_SECONDS)?,
+            ),
+        },
+    })
+}
+
+impl<'a> Arbitrary<'a> for Value {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let datatype = u.arbitrary()?;
+        arbitrary_value(u, datatype)
+    }
+}
+
+/// Turn a numeric `value` with the flag 1 into `Value::Unset` as it is decoded from an answer
+fn answer_value(value: Value) -> Value {
+    let datatype = value.datatype();
+    match value.flag() {
+        Some(flag @ 1) if is_nullable(datatype) => Value::Unset { flag, datatype },
+        _ => value,
+    }
+}
+
+impl<'a> Arbitrary<'a> for FieldValue {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let fields = Field::iter().map(|(_, field)| field).collect::<Vec<_>>();
+        let field = u.choose(&fields)?;
+        let value = answer_value(arbitrary_value(u, field.datatype())?);
+        Ok(FieldValue::new(field.id(), value).expect("the field is part of the database"))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Frame {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let length = u.int_in_range(0..=MAX_PAYLOAD_LENGTH)?;
+        Ok(Frame::new_unchecked(
+            u.arbitrary()?,
+            u.arbitrary()?,
+            u.arbitrary()?,
+            u.arbitrary()?,
+            u.bytes(length)?.to_vec(),
+        ))
+    }
+}
+
+/// `proptest` strategy for any `Datatype`
+pub fn datatype() -> impl Strategy<Value = Datatype> {
+    prop_oneof![
+        any::<u8>().prop_map(Datatype::Setting),
+        Just(Datatype::Number),
+        Just(Datatype::SignedNumber),
+        (1..=u8::MAX).prop_map(Datatype::Float),
+        Just(Datatype::DateTime),
+        Just(Datatype::Schedule),
+        any::<u8>().prop_map(Datatype::Trigger),
+        Just(Datatype::String),
+        Just(Datatype::ErrorRecord),
+    ]
+}
+
+/// `proptest` strategy for a `Value` of `datatype`
+pub fn value_of(datatype: Datatype) -> impl Strategy<Value = Value> {
+    let flag = any::<u8>();
+    match datatype {
+        Datatype::Setting(max) => (flag, 0..=max)
+            .prop_map(move |(flag, setting)| Value::Setting { flag, setting, max })
+            .boxed(),
+        Datatype::Number => (flag, any::<u16>())
+            .prop_map(|(flag, value)| Value::Number { flag, value })
+            .boxed(),
+        Datatype::SignedNumber => (flag, any::<i16>())
+            .prop_map(|(flag, value)| Value::SignedNumber { flag, value })
+            .boxed(),
+        Datatype::Float(factor) => prop_oneof![
+            15 => (flag, -i16::MAX..=i16::MAX).prop_map(move |(flag, raw)| Value::Float {
+                flag,
+                value: f32::from(raw) / f32::from(factor),
+                factor,
+            }),
+            1 => any::<u8>().prop_map(move |flag| Value::Invalid { flag, factor }),
+        ]
+        .boxed(),
+        Datatype::DateTime => (flag, 0..=MAX_DATETIME_DAYS, 0..=MAX_SECONDS, any::<u8>())
+            .prop_map(|(flag, days, seconds, trailing_flag)| {
+                datetime(days, seconds, flag, trailing_flag)
+            })
+            .boxed(),
+        Datatype::Schedule => vec(
+            (0..=24u8, 0..=59u8, 0..=24u8, 0..=59u8),
+            0..=MAX_SCHEDULE_RANGES,
+        )
+        .prop_map(Value::Schedule)
+        .boxed(),
+        Datatype::Trigger(token) => flag
+            .prop_map(move |flag| Value::Trigger { flag, token })
+            .boxed(),
+        Datatype::String => (flag, vec(1..=0x7fu8, 0..=MAX_STRING_LENGTH))
+            .prop_map(|(flag, bytes)| string(flag, bytes))
+            .boxed(),
+        Datatype::ErrorRecord => (flag, any::<u16>(), 0..=MAX_DATETIME_DAYS, 0..=MAX_SECONDS)
+            .prop_map(|(flag, code, days, seconds)| Value::ErrorRecord {
+                flag,
+                code,
+                datetime: naive_datetime(days, seconds),
+            })
+            .boxed(),
+    }
+}
+
+/// `proptest` strategy for any `Value`
+pub fn value() -> impl Strategy<Value = Value> {
+    datatype().prop_flat_map(value_of)
+}
+
+/// `proptest` strategy for a `FieldValue` of any `Field` of the database.
+/// The values survive a round trip through a `Ret` frame
+///
+/// # Panics
+///
+/// Does not panic, only fields of the database are selected
+pub fn field_value() -> impl Strategy<Value = FieldValue> {
+    let fields = Field::iter().map(|(_, field)| field).collect::<Vec<_>>();
+    select(fields).prop_flat_map(|field| {
+        value_of(field.datatype()).prop_map(|value| {
+            FieldValue::new(field.id(), answer_value(value))
+                .expect("the field is part of the database")
+        })
+    })
+}
+
+/// `proptest` strategy for any valid `Frame`
+pub fn frame() -> impl Strategy<Value = Frame> {
+    (
+        any::<u8>(),
+        any::<u8>(),
+        any::<u8>(),
+        any::<u32>(),
+        vec(any::<u8>(), 0..=MAX_PAYLOAD_LENGTH),
+    )
+        .prop_map(
+            |(destination_address, source_address, packet_type, field_id, payload)| {
+                Frame::new_unchecked(
+                    destination_address,
+                    source_address,
+                    packet_type,
+                    field_id,
+                    payload,
+                )
+            },
+        )
+}
+
+/// Defect that `FrameGenerator::corrupted` introduces into a serialized `Frame`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Defect {
+    /// The checksum does not match, the parser reports `ParseErrorKind::ChecksumError`
+    BadChecksum,
+    /// The frame is cut off, the parser reports `ParseResult::Incomplete`
+    Truncated,
+    /// The length byte is out of range, the parser reports `ParseErrorKind::InvalidLength`
+    WrongLength,
+}
+
+/// Deterministic generator of random valid `Frame`s and corrupted serialized frames,
+/// e.g. to test the buffering and error handling of a bus reader.
+/// The same `seed` always produces the same sequence
+#[derive(Debug, Clone)]
+pub struct FrameGenerator {
+    state: u64,
+}
+
+impl FrameGenerator {
+    /// Create a new `FrameGenerator` from a `seed`
+    #[must_use]
+    pub fn new(seed: u64) -> FrameGenerator {
+        // the xorshift state must never be zero
+        FrameGenerator {
+            state: (seed ^ 0x9e37_79b9_7f4a_7c15).max(1),
+        }
+    }
+
+    /// Next pseudo random number (xorshift64*)
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Next pseudo random byte
+    fn next_u8(&mut self) -> u8 {
+        self.next_u64().to_be_bytes()[0]
+    }
+
+    /// Next pseudo random number in `0..max`
+    fn next_below(&mut self, max: usize) -> usize {
+        usize::try_from(self.next_u64() % max as u64).expect("the number is smaller than max")
+    }
+
+    /// Generate a random valid `Frame`
+    pub fn frame(&mut self) -> Frame {
+        let length = self.next_below(MAX_PAYLOAD_LENGTH + 1);
+        let payload = (0..length).map(|_| self.next_u8()).collect();
+        Frame::new_unchecked(
+            self.next_u8(),
+            self.next_u8(),
+            self.next_u8(),
+            self.next_u64().to_be_bytes()[..4]
+                .iter()
+                .fold(0, |id, byte| (id << 8) | u32::from(*byte)),
+            payload,
+        )
+    }
+
+    /// Generate a serialized random `Frame` with the specified `defect`
+    pub fn corrupted(&mut self, defect: Defect) -> Vec<u8> {
+        let mut bytes = self.frame().serialize();
+        match defect {
+            Defect::BadChecksum => {
+                // flipping at least one bit of the checksum always invalidates it
+                let mask = self.next_u8() | 1;
+                let last = bytes.len() - 1;
+                bytes[last] ^= mask;
+            }
+            Defect::Truncated => {
+                // keep at least the SOF byte
+                let length = 1 + self.next_below(bytes.len() - 1);
+                bytes.truncate(length);
+            }
+            Defect::WrongLength => {
+                // valid lengths are 11..=MAX_FRAME_LENGTH
+                let invalid = (0..11)
+                    .chain(MAX_FRAME_LENGTH + 1..=u8::MAX)
+                    .collect::<Vec<_>>();
+                bytes[3] = invalid[self.next_below(invalid.len())];
+            }
+        }
+        bytes
+    }
+}
+
+impl Iterator for FrameGenerator {
+    type Item = Frame;
+
+    /// Generate an endless stream of random valid `Frame`s
+    fn next(&mut self) -> Option<Frame> {
+        Some(self.frame())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arbitrary::{Arbitrary as _, Unstructured};
+    use proptest::proptest;
+
+    use crate::{FieldValue, Frame, ParseErrorKind, ParseResult, Value};
+
+    use super::{Defect, FrameGenerator};
+
+    #[test]
+    fn test_frame_generator_valid_frames() {
+        for frame in FrameGenerator::new(1).take(100) {
+            let bytes = frame.serialize();
+            let ParseResult::Ok {
+                rest,
+                frame: testcase,
+            } = Frame::parse(&bytes)
+            else {
+                panic!("not a frame")
+            };
+            assert!(rest.is_empty());
+            assert_eq!(testcase, frame);
+        }
+    }
+
+    #[test]
+    fn test_frame_generator_is_deterministic() {
+        let testcase = FrameGenerator::new(42).take(10).collect::<Vec<_>>();
+        let want = FrameGenerator::new(42).take(10).collect::<Vec<_>>();
+        assert_eq!(testcase, want);
+    }
+
+    #[test]
+    fn test_frame_generator_corrupted() {
+        let mut generator = FrameGenerator::new(7);
+        for _ in 0..100 {
+            let bytes = generator.corrupted(Defect::BadChecksum);
+            assert!(matches!(
+                Frame::parse(&bytes),
+                ParseResult::Failure {
+                    error: ParseErrorKind::ChecksumError,
+                    ..
+                }
+            ));
+            let bytes = generator.corrupted(Defect::Truncated);
+            assert!(matches!(Frame::parse(&bytes), ParseResult::Incomplete));
+            let bytes = generator.corrupted(Defect::WrongLength);
+            assert!(matches!(
+                Frame::parse(&bytes),
+                ParseResult::Failure {
+                    error: ParseErrorKind::InvalidLength,
+                    ..
+                }
+            ));
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_frame_serialize_parse_round_trip(frame in super::frame()) {
+            let bytes = frame.serialize();
+            let ParseResult::Ok { rest, frame: testcase } = Frame::parse(&bytes) else {
+                panic!("not a frame")
+            };
+            assert!(rest.is_empty());
+            assert_eq!(testcase, frame);
+        }
+
+        #[test]
+        fn test_value_encode_decode_round_trip(value in super::value()) {
+            let testcase = Value::decode(&value.encode(), value.datatype()).unwrap();
+            assert_eq!(testcase, value);
+        }
+
+        #[test]
+        fn test_field_value_from_frame_round_trip(field_value in super::field_value()) {
+            let frame = Frame::new(0, 66, 7, field_value.field_id(), field_value.encode()).unwrap();
+            assert_eq!(FieldValue::from_frame(&frame).unwrap(), field_value);
+        }
+
+        #[test]
+        fn test_arbitrary_value_round_trip(bytes: Vec<u8>) {
+            let mut u = Unstructured::new(&bytes);
+            if let Ok(value) = Value::arbitrary(&mut u) {
+                assert_eq!(Value::decode(&value.encode(), value.datatype()).unwrap(), value);
+            }
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+/// Physical `Unit` of a `Field` value
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, Serialize, Deserialize)]
+pub enum Unit {
+    /// Temperature in degree Celsius, the unit used on the bus
+    Celsius,
+    /// Temperature in degree Fahrenheit
+    Fahrenheit,
+    /// Pressure in bar, the unit used on the bus
+    Bar,
+    /// Pressure in pound-force per square inch
+    Psi,
+    /// Percentage, e.g. of a modulation or pump speed
+    Percent,
+}
+
+/// psi per bar
+const PSI_PER_BAR: f64 = 14.503_773_773;
+
+impl Unit {
+    /// Convert a `value` from this `Unit` into the unit `to`.
+    /// Returns `None` if the units measure different quantities
+    #[must_use]
+    pub fn convert(self, value: f64, to: Unit) -> Option<f64> {
+        match (self, to) {
+            (from, to) if from == to => Some(value),
+            (Unit::Celsius, Unit::Fahrenheit) => Some(value * 9.0 / 5.0 + 32.0),
+            (Unit::Fahrenheit, Unit::Celsius) => Some((value - 32.0) * 5.0 / 9.0),
+            (Unit::Bar, Unit::Psi) => Some(value * PSI_PER_BAR),
+            (Unit::Psi, Unit::Bar) => Some(value / PSI_PER_BAR),
+            _ => None,
+        }
+    }
+}
+
+/// Units used for output, e.g. in `FieldValue::to_named_value_in`.
+/// Values of fields with another unit of the same quantity are converted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct UnitPreference {
+    temperature: Unit,
+    pressure: Unit,
+}
+
+impl Default for UnitPreference {
+    /// The units used on the bus
+    fn default() -> Self {
+        UnitPreference {
+            temperature: Unit::Celsius,
+            pressure: Unit::Bar,
+        }
+    }
+}
+
+impl UnitPreference {
+    /// Return the `UnitPreference` with `unit` for temperatures.
+    /// Other units than `Celsius` and `Fahrenheit` are ignored
+    #[must_use]
+    pub fn with_temperature(mut self, unit: Unit) -> UnitPreference {
+        if matches!(unit, Unit::Celsius | Unit::Fahrenheit) {
+            self.temperature = unit;
+        }
+        self
+    }
+
+    /// Return the `UnitPreference` with `unit` for pressures.
+    /// Other units than `Bar` and `Psi` are ignored
+    #[must_use]
+    pub fn with_pressure(mut self, unit: Unit) -> UnitPreference {
+        if matches!(unit, Unit::Bar | Unit::Psi) {
+            self.pressure = unit;
+        }
+        self
+    }
+
+    /// The preferred unit for the quantity measured in `unit`
+    #[must_use]
+    pub fn resolve(&self, unit: Unit) -> Unit {
+        match unit {
+            Unit::Celsius | Unit::Fahrenheit => self.temperature,
+            Unit::Bar | Unit::Psi => self.pressure,
+            Unit::Percent => unit,
+        }
+    }
+}
+
+impl Display for Unit {
+    /// Display the symbol of the `Unit`, e.g. "°C"
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let symbol = match self {
+            Unit::Celsius => "°C",
+            Unit::Fahrenheit => "°F",
+            Unit::Bar => "bar",
+            Unit::Psi => "psi",
+            Unit::Percent => "%",
+        };
+        write!(f, "{symbol}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Unit, UnitPreference};
+
+    #[test]
+    fn test_unit_convert() {
+        // (<from>, <value>, <to>, <converted>)
+        let testcases = vec![
+            (Unit::Celsius, 20.0, Unit::Fahrenheit, Some(68.0)),
+            (Unit::Fahrenheit, -40.0, Unit::Celsius, Some(-40.0)),
+            (Unit::Bar, 1.5, Unit::Bar, Some(1.5)),
+            (Unit::Celsius, 1.5, Unit::Psi, None),
+        ];
+        for (from, value, to, want) in testcases {
+            assert_eq!(from.convert(value, to), want);
+        }
+        let psi = Unit::Bar.convert(1.0, Unit::Psi).unwrap();
+        assert!((psi - 14.5038).abs() < 1e-4);
+        let bar = Unit::Psi.convert(psi, Unit::Bar).unwrap();
+        assert!((bar - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_unit_preference_resolve() {
+        let preference = UnitPreference::default()
+            .with_temperature(Unit::Fahrenheit)
+            .with_pressure(Unit::Fahrenheit);
+        // (<unit>, <preferred unit>)
+        let testcases = vec![
+            (Unit::Celsius, Unit::Fahrenheit),
+            (Unit::Fahrenheit, Unit::Fahrenheit),
+            (Unit::Bar, Unit::Bar),
+            (Unit::Psi, Unit::Bar),
+        ];
+        for (unit, want) in testcases {
+            assert_eq!(preference.resolve(unit), want);
+        }
+    }
+
+    #[test]
+    fn test_unit_to_string() {
+        assert_eq!(Unit::Celsius.to_string(), "°C");
+        assert_eq!(Unit::Psi.to_string(), "psi");
+    }
+}
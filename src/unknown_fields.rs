@@ -0,0 +1,357 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+    io::Write,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{hex::to_hex, BsbError, Datatype, Field, Frame};
+
+/// Number of distinct payloads that are kept as samples for each unknown field
+const MAX_SAMPLES: usize = 5;
+
+/// Observations of a field id that is not part of the field database
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownField {
+    field_id: u32,
+    packet_types: BTreeSet<u8>,
+    samples: Vec<Vec<u8>>,
+    count: usize,
+}
+
+impl UnknownField {
+    /// Access `UnknownField.field_id`
+    #[must_use]
+    pub fn field_id(&self) -> u32 {
+        self.field_id
+    }
+
+    /// Access `UnknownField.packet_types`, all packet types seen with this field id
+    #[must_use]
+    pub fn packet_types(&self) -> &BTreeSet<u8> {
+        &self.packet_types
+    }
+
+    /// Access `UnknownField.samples`, the first distinct non-empty payloads
+    #[must_use]
+    pub fn samples(&self) -> &[Vec<u8>] {
+        &self.samples
+    }
+
+    /// Access `UnknownField.count`, the number of frames seen with this field id
+    #[must_use]
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Guess the `Datatype` from the length of the payload samples.
+    /// This is only a starting point, e.g. a `Number` could also be a `Float`
+    #[must_use]
+    pub fn guess_datatype(&self) -> Datatype {
+        match self.samples.first().map(Vec::len) {
+            Some(2) => Datatype::Setting(
+                self.samples
+                    .iter()
+                    .filter_map(|sample| sample.get(1).copied())
+                    .max()
+                    .unwrap_or_default(),
+            ),
+            Some(9) => Datatype::DateTime,
+            Some(length) if length >= 12 && length % 4 == 0 => Datatype::Schedule,
+            _ => Datatype::Number,
+        }
+    }
+
+    /// Render a row in the format of `bsb-fields.csv` with a placeholder name and path
+    #[must_use]
+    pub fn to_csv_row(&self) -> String {
+        format!(
+            "0x{:08x},{},0,{},{},",
+            self.field_id,
+            name(self.field_id),
+            self.guess_datatype(),
+            path(self.field_id),
+        )
+    }
+}
+
+/// Placeholder name of an unknown field, e.g. `unknown_12345678`
+fn name(field_id: u32) -> String {
+    format!("unknown_{field_id:08x}")
+}
+
+/// Placeholder path of an unknown field, e.g. `unknown/12345678`
+fn path(field_id: u32) -> String {
+    format!("unknown/{field_id:08x}")
+}
+
+/// The raw payload of a field that is not part of the field database.
+/// It carries frames of unknown fields through a pipeline (e.g. a MQTT bridge) that would
+/// otherwise be dropped by `FieldValue::from_frame` and re-encodes them unchanged
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "RawUnknownFieldValue")]
+pub struct UnknownFieldValue {
+    #[serde(with = "crate::hex::field_id")]
+    field_id: u32,
+    #[serde(with = "crate::hex::payload")]
+    payload: Vec<u8>,
+}
+
+/// Deserialized form of `UnknownFieldValue` that is checked by `UnknownFieldValue::new`
+#[derive(Deserialize)]
+struct RawUnknownFieldValue {
+    #[serde(with = "crate::hex::field_id")]
+    field_id: u32,
+    #[serde(with = "crate::hex::payload")]
+    payload: Vec<u8>,
+}
+
+impl TryFrom<RawUnknownFieldValue> for UnknownFieldValue {
+    type Error = String;
+
+    fn try_from(raw: RawUnknownFieldValue) -> Result<Self, Self::Error> {
+        UnknownFieldValue::new(raw.field_id, raw.payload).ok_or_else(|| {
+            format!(
+                "field 0x{:08x} is part of the field database, use FieldValue",
+                raw.field_id
+            )
+        })
+    }
+}
+
+impl UnknownFieldValue {
+    /// Create a new `UnknownFieldValue` with the raw `payload` of `field_id`.
+    /// Returns `None` if the field is part of the field database, use `FieldValue` instead
+    #[must_use]
+    pub fn new(field_id: u32, payload: Vec<u8>) -> Option<UnknownFieldValue> {
+        Field::by_id(field_id)
+            .is_none()
+            .then_some(UnknownFieldValue { field_id, payload })
+    }
+
+    /// Create an `UnknownFieldValue` from a `Frame` whose field is not part of the field database.
+    /// Returns `None` if the field is known, use `FieldValue::from_frame` instead
+    #[must_use]
+    pub fn from_frame(frame: &Frame) -> Option<UnknownFieldValue> {
+        Self::new(frame.field_id(), frame.payload().to_vec())
+    }
+
+    /// Access `UnknownFieldValue.field_id`
+    #[must_use]
+    pub fn field_id(&self) -> u32 {
+        self.field_id
+    }
+
+    /// Access `UnknownFieldValue.payload`
+    #[must_use]
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    /// Placeholder name derived from the field id, e.g. `unknown_12345678`
+    #[must_use]
+    pub fn name(&self) -> String {
+        name(self.field_id)
+    }
+
+    /// Placeholder path derived from the field id (e.g. for MQTT), e.g. `unknown/12345678`
+    #[must_use]
+    pub fn path(&self) -> String {
+        path(self.field_id)
+    }
+
+    /// The unchanged raw payload for a `Frame`
+    #[must_use]
+    pub fn encode(&self) -> Vec<u8> {
+        self.payload.clone()
+    }
+
+    /// Create a `Frame` of `packet_type` that carries the raw payload
+    ///
+    /// # Errors
+    ///
+    /// Returns `BsbError::InvalidPayloadLength` if the payload is too long
+    pub fn to_frame(
+        &self,
+        destination_address: u8,
+        source_address: u8,
+        packet_type: u8,
+    ) -> Result<Frame, BsbError> {
+        Frame::new(
+            destination_address,
+            source_address,
+            packet_type,
+            self.field_id,
+            self.encode(),
+        )
+    }
+
+    /// Create a `Set` `Frame` that writes the raw payload
+    ///
+    /// # Errors
+    ///
+    /// Returns `BsbError::InvalidPayloadLength` if the payload is empty or too long
+    pub fn to_set_frame(
+        &self,
+        destination_address: u8,
+        source_address: u8,
+    ) -> Result<Frame, BsbError> {
+        Frame::new_set(
+            destination_address,
+            source_address,
+            self.field_id,
+            self.encode(),
+        )
+    }
+}
+
+impl Display for UnknownFieldValue {
+    /// Display the name and the payload as hex, e.g. `unknown_12345678: 00000f`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.name(), to_hex(&self.payload))
+    }
+}
+
+/// Collects all `Frame`s whose field id is not part of the field database,
+/// e.g. to contribute new fields to `bsb-fields.csv`
+#[derive(Debug, Default)]
+pub struct UnknownFieldCollector {
+    fields: BTreeMap<u32, UnknownField>,
+}
+
+impl UnknownFieldCollector {
+    /// Create a new empty `UnknownFieldCollector`
+    #[must_use]
+    pub fn new() -> UnknownFieldCollector {
+        UnknownFieldCollector::default()
+    }
+
+    /// Record the `frame` if its field is unknown. Returns `true` if the frame was recorded
+    pub fn record(&mut self, frame: &Frame) -> bool {
+        if Field::by_id(frame.field_id()).is_some() {
+            return false;
+        }
+        let field = self
+            .fields
+            .entry(frame.field_id())
+            .or_insert_with(|| UnknownField {
+                field_id: frame.field_id(),
+                packet_types: BTreeSet::new(),
+                samples: Vec::new(),
+                count: 0,
+            });
+        field.count += 1;
+        field.packet_types.insert(frame.packet_type());
+        let payload = frame.payload();
+        if !payload.is_empty()
+            && field.samples.len() < MAX_SAMPLES
+            && !field.samples.iter().any(|sample| sample == payload)
+        {
+            field.samples.push(payload.to_vec());
+        }
+        true
+    }
+
+    /// Iterate over all recorded `UnknownField`s ordered by field id
+    pub fn fields(&self) -> impl Iterator<Item = &UnknownField> {
+        self.fields.values()
+    }
+
+    /// Number of recorded unknown field ids
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// Check if no unknown field was recorded
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Write one `bsb-fields.csv` row per unknown field to `writer`, ready to be appended to
+    /// the field database. Returns the number of written rows
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to `writer` fails
+    pub fn to_csv<W: Write>(&self, mut writer: W) -> std::io::Result<usize> {
+        for field in self.fields() {
+            writeln!(writer, "{}", field.to_csv_row())?;
+        }
+        Ok(self.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Datatype, Frame, PacketType};
+
+    use super::{UnknownFieldCollector, UnknownFieldValue};
+
+    #[test]
+    fn test_unknown_field_collector_record() {
+        let mut collector = UnknownFieldCollector::new();
+        // known field
+        assert!(!collector.record(&Frame::new(66, 0, 7, 0x053d_19f0, vec![0, 0, 15]).unwrap()));
+        assert!(collector.record(&Frame::new(0, 66, 6, 0x1234_5678, vec![]).unwrap()));
+        assert!(collector.record(&Frame::new(66, 0, 7, 0x1234_5678, vec![0, 1]).unwrap()));
+        assert!(collector.record(&Frame::new(66, 0, 7, 0x1234_5678, vec![0, 1]).unwrap()));
+        assert!(collector.record(&Frame::new(66, 0, 7, 0x1234_5678, vec![0, 3]).unwrap()));
+        assert_eq!(collector.len(), 1);
+        let field = collector.fields().next().unwrap();
+        assert_eq!(field.field_id(), 0x1234_5678);
+        assert_eq!(field.count(), 4);
+        assert_eq!(
+            field.packet_types().iter().copied().collect::<Vec<_>>(),
+            [6, 7]
+        );
+        assert_eq!(field.samples(), [vec![0, 1], vec![0, 3]]);
+        assert_eq!(field.guess_datatype(), Datatype::Setting(3));
+    }
+
+    #[test]
+    fn test_unknown_field_collector_to_csv() {
+        let mut collector = UnknownFieldCollector::new();
+        collector.record(&Frame::new(66, 0, 7, 0x1234_5678, vec![0, 0, 15]).unwrap());
+        let mut output = Vec::new();
+        let count = collector.to_csv(&mut output).unwrap();
+        assert_eq!(count, 1);
+        let testcase = String::from_utf8(output).unwrap();
+        let want = "0x12345678,unknown_12345678,0,Number,unknown/12345678,\n";
+        assert_eq!(testcase, want);
+    }
+
+    #[test]
+    fn test_unknown_field_value() {
+        let frame = Frame::new(66, 0, PacketType::Ret.into(), 0x1234_5678, vec![0, 0, 15]).unwrap();
+        let testcase = UnknownFieldValue::from_frame(&frame).unwrap();
+        assert_eq!(testcase.path(), "unknown/12345678");
+        assert_eq!(testcase.to_string(), "unknown_12345678: 00000f");
+        assert_eq!(
+            testcase.to_frame(66, 0, PacketType::Ret.into()).unwrap(),
+            frame
+        );
+        assert_eq!(
+            testcase.to_set_frame(0, 66).unwrap(),
+            Frame::new(0, 66, PacketType::Set.into(), 0x1234_5678, vec![0, 0, 15]).unwrap()
+        );
+        let json = serde_json::to_string(&testcase).unwrap();
+        assert_eq!(json, r#"{"field_id":"0x12345678","payload":"00000f"}"#);
+        assert_eq!(
+            serde_json::from_str::<UnknownFieldValue>(&json).unwrap(),
+            testcase
+        );
+        // a known field id is rejected like in `UnknownFieldValue::new`
+        let known = r#"{"field_id":"0x053d19f0","payload":"00000f"}"#;
+        assert!(serde_json::from_str::<UnknownFieldValue>(known).is_err());
+        // known fields are decoded with `FieldValue`
+        assert_eq!(
+            UnknownFieldValue::from_frame(
+                &Frame::new(66, 0, 7, 0x053d_19f0, vec![0, 0, 15]).unwrap()
+            ),
+            None
+        );
+    }
+}
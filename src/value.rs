@@ -1,14 +1,40 @@
-use std::fmt::Display;
+use std::{
+    cmp::Ordering,
+    fmt::Display,
+    hash::{Hash, Hasher},
+};
 
 use chrono::{DateTime, Datelike as _, NaiveDate, NaiveDateTime, NaiveTime, Timelike as _};
 use serde::{Deserialize, Serialize};
 
-use crate::{BsbError, Datatype};
+use crate::{
+    BsbError, Datatype, DecodeOptions, Field, FixedPoint, PacketType, Quirks, Rounding,
+    WeekdayCheck, MAX_PAYLOAD_LENGTH,
+};
+
+/// Raw value of a `Float` payload that marks a sensor error
+const INVALID_RAW: u16 = 0x8000;
+
+/// String representation of `Value::Invalid` and `Value::Unset`
+const INVALID_STR: &str = "---";
+
+/// Flag of an answer payload that marks an unset value
+const UNSET_FLAG: u8 = 1;
+
+/// Flag of a `Set` payload that unsets a value, e.g. to disable a limit
+const SET_UNSET_FLAG: u8 = 5;
+
+/// String representation of the date and time of `DateTime` and `ErrorRecord` values
+const DATETIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
 
 /// The Value enum is aligned with the Datatype enum
 /// This type stores the actual values together with flags if necessary,
-/// It is self sufficient to encode the value into a valid payload
-#[derive(Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+/// It is self sufficient to encode the value into a valid payload.
+/// Floats are compared and hashed by their bit pattern and ordered by `f32::total_cmp`, so `Value`
+/// can be used as key in maps and sorted sets.
+/// New values are added without a major version, so matches need a wildcard arm
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub enum Value {
     /// Setting value based on u8 representation of the enum for this field
     Setting {
@@ -21,27 +47,167 @@ pub enum Value {
         flag: u8,
         value: u16,
     },
+    /// A signed integer for e.g. offsets
+    SignedNumber {
+        flag: u8,
+        value: i16,
+    },
     /// Float numbers like pressure, slope, temperature
     Float {
         flag: u8,
         value: f32,
         factor: u8,
     },
+    /// Sensor error of a `Float` field (raw value 0x8000), e.g. a missing or shorted sensor
+    Invalid {
+        flag: u8,
+        factor: u8,
+    },
+    /// A numeric field without a value (displayed as "---" by the devices), reported with the
+    /// flag 1 in an answer, e.g. a disabled limit
+    Unset {
+        flag: u8,
+        datatype: Datatype,
+    },
+    /// Date and time, the `trailing_flag` is the unknown last payload byte (seen as 0 and 1)
     DateTime {
         flag: u8,
         datetime: chrono::NaiveDateTime,
+        trailing_flag: u8,
     },
     // List of time ranges
     Schedule(Vec<(u8, u8, u8, u8)>),
+    /// Command of a write-only field with its fixed `token`
+    Trigger {
+        flag: u8,
+        token: u8,
+    },
+    /// ASCII text without the null terminator
+    String {
+        flag: u8,
+        value: String,
+    },
+    /// Entry of the error history with the error `code` and the time it occurred
+    ErrorRecord {
+        flag: u8,
+        code: u16,
+        datetime: chrono::NaiveDateTime,
+    },
+}
+
+/// Float that is compared and hashed by its bit pattern and ordered by `f32::total_cmp`,
+/// which is `Equal` exactly for the same bit pattern
+#[derive(Clone, Copy)]
+struct TotalF32(f32);
+
+impl PartialEq for TotalF32 {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.to_bits() == other.0.to_bits()
+    }
+}
+
+impl Eq for TotalF32 {}
+
+impl PartialOrd for TotalF32 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for TotalF32 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl Hash for TotalF32 {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state);
+    }
+}
+
+/// Representation of a `Value` that compares, orders and hashes the float as `TotalF32`
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum ValueKey<'a> {
+    Setting(u8, u8, u8),
+    Number(u8, u16),
+    SignedNumber(u8, i16),
+    Float(u8, TotalF32, u8),
+    Invalid(u8, u8),
+    Unset(u8, Datatype),
+    DateTime(u8, &'a NaiveDateTime, u8),
+    Schedule(&'a [(u8, u8, u8, u8)]),
+    Trigger(u8, u8),
+    String(u8, &'a str),
+    ErrorRecord(u8, u16, &'a NaiveDateTime),
+}
+
+impl Value {
+    /// Create the `ValueKey` that is used for `PartialEq`, `Ord` and `Hash`
+    fn key(&self) -> ValueKey<'_> {
+        match self {
+            Value::Setting { flag, setting, max } => ValueKey::Setting(*flag, *setting, *max),
+            Value::Number { flag, value } => ValueKey::Number(*flag, *value),
+            Value::SignedNumber { flag, value } => ValueKey::SignedNumber(*flag, *value),
+            Value::Float {
+                flag,
+                value,
+                factor,
+            } => ValueKey::Float(*flag, TotalF32(*value), *factor),
+            Value::Invalid { flag, factor } => ValueKey::Invalid(*flag, *factor),
+            Value::Unset { flag, datatype } => ValueKey::Unset(*flag, *datatype),
+            Value::DateTime {
+                flag,
+                datetime,
+                trailing_flag,
+            } => ValueKey::DateTime(*flag, datetime, *trailing_flag),
+            Value::Schedule(items) => ValueKey::Schedule(items),
+            Value::Trigger { flag, token } => ValueKey::Trigger(*flag, *token),
+            Value::String { flag, value } => ValueKey::String(*flag, value),
+            Value::ErrorRecord {
+                flag,
+                code,
+                datetime,
+            } => ValueKey::ErrorRecord(*flag, *code, datetime),
+        }
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Value {}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Value {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
 }
 
 impl Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Value::Setting { setting: v, .. } => write!(f, "{v}"),
+            Value::Setting { setting: v, .. } | Value::Trigger { token: v, .. } => write!(f, "{v}"),
             Value::Number { value: v, .. } => write!(f, "{v}"),
+            Value::SignedNumber { value: v, .. } => write!(f, "{v}"),
             Value::Float { value: v, .. } => write!(f, "{v}"),
-            Value::DateTime { datetime: v, .. } => write!(f, "{}", v.format("%Y-%m-%dT%H:%M:%S")),
+            Value::Invalid { .. } | Value::Unset { .. } => write!(f, "{INVALID_STR}"),
+            Value::DateTime { datetime: v, .. } => write!(f, "{}", v.format(DATETIME_FORMAT)),
             Value::Schedule(v) => write!(
                 f,
                 "{}",
@@ -50,13 +216,33 @@ impl Display for Value {
                     .collect::<Vec<_>>()
                     .join(",")
             ),
+            Value::String { value, .. } => write!(f, "{value}"),
+            Value::ErrorRecord { code, datetime, .. } => {
+                write!(f, "{code} {}", datetime.format(DATETIME_FORMAT))
+            }
         }
     }
 }
 
 impl Value {
-    /// Encode the `Value` into a `Vec<u8>` that can be used in a BSB protocol payload
+    /// Encode the `Value` into a `Vec<u8>` that can be used in a BSB protocol payload.
+    /// Floats are rounded half to even
+    ///
+    /// # Panics
+    ///
+    /// Panics if the date components of a `DateTime` do not fit into a byte, which chrono guarantees
+    #[must_use]
     pub fn encode(&self) -> Vec<u8> {
+        self.encode_rounded(Rounding::default())
+    }
+
+    /// Encode the `Value` like `Value::encode` rounding floats with `rounding`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the date components of a `DateTime` do not fit into a byte, which chrono guarantees
+    #[must_use]
+    pub fn encode_rounded(&self, rounding: Rounding) -> Vec<u8> {
         match &self {
             Value::Setting { flag, setting, .. } => {
                 // this is the value for the payload
@@ -67,28 +253,35 @@ impl Value {
                 r.insert(0, *flag);
                 r
             }
+            Value::SignedNumber { flag, value } => {
+                let bytes = value.to_be_bytes();
+                vec![*flag, bytes[0], bytes[1]]
+            }
             Value::Float {
                 flag,
                 value,
                 factor,
             } => {
-                let scaled_number = (value * f32::from(*factor)) as u16;
+                // the payload is a signed 16bit integer, out of range values saturate
+                #[allow(clippy::cast_possible_truncation)]
+                let scaled_number = rounding.apply(f64::from(*value) * f64::from(*factor)) as i16;
                 let bytes = scaled_number.to_be_bytes();
                 vec![*flag, bytes[0], bytes[1]]
             }
-            Value::DateTime { flag, datetime } => {
-                let value = datetime;
-                vec![
-                    *flag,
-                    (value.year() - 1900).try_into().unwrap_or_default(),
-                    value.month().try_into().unwrap(),
-                    value.day().try_into().unwrap(),
-                    value.weekday().number_from_monday().try_into().unwrap(),
-                    value.hour().try_into().unwrap(),
-                    value.minute().try_into().unwrap(),
-                    value.second().try_into().unwrap(),
-                    0, // some timezone flag? seen 1 already
-                ]
+            Value::Invalid { flag, .. } => {
+                let bytes = INVALID_RAW.to_be_bytes();
+                vec![*flag, bytes[0], bytes[1]]
+            }
+            Value::Unset { flag, .. } => vec![*flag, 0, 0],
+            Value::DateTime {
+                flag,
+                datetime,
+                trailing_flag,
+            } => {
+                let mut result = vec![*flag];
+                result.extend_from_slice(&encode_datetime(datetime));
+                result.push(*trailing_flag);
+                result
             }
             Value::Schedule(items) => {
                 let mut result = vec![];
@@ -96,15 +289,130 @@ impl Value {
                     result.extend_from_slice(&[*sh, *sm, *eh, *em]);
                 }
                 // terminate the schedule
-                result.extend_from_slice(&[24 ^ 0x80, 0, 24, 0]);
+                result.extend_from_slice(&[0x18 ^ 0x80, 0, 0x18, 0]);
+                result
+            }
+            Value::Trigger { flag, token } => vec![*flag, *token],
+            Value::String { flag, value } => {
+                let mut result = vec![*flag];
+                result.extend_from_slice(value.as_bytes());
+                // terminate the string
+                result.push(0);
+                result
+            }
+            Value::ErrorRecord {
+                flag,
+                code,
+                datetime,
+            } => {
+                let mut result = vec![*flag];
+                result.extend_from_slice(&code.to_be_bytes());
+                result.extend_from_slice(&encode_datetime(datetime));
                 result
             }
         }
     }
 
+    /// Encode the `Value` into a payload for a `Frame` with `packet_type`.
+    /// A `Set` payload carries the flag 1 to enable the value or the flag 5 to unset it,
+    /// `Info` broadcasts carry no flag and answers (e.g. `Ret`) use the stored `flag`.
+    /// Schedules have no flag and are encoded identically
+    ///
+    /// # Errors
+    ///
+    /// Returns `BsbError::InvalidFieldValue` for a `Set` of `Value::Invalid`, a sensor error
+    /// cannot be written, and `BsbError::OutOfRange` for a `Set` of a `Value::Float` that does not
+    /// fit into the payload
+    pub fn encode_for(&self, packet_type: PacketType) -> Result<Vec<u8>, BsbError> {
+        let payload = match (packet_type, self) {
+            (PacketType::Set, Value::Invalid { .. }) => return Err(BsbError::InvalidFieldValue),
+            (PacketType::Set, _) if !self.fits_payload() => return Err(BsbError::OutOfRange),
+            (PacketType::Set, Value::Unset { datatype, .. }) => Value::Unset {
+                flag: SET_UNSET_FLAG,
+                datatype: *datatype,
+            }
+            .encode(),
+            (PacketType::Set, _) => {
+                let mut value = self.clone();
+                value.set_flag(1);
+                value.encode()
+            }
+            (PacketType::Info, _) => self.encode_with(Quirks::NO_FLAG_BYTE),
+            _ => self.encode(),
+        };
+        Ok(payload)
+    }
+
+    /// Check if a `Float` scales to a finite number within the signed 16bit integer of the payload,
+    /// `Value::encode` saturates other floats. Values of other datatypes always fit
+    pub(crate) fn fits_payload(&self) -> bool {
+        let Value::Float { value, factor, .. } = self else {
+            return true;
+        };
+        let scaled = Rounding::default().apply(f64::from(*value) * f64::from(*factor));
+        scaled.is_finite() && (f64::from(i16::MIN)..=f64::from(i16::MAX)).contains(&scaled)
+    }
+
+    /// Encode the `Value` like `Value::encode` honoring the device `quirks`
+    #[must_use]
+    pub fn encode_with(&self, quirks: Quirks) -> Vec<u8> {
+        let mut payload = self.encode();
+        if quirks.contains(Quirks::NO_FLAG_BYTE) && self.flag().is_some() {
+            payload.remove(0);
+        }
+        payload
+    }
+
     /// Decode the BSB protocol `payload` with the specified `datatype` into a `Value`.
-    /// Returns None if invalid settings are encountered
+    ///
+    /// # Errors
+    ///
+    /// Returns a `BsbError` if the payload is too short or contains invalid data for the `datatype`
     pub fn decode(payload: &[u8], datatype: Datatype) -> Result<Value, BsbError> {
+        Self::decode_with(payload, datatype, &DecodeOptions::default())
+    }
+
+    /// Decode the BSB protocol `payload` with the specified `datatype` into a `Value` using
+    /// custom `DecodeOptions`. Payloads of `Info` broadcasts carry no flag and are decoded
+    /// with the flag 0
+    ///
+    /// # Errors
+    ///
+    /// Returns a `BsbError` if the payload is too short or contains invalid data for the `datatype`
+    pub fn decode_with(
+        payload: &[u8],
+        datatype: Datatype,
+        options: &DecodeOptions,
+    ) -> Result<Value, BsbError> {
+        let no_flag = options.quirks().contains(Quirks::NO_FLAG_BYTE)
+            || options.packet_type() == Some(PacketType::Info);
+        if no_flag && datatype != Datatype::Schedule {
+            // decode the payload as if it had the flag 0
+            let payload = [&[0], payload].concat();
+            return Self::decode_flagged(&payload, datatype, options);
+        }
+        Self::decode_flagged(payload, datatype, options)
+    }
+
+    /// Decode a `payload` that starts with the flag byte
+    ///
+    /// # Panics
+    ///
+    /// Does not panic, the payload length is checked before slicing
+    fn decode_flagged(
+        payload: &[u8],
+        datatype: Datatype,
+        options: &DecodeOptions,
+    ) -> Result<Value, BsbError> {
+        if is_unset(payload, datatype, options.packet_type()) {
+            if payload.len() < 3 {
+                return Err(BsbError::InvalidPayloadLength);
+            }
+            return Ok(Value::Unset {
+                flag: UNSET_FLAG,
+                datatype,
+            });
+        }
         let value = match datatype {
             Datatype::Setting(max) => {
                 // use the second byte in the payload as the integer value for the enum
@@ -114,7 +422,7 @@ impl Value {
                     return Err(BsbError::InvalidSetting);
                 }
                 Value::Setting {
-                    flag: *payload.get(0).ok_or(BsbError::NoFlag)?,
+                    flag: *payload.first().ok_or(BsbError::NoFlag)?,
                     setting,
                     max,
                 }
@@ -122,24 +430,40 @@ impl Value {
             Datatype::Number => {
                 if payload.len() < 3 {
                     return Err(BsbError::InvalidPayloadLength);
-                };
+                }
 
-                // unclear if this is unsigned
+                // unsigned unless the field is defined as `SignedNumber`
                 Value::Number {
-                    flag: *payload.get(0).ok_or(BsbError::NoFlag)?,
+                    flag: *payload.first().ok_or(BsbError::NoFlag)?,
                     value: u16::from_be_bytes(payload[1..3].try_into().unwrap()),
                 }
             }
+            Datatype::SignedNumber => {
+                if payload.len() < 3 {
+                    return Err(BsbError::InvalidPayloadLength);
+                }
+
+                Value::SignedNumber {
+                    flag: *payload.first().ok_or(BsbError::NoFlag)?,
+                    value: i16::from_be_bytes(payload[1..3].try_into().unwrap()),
+                }
+            }
             Datatype::Float(factor) => {
                 if payload.len() < 3 {
                     return Err(BsbError::InvalidPayloadLength);
                 }
 
+                let flag = *payload.first().ok_or(BsbError::NoFlag)?;
+                let raw = u16::from_be_bytes(payload[1..3].try_into().unwrap());
+                if raw == INVALID_RAW {
+                    // the device reports a sensor error instead of a value
+                    return Ok(Value::Invalid { flag, factor });
+                }
                 // signed 16bit integer with a division factor
                 Value::Float {
-                    flag: *payload.get(0).ok_or(BsbError::NoFlag)?,
-                    value: i16::from_be_bytes(payload[1..3].try_into().ok().unwrap()) as f32
-                        / factor as f32,
+                    flag,
+                    value: f32::from(i16::from_be_bytes(payload[1..3].try_into().unwrap()))
+                        / f32::from(factor),
                     factor,
                 }
             }
@@ -147,55 +471,178 @@ impl Value {
                 if payload.len() < 9 {
                     return Err(BsbError::InvalidPayloadLength);
                 }
-                // convert the payload bytes to the right datatypes
-                let year = 1900 + payload[1] as i32;
-                let month = payload[2] as u32;
-                let day = payload[3] as u32;
-                // day of week is currently not used - could be used as additional check
-                let _dow = payload[4] as u32;
-                let hour = payload[5] as u32;
-                let minute = payload[6] as u32;
-                let second = payload[7] as u32;
-                let _unknown_flag = payload[8] as u32;
                 Value::DateTime {
-                    flag: *payload.get(0).ok_or(BsbError::NoFlag)?,
-                    datetime: NaiveDateTime::new(
-                        NaiveDate::from_ymd_opt(year, month, day)
-                            .ok_or(BsbError::InvalidDateTime)?,
-                        NaiveTime::from_hms_opt(hour, minute, second)
-                            .ok_or(BsbError::InvalidDateTime)?,
-                    ),
+                    flag: *payload.first().ok_or(BsbError::NoFlag)?,
+                    datetime: Self::decode_datetime(payload, options)?,
+                    // payload[8] is an unknown flag that is kept for the encoding
+                    trailing_flag: payload[8],
                 }
             }
-            Datatype::Schedule => {
-                let mut ranges = Vec::new();
-                let mut range = payload.chunks_exact(4);
-                for chunk in &mut range {
-                    let (sh, sm, eh, em) = (chunk[0], chunk[1], chunk[2], chunk[3]);
-                    if sh & 0x80 != 0 {
-                        break;
-                    }
-                    // validate correct hour and minute values
-                    if sh > 24 || eh > 24 || sm > 59 || em > 59 {
-                        return Err(BsbError::InvalidSchedule);
-                    }
-                    ranges.push((sh, sm, eh, em));
+            Datatype::Schedule => Self::decode_schedule(payload)?,
+            Datatype::Trigger(token) => {
+                // the device answers with the token it expects, anything else is not a trigger
+                if *payload.get(1).ok_or(BsbError::InvalidPayloadLength)? != token {
+                    return Err(BsbError::InvalidFieldValue);
                 }
-                // if there is remaining data, the schedule was not provided in chunks of 4 bytes
-                if !range.remainder().is_empty() {
-                    return Err(BsbError::InvalidSchedule);
+                Value::Trigger {
+                    flag: *payload.first().ok_or(BsbError::NoFlag)?,
+                    token,
+                }
+            }
+            Datatype::String => Self::decode_string(payload)?,
+            Datatype::ErrorRecord => {
+                if payload.len() < 10 {
+                    return Err(BsbError::InvalidPayloadLength);
+                }
+                // the date and time follow the error code, skip it to decode them like a `DateTime`
+                Value::ErrorRecord {
+                    flag: *payload.first().ok_or(BsbError::NoFlag)?,
+                    code: u16::from_be_bytes(payload[1..3].try_into().unwrap()),
+                    datetime: Self::decode_datetime(&payload[2..], options)?,
                 }
-                Value::Schedule(ranges)
             }
         };
         Ok(value)
     }
 
-    // reverse of Display for Value
+    /// Decode the null-terminated ASCII text of a `String` payload
+    fn decode_string(payload: &[u8]) -> Result<Value, BsbError> {
+        let flag = *payload.first().ok_or(BsbError::NoFlag)?;
+        // the text ends at the first null byte, devices may pad the payload after it
+        let text = payload[1..]
+            .split(|&byte| byte == 0)
+            .next()
+            .unwrap_or_default();
+        if !text.is_ascii() {
+            return Err(BsbError::InvalidFieldValue);
+        }
+        Ok(Value::String {
+            flag,
+            value: String::from_utf8_lossy(text).into_owned(),
+        })
+    }
+
+    /// Decode the time ranges of a `Schedule` payload
+    fn decode_schedule(payload: &[u8]) -> Result<Value, BsbError> {
+        let mut ranges = Vec::new();
+        for chunk in payload.chunks(4) {
+            // the first range with the high bit set in the start hour terminates the
+            // schedule (seen as `98 00 18 00` and `80 00 00 00`), devices fill the
+            // remaining slots with arbitrary padding that is ignored
+            if chunk[0] & 0x80 != 0 {
+                break;
+            }
+            // without a terminator the schedule has to be provided in chunks of 4 bytes
+            let &[sh, sm, eh, em] = chunk else {
+                return Err(BsbError::InvalidSchedule);
+            };
+            // validate correct hour and minute values
+            if sh > 24 || eh > 24 || sm > 59 || em > 59 {
+                return Err(BsbError::InvalidSchedule);
+            }
+            ranges.push((sh, sm, eh, em));
+        }
+        Ok(Value::Schedule(ranges))
+    }
+
+    /// Decode the date and time of a `DateTime` payload that is already checked for its length
+    fn decode_datetime(payload: &[u8], options: &DecodeOptions) -> Result<NaiveDateTime, BsbError> {
+        // convert the payload bytes to the right datatypes
+        let date = Self::decode_date(payload)?;
+        // day of week (payload[4]) is derived from the date, it is only used as optional check
+        if options.weekday_check() == WeekdayCheck::Reject
+            && u32::from(payload[4]) != date.weekday().number_from_monday()
+        {
+            return Err(BsbError::InvalidWeekday);
+        }
+        let hour = u32::from(payload[5]);
+        let minute = u32::from(payload[6]);
+        let second = u32::from(payload[7]);
+        Ok(NaiveDateTime::new(
+            date,
+            NaiveTime::from_hms_opt(hour, minute, second).ok_or(BsbError::InvalidDateTime)?,
+        ))
+    }
+
+    /// Decode the date of a `DateTime` payload that is already checked for its length
+    fn decode_date(payload: &[u8]) -> Result<NaiveDate, BsbError> {
+        let year = 1900 + i32::from(payload[1]);
+        let month = u32::from(payload[2]);
+        let day = u32::from(payload[3]);
+        NaiveDate::from_ymd_opt(year, month, day).ok_or(BsbError::InvalidDateTime)
+    }
+
+    /// Repair the day of week byte of a `DateTime` `payload` in place so it matches the date.
+    /// Returns `true` if the payload was changed
+    ///
+    /// # Errors
+    ///
+    /// Returns a `BsbError` if the payload is too short or does not contain a valid date
+    ///
+    /// # Panics
+    ///
+    /// Does not panic, the day of week always fits into a byte
+    pub fn repair_weekday(payload: &mut [u8]) -> Result<bool, BsbError> {
+        if payload.len() < 9 {
+            return Err(BsbError::InvalidPayloadLength);
+        }
+        let weekday = Self::decode_date(payload)?
+            .weekday()
+            .number_from_monday()
+            .try_into()
+            .expect("day of week is always between 1 and 7");
+        let changed = payload[4] != weekday;
+        payload[4] = weekday;
+        Ok(changed)
+    }
+
+    /// Parse a `Value` of the `field` from a string, this is the reverse of `Value::to_string_for`.
+    /// `Setting` values also accept the labels of the field, e.g. "Automatic".
+    /// "---" is `Value::Unset` for writable fields, which a `Set` writes to unset the value
+    ///
+    /// # Errors
+    ///
+    /// Returns a `BsbError` if the string cannot be parsed or is not valid for the datatype of the `field`
+    pub fn from_str_for(s: &str, field: &Field) -> Result<Value, BsbError> {
+        match (field.datatype(), field.setting_by_label(s)) {
+            (Datatype::Setting(max), Some(setting)) => Ok(Value::Setting {
+                flag: 0,
+                setting,
+                max,
+            }),
+            (datatype, _) if s == INVALID_STR && field.is_writable() && is_nullable(datatype) => {
+                Ok(Value::Unset { flag: 0, datatype })
+            }
+            (datatype, _) => Value::from_str(s, datatype),
+        }
+    }
+
+    /// String representation of the `Value` of the `field`.
+    /// `Setting` values are shown with their label if the field has one
+    #[must_use]
+    pub fn to_string_for(&self, field: &Field) -> String {
+        match self {
+            Value::Setting { setting, .. } => field
+                .setting_label(*setting)
+                .map_or_else(|| self.to_string(), ToString::to_string),
+            _ => self.to_string(),
+        }
+    }
+
+    /// Parse a `Value` of the specified `datatype` from a string, this is the reverse of Display for `Value`.
+    /// "---" is `Value::Invalid` for floats (a sensor error) and `Value::Unset` for numbers
+    ///
+    /// # Errors
+    ///
+    /// Returns a `BsbError` if the string cannot be parsed or is not valid for the `datatype`
     pub fn from_str(s: &str, datatype: Datatype) -> Result<Value, BsbError> {
         match datatype {
             Datatype::Setting(max) => {
-                let setting = s.parse::<u8>()?;
+                // two-state settings also accept the labels of switches
+                let setting = match parse_bool_label(s) {
+                    Some(state) if max == 1 => u8::from(state),
+                    _ => s.parse::<u8>()?,
+                };
                 if setting > max {
                     return Err(BsbError::InvalidSetting);
                 }
@@ -205,11 +652,22 @@ impl Value {
                     max,
                 })
             }
+            Datatype::Number | Datatype::SignedNumber if s == INVALID_STR => {
+                Ok(Value::Unset { flag: 0, datatype })
+            }
             Datatype::Number => {
                 let value = s.parse::<u16>()?;
                 Ok(Value::Number { flag: 0, value })
             }
+            Datatype::SignedNumber => {
+                let value = s.parse::<i16>()?;
+                Ok(Value::SignedNumber { flag: 0, value })
+            }
+            Datatype::Float(factor) if s == INVALID_STR => Ok(Value::Invalid { flag: 0, factor }),
             Datatype::Float(factor) => {
+                // German manuals and HMIs use a decimal comma, e.g. "55,5"
+                #[cfg(feature = "locale")]
+                let s = s.replacen(',', ".", 1);
                 let value = s.parse::<f32>()?;
                 Ok(Value::Float {
                     flag: 0,
@@ -218,8 +676,12 @@ impl Value {
                 })
             }
             Datatype::DateTime => {
-                let datetime = NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S")?;
-                Ok(Value::DateTime { flag: 0, datetime })
+                let datetime = NaiveDateTime::parse_from_str(s, DATETIME_FORMAT)?;
+                Ok(Value::DateTime {
+                    flag: 0,
+                    datetime,
+                    trailing_flag: 0,
+                })
             }
             Datatype::Schedule => {
                 let mut ranges = Vec::new();
@@ -241,16 +703,48 @@ impl Value {
                 }
                 Ok(Value::Schedule(ranges))
             }
+            Datatype::Trigger(token) => {
+                if s.parse::<u8>()? != token {
+                    return Err(BsbError::InvalidFieldValue);
+                }
+                Ok(Value::Trigger { flag: 0, token })
+            }
+            Datatype::String => {
+                // the flag, the text and the terminator have to fit into the payload
+                if !s.is_ascii() || s.contains('\0') || s.len() + 2 > MAX_PAYLOAD_LENGTH {
+                    return Err(BsbError::InvalidFieldValue);
+                }
+                Ok(Value::String {
+                    flag: 0,
+                    value: s.to_string(),
+                })
+            }
+            Datatype::ErrorRecord => {
+                // "<code> <datetime>"
+                let (code, datetime) = s.split_once(' ').ok_or(BsbError::InvalidFieldValue)?;
+                Ok(Value::ErrorRecord {
+                    flag: 0,
+                    code: code.parse::<u16>()?,
+                    datetime: NaiveDateTime::parse_from_str(datetime, DATETIME_FORMAT)?,
+                })
+            }
         }
     }
 
     /// Access the `flag` if available
+    #[must_use]
     pub fn flag(&self) -> Option<u8> {
         match self {
             Value::Setting { flag, .. }
             | Value::Number { flag, .. }
+            | Value::SignedNumber { flag, .. }
             | Value::Float { flag, .. }
-            | Value::DateTime { flag, .. } => Some(*flag),
+            | Value::Invalid { flag, .. }
+            | Value::Unset { flag, .. }
+            | Value::DateTime { flag, .. }
+            | Value::Trigger { flag, .. }
+            | Value::String { flag, .. }
+            | Value::ErrorRecord { flag, .. } => Some(*flag),
             Value::Schedule(_) => None,
         }
     }
@@ -260,24 +754,107 @@ impl Value {
         match self {
             Value::Setting { flag, .. }
             | Value::Number { flag, .. }
+            | Value::SignedNumber { flag, .. }
             | Value::Float { flag, .. }
-            | Value::DateTime { flag, .. } => *flag = new_flag,
+            | Value::Invalid { flag, .. }
+            | Value::Unset { flag, .. }
+            | Value::DateTime { flag, .. }
+            | Value::Trigger { flag, .. }
+            | Value::String { flag, .. }
+            | Value::ErrorRecord { flag, .. } => *flag = new_flag,
             Value::Schedule(..) => {}
         }
     }
 
+    /// Numeric representation of `Setting`, `Number` and `Float` values, `None` for other values
+    #[must_use]
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Setting { setting, .. } => Some(f64::from(*setting)),
+            Value::Number { value, .. } => Some(f64::from(*value)),
+            Value::SignedNumber { value, .. } => Some(f64::from(*value)),
+            Value::Float { value, .. } => Some(f64::from(*value)),
+            Value::Invalid { .. }
+            | Value::Unset { .. }
+            | Value::DateTime { .. }
+            | Value::Schedule(_)
+            | Value::Trigger { .. }
+            | Value::String { .. }
+            | Value::ErrorRecord { .. } => None,
+        }
+    }
+
+    /// String representation like Display but with a decimal comma for `Float` values, e.g. "55,5"
+    #[cfg(feature = "locale")]
+    #[must_use]
+    pub fn to_string_decimal_comma(&self) -> String {
+        match self {
+            Value::Float { .. } => self.to_string().replacen('.', ",", 1),
+            _ => self.to_string(),
+        }
+    }
+
+    /// Exact `FixedPoint` representation of a `Float` value, `None` for other values
+    #[must_use]
+    pub fn as_fixed_point(&self) -> Option<FixedPoint> {
+        let Value::Float { factor, .. } = self else {
+            return None;
+        };
+        let bytes = self.encode();
+        FixedPoint::new(i16::from_be_bytes([bytes[1], bytes[2]]), *factor).ok()
+    }
+
+    /// Integer representation of `Setting`, `Number` and `Float` values (rounded to the nearest integer),
+    /// `None` for other values
+    #[must_use]
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Setting { setting, .. } => Some(i64::from(*setting)),
+            Value::Number { value, .. } => Some(i64::from(*value)),
+            Value::SignedNumber { value, .. } => Some(i64::from(*value)),
+            // an f32 decoded from 16 bits always fits into an i64
+            #[allow(clippy::cast_possible_truncation)]
+            Value::Float { value, .. } => Some(value.round() as i64),
+            Value::Invalid { .. }
+            | Value::Unset { .. }
+            | Value::DateTime { .. }
+            | Value::Schedule(_)
+            | Value::Trigger { .. }
+            | Value::String { .. }
+            | Value::ErrorRecord { .. } => None,
+        }
+    }
+
+    /// Boolean representation of `Setting`, `Number` and `Float` values (true if not zero),
+    /// `None` for other values
+    #[must_use]
+    pub fn as_bool(&self) -> Option<bool> {
+        self.as_f64().map(|value| value != 0.0)
+    }
+
     /// Retrieve the datatype of this value
+    #[must_use]
     pub fn datatype(&self) -> Datatype {
         match self {
             Value::Setting { max, .. } => Datatype::Setting(*max),
             Value::Number { .. } => Datatype::Number,
-            Value::Float { factor, .. } => Datatype::Float(*factor),
+            Value::SignedNumber { .. } => Datatype::SignedNumber,
+            Value::Float { factor, .. } | Value::Invalid { factor, .. } => Datatype::Float(*factor),
             Value::DateTime { .. } => Datatype::DateTime,
             Value::Schedule(_) => Datatype::Schedule,
+            Value::Trigger { token, .. } => Datatype::Trigger(*token),
+            Value::String { .. } => Datatype::String,
+            Value::ErrorRecord { .. } => Datatype::ErrorRecord,
+            Value::Unset { datatype, .. } => *datatype,
         }
     }
 
     /// Retrieve a default (Zero) `Value` for the specified `Datatype`
+    ///
+    /// # Panics
+    ///
+    /// Does not panic, the unix epoch is always a valid timestamp
+    #[must_use]
     pub fn default_for_datatype(datatype: Datatype) -> Value {
         match datatype {
             Datatype::Setting(max) => Value::Setting {
@@ -286,6 +863,7 @@ impl Value {
                 max,
             },
             Datatype::Number => Value::Number { flag: 0, value: 0 },
+            Datatype::SignedNumber => Value::SignedNumber { flag: 0, value: 0 },
             Datatype::Float(factor) => Value::Float {
                 flag: 0,
                 value: 0.0,
@@ -294,21 +872,77 @@ impl Value {
             Datatype::DateTime => Value::DateTime {
                 flag: 0,
                 datetime: DateTime::from_timestamp(0, 0).unwrap().naive_utc(),
+                trailing_flag: 0,
             },
             Datatype::Schedule => Value::Schedule(vec![(0, 0, 0, 0)]),
+            Datatype::Trigger(token) => Value::Trigger { flag: 0, token },
+            Datatype::String => Value::String {
+                flag: 0,
+                value: String::new(),
+            },
+            Datatype::ErrorRecord => Value::ErrorRecord {
+                flag: 0,
+                code: 0,
+                datetime: DateTime::from_timestamp(0, 0).unwrap().naive_utc(),
+            },
         }
     }
 }
 
+/// Encode the date and time components of a `DateTime` payload without the flags
+///
+/// # Panics
+///
+/// Panics if the date components do not fit into a byte, which chrono guarantees
+fn encode_datetime(datetime: &NaiveDateTime) -> [u8; 7] {
+    [
+        (datetime.year() - 1900).try_into().unwrap_or_default(),
+        datetime.month().try_into().unwrap(),
+        datetime.day().try_into().unwrap(),
+        datetime.weekday().number_from_monday().try_into().unwrap(),
+        datetime.hour().try_into().unwrap(),
+        datetime.minute().try_into().unwrap(),
+        datetime.second().try_into().unwrap(),
+    ]
+}
+
+/// Check if payloads of the `datatype` can mark an unset value with their flag
+pub(crate) fn is_nullable(datatype: Datatype) -> bool {
+    matches!(
+        datatype,
+        Datatype::Number | Datatype::SignedNumber | Datatype::Float(_)
+    )
+}
+
+/// Check if the `payload` of a `Ret` answer marks an unset value with its flag
+fn is_unset(payload: &[u8], datatype: Datatype, packet_type: Option<PacketType>) -> bool {
+    is_nullable(datatype)
+        && payload.first() == Some(&UNSET_FLAG)
+        && packet_type == Some(PacketType::Ret)
+}
+
+/// Parse the label of a two-state setting, e.g. "On" or "no"
+fn parse_bool_label(s: &str) -> Option<bool> {
+    match s.to_ascii_lowercase().as_str() {
+        "on" | "yes" | "true" => Some(true),
+        "off" | "no" | "false" => Some(false),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use std::str::FromStr as _;
+    use std::{cmp::Ordering, collections::HashSet, str::FromStr as _};
 
     use chrono::{DateTime, NaiveDateTime};
 
-    use crate::{BsbError, Datatype, Value};
+    use crate::{
+        BsbError, Datatype, DecodeOptions, Field, PacketType, Quirks, Rounding, Value, WeekdayCheck,
+    };
 
     /// a set of successfull testcases with (<datatype>, <encoded_bytes>, <flag>, <decoded_value>, <value_str>)
+    // one entry per datatype, the table grows with every new datatype
+    #[allow(clippy::too_many_lines)]
     fn datatype_value_success_testcases(
     ) -> Vec<(Datatype, Vec<u8>, Option<u8>, Value, &'static str)> {
         vec![
@@ -330,6 +964,16 @@ mod tests {
                 Value::Number { flag: 0, value: 15 },
                 "15",
             ),
+            (
+                Datatype::SignedNumber,
+                vec![0, 0xff, 0xf1],
+                Some(0),
+                Value::SignedNumber {
+                    flag: 0,
+                    value: -15,
+                },
+                "-15",
+            ),
             (
                 Datatype::Float(10),
                 vec![0, 0, 15],
@@ -363,6 +1007,16 @@ mod tests {
                 },
                 "23",
             ),
+            (
+                Datatype::Float(64),
+                vec![0, 0x80, 0],
+                Some(0),
+                Value::Invalid {
+                    flag: 0,
+                    factor: 64,
+                },
+                "---",
+            ),
             (
                 Datatype::DateTime,
                 vec![0, 124, 11, 11, 1, 9, 36, 57, 0],
@@ -370,6 +1024,7 @@ mod tests {
                 Value::DateTime {
                     flag: 0,
                     datetime: NaiveDateTime::from_str("2024-11-11T09:36:57").unwrap(),
+                    trailing_flag: 0,
                 },
                 "2024-11-11T09:36:57",
             ),
@@ -380,6 +1035,24 @@ mod tests {
                 Value::Schedule(vec![(6, 50, 7, 10), (18, 30, 18, 50)]),
                 "6:50-7:10,18:30-18:50",
             ),
+            (
+                Datatype::Trigger(1),
+                vec![0, 1],
+                Some(0),
+                Value::Trigger { flag: 0, token: 1 },
+                "1",
+            ),
+            (
+                Datatype::ErrorRecord,
+                vec![0, 0, 110, 124, 11, 11, 1, 9, 36, 57],
+                Some(0),
+                Value::ErrorRecord {
+                    flag: 0,
+                    code: 110,
+                    datetime: NaiveDateTime::from_str("2024-11-11T09:36:57").unwrap(),
+                },
+                "110 2024-11-11T09:36:57",
+            ),
         ]
     }
 
@@ -405,6 +1078,69 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "locale")]
+    #[test]
+    fn test_value_decimal_comma() {
+        let testcase = Value::from_str("55,5", Datatype::Float(64)).unwrap();
+        let want = Value::Float {
+            flag: 0,
+            value: 55.5,
+            factor: 64,
+        };
+        assert_eq!(testcase, want);
+        assert_eq!(testcase.to_string_decimal_comma(), "55,5");
+        assert!(Value::from_str("55,5", Datatype::Number).is_err());
+        let datetime = Value::from_str("2024-11-11T09:36:57", Datatype::DateTime).unwrap();
+        assert_eq!(datetime.to_string_decimal_comma(), "2024-11-11T09:36:57");
+    }
+
+    #[test]
+    fn test_value_encode_rounded() {
+        // (<value>, <factor>, <rounding>, <raw value>)
+        let testcases = vec![
+            (0.25, 2, Rounding::HalfEven, 0),
+            (0.75, 2, Rounding::HalfEven, 2),
+            (0.25, 2, Rounding::HalfAwayFromZero, 1),
+            (-0.25, 2, Rounding::HalfAwayFromZero, -1),
+            (22.3, 64, Rounding::TowardZero, 1427),
+            (-1.55, 10, Rounding::Down, -16),
+            (1.51, 10, Rounding::Up, 16),
+        ];
+        for (value, factor, rounding, want) in testcases {
+            let value = Value::Float {
+                flag: 0,
+                value,
+                factor,
+            };
+            let testcase = value.encode_rounded(rounding);
+            let want = i16::to_be_bytes(want);
+            assert_eq!(testcase, vec![0, want[0], want[1]]);
+        }
+    }
+
+    #[test]
+    fn test_value_encode_nearest_representable() {
+        for factor in [2, 10, 50, 64, 100] {
+            for step in -500..500 {
+                let value = f32::from(i16::try_from(step).unwrap()) * 0.073;
+                let encoded = Value::Float {
+                    flag: 0,
+                    value,
+                    factor,
+                }
+                .encode();
+                let Value::Float { value: decoded, .. } =
+                    Value::decode(&encoded, Datatype::Float(factor)).unwrap()
+                else {
+                    panic!("not a float");
+                };
+                // the decoded value is at most half a step of the factor away
+                let difference = f64::from((decoded - value).abs()) * f64::from(factor);
+                assert!(difference <= 0.5 + 1e-3, "{value} with factor {factor}");
+            }
+        }
+    }
+
     #[test]
     fn test_value_decode_encode_identical() {
         for (datatype, bytes, _flag, _value, _display_str) in
@@ -428,6 +1164,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_value_labels() {
+        let field = Field::by_prognr(700).unwrap();
+        // (<value_str>, <setting>, <display_str>)
+        let testcases = vec![
+            ("Automatic", 1, "Automatic"),
+            ("reduced", 2, "Reduced"),
+            ("3", 3, "Comfort"),
+        ];
+        for (value_str, setting, want) in testcases {
+            let testcase = Value::from_str_for(value_str, field).unwrap();
+            assert_eq!(
+                testcase,
+                Value::Setting {
+                    flag: 0,
+                    setting,
+                    max: 3
+                }
+            );
+            assert_eq!(testcase.to_string_for(field), want);
+        }
+        assert!(matches!(
+            Value::from_str_for("Eco", field),
+            Err(BsbError::ParseIntError(_))
+        ));
+    }
+
     #[test]
     fn test_value_to_string() {
         for (_datatype, _bytes, _flag, value, display_str) in
@@ -500,6 +1263,80 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_value_as_numeric() {
+        // (<value>, <as_f64>, <as_i64>, <as_bool>)
+        let testcases = vec![
+            (
+                Value::Setting {
+                    flag: 0,
+                    setting: 2,
+                    max: 3,
+                },
+                Some(2.0),
+                Some(2),
+                Some(true),
+            ),
+            (
+                Value::Number { flag: 0, value: 0 },
+                Some(0.0),
+                Some(0),
+                Some(false),
+            ),
+            (
+                Value::Float {
+                    flag: 0,
+                    value: -1.75,
+                    factor: 64,
+                },
+                Some(-1.75),
+                Some(-2),
+                Some(true),
+            ),
+            (
+                Value::Invalid {
+                    flag: 0,
+                    factor: 64,
+                },
+                None,
+                None,
+                None,
+            ),
+            (Value::Schedule(vec![(6, 0, 7, 0)]), None, None, None),
+        ];
+        for (value, as_f64, as_i64, as_bool) in testcases {
+            assert_eq!(value.as_f64(), as_f64);
+            assert_eq!(value.as_i64(), as_i64);
+            assert_eq!(value.as_bool(), as_bool);
+        }
+    }
+
+    #[test]
+    fn test_value_hash_eq() {
+        let mut set = HashSet::new();
+        for (_datatype, _bytes, _flag, value, _display_str) in datatype_value_success_testcases() {
+            set.insert(value.clone());
+            assert!(set.contains(&value));
+        }
+        assert_eq!(set.len(), datatype_value_success_testcases().len());
+        let nan = Value::Float {
+            flag: 0,
+            value: f32::NAN,
+            factor: 64,
+        };
+        assert_eq!(nan, nan.clone());
+        // the order agrees with the equality, also for NaN and signed zeros
+        assert_eq!(nan.partial_cmp(&nan.clone()), Some(Ordering::Equal));
+        let float = |value| Value::Float {
+            flag: 0,
+            value,
+            factor: 64,
+        };
+        assert_ne!(float(0.0), float(-0.0));
+        assert_eq!(float(-0.0).cmp(&float(0.0)), Ordering::Less);
+        assert_eq!(float(-1.5).cmp(&float(0.5)), Ordering::Less);
+    }
+
     #[test]
     fn test_value_from_string_errors() {
         // a set of error testcases for the value from string method (<datatype>, <string>, <error>)
@@ -536,6 +1373,10 @@ mod tests {
             Value::default_for_datatype(Datatype::Number),
             Value::Number { flag: 0, value: 0 }
         );
+        assert_eq!(
+            Value::default_for_datatype(Datatype::SignedNumber),
+            Value::SignedNumber { flag: 0, value: 0 }
+        );
         assert_eq!(
             Value::default_for_datatype(Datatype::Float(10)),
             Value::Float {
@@ -548,7 +1389,8 @@ mod tests {
             Value::default_for_datatype(Datatype::DateTime),
             Value::DateTime {
                 flag: 0,
-                datetime: DateTime::from_timestamp(0, 0).unwrap().naive_utc()
+                datetime: DateTime::from_timestamp(0, 0).unwrap().naive_utc(),
+                trailing_flag: 0,
             }
         );
         assert_eq!(
@@ -557,12 +1399,328 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_value_encode_for() {
+        for (_datatype, bytes, flag, value, _display_str) in datatype_value_success_testcases() {
+            assert_eq!(value.encode_for(PacketType::Ret).unwrap(), bytes);
+            let mut want = bytes.clone();
+            if flag.is_some() {
+                want[0] = 1;
+            }
+            match value {
+                Value::Invalid { .. } => assert_eq!(
+                    value.encode_for(PacketType::Set),
+                    Err(BsbError::InvalidFieldValue)
+                ),
+                Value::Unset { .. } => {
+                    want[0] = 5;
+                    assert_eq!(value.encode_for(PacketType::Set).unwrap(), want);
+                }
+                _ => assert_eq!(value.encode_for(PacketType::Set).unwrap(), want),
+            }
+            let want = if flag.is_some() { &bytes[1..] } else { &bytes };
+            assert_eq!(value.encode_for(PacketType::Info).unwrap(), want);
+        }
+    }
+
+    #[test]
+    fn test_value_encode_for_set_out_of_range() {
+        for value in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY, 512.0, -512.5] {
+            let testcase = Value::Float {
+                flag: 0,
+                value,
+                factor: 64,
+            };
+            assert_eq!(
+                testcase.encode_for(PacketType::Set),
+                Err(BsbError::OutOfRange)
+            );
+        }
+        // the limits of the raw integer
+        for value in [511.98, -512.0] {
+            let testcase = Value::Float {
+                flag: 0,
+                value,
+                factor: 64,
+            };
+            assert!(testcase.encode_for(PacketType::Set).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_value_encode_for_set_unset() {
+        let testcase = Value::Unset {
+            flag: 1,
+            datatype: Datatype::Float(64),
+        };
+        assert_eq!(testcase.encode_for(PacketType::Set).unwrap(), vec![5, 0, 0]);
+        // the flag of an answer is kept
+        assert_eq!(testcase.encode_for(PacketType::Ret).unwrap(), vec![1, 0, 0]);
+    }
+
+    #[test]
+    fn test_value_datetime_trailing_flag_round_trip() {
+        let payload = vec![0, 124, 11, 11, 1, 9, 36, 57, 1];
+        let testcase = Value::decode(&payload, Datatype::DateTime).unwrap();
+        let want = Value::DateTime {
+            flag: 0,
+            datetime: NaiveDateTime::from_str("2024-11-11T09:36:57").unwrap(),
+            trailing_flag: 1,
+        };
+        assert_eq!(testcase, want);
+        assert_eq!(testcase.encode(), payload);
+    }
+
+    #[test]
+    fn test_value_no_flag_byte_quirk() {
+        let options = DecodeOptions::default().with_quirks(Quirks::NO_FLAG_BYTE);
+        for (datatype, bytes, _flag, value, _display_str) in datatype_value_success_testcases() {
+            let payload = value.encode_with(Quirks::NO_FLAG_BYTE);
+            if datatype == Datatype::Schedule {
+                assert_eq!(payload, bytes);
+            } else {
+                assert_eq!(payload, bytes[1..]);
+            }
+            let testcase = Value::decode_with(&payload, datatype, &options).unwrap();
+            assert_eq!(testcase, value);
+        }
+    }
+
+    #[test]
+    fn test_value_decode_weekday_check() {
+        // 2024-11-11 is a monday (1) but the payload claims a tuesday (2)
+        let payload = vec![0, 124, 11, 11, 2, 9, 36, 57, 0];
+        let want = Value::DateTime {
+            flag: 0,
+            datetime: NaiveDateTime::from_str("2024-11-11T09:36:57").unwrap(),
+            trailing_flag: 0,
+        };
+        let options = DecodeOptions::default();
+        assert_eq!(
+            Value::decode_with(&payload, Datatype::DateTime, &options),
+            Ok(want.clone())
+        );
+        let options = options.with_weekday_check(WeekdayCheck::Reject);
+        assert_eq!(
+            Value::decode_with(&payload, Datatype::DateTime, &options),
+            Err(BsbError::InvalidWeekday)
+        );
+        let mut repaired = payload.clone();
+        assert_eq!(Value::repair_weekday(&mut repaired), Ok(true));
+        assert_eq!(repaired[4], 1);
+        assert_eq!(Value::repair_weekday(&mut repaired), Ok(false));
+        assert_eq!(
+            Value::decode_with(&repaired, Datatype::DateTime, &options),
+            Ok(want)
+        );
+    }
+
+    #[test]
+    fn test_value_decode_schedule_terminators() {
+        // (<payload>, <ranges>)
+        let testcases = vec![
+            // all three slots used, no terminator
+            (
+                vec![6, 0, 8, 0, 12, 0, 13, 0, 17, 30, 22, 0],
+                vec![(6, 0, 8, 0), (12, 0, 13, 0), (17, 30, 22, 0)],
+            ),
+            // unused slots filled with the terminator
+            (
+                vec![6, 0, 22, 0, 0x98, 0, 0x18, 0, 0x98, 0, 0x18, 0],
+                vec![(6, 0, 22, 0)],
+            ),
+            // zeroed terminator followed by garbage in the unused slot
+            (
+                vec![5, 30, 21, 0, 0x80, 0, 0, 0, 0xff, 0xff, 0xff, 0xff],
+                vec![(5, 30, 21, 0)],
+            ),
+            // terminator followed by padding that is not a full slot
+            (
+                vec![6, 50, 7, 10, 0x98, 0, 0x18, 0, 0],
+                vec![(6, 50, 7, 10)],
+            ),
+            // truncated terminator
+            (vec![6, 50, 7, 10, 0x98, 0], vec![(6, 50, 7, 10)]),
+            // no ranges at all
+            (vec![0x98, 0, 0x18, 0], vec![]),
+        ];
+        for (payload, ranges) in testcases {
+            assert_eq!(
+                Value::decode(&payload, Datatype::Schedule).unwrap(),
+                Value::Schedule(ranges)
+            );
+        }
+    }
+
+    #[test]
+    fn test_value_string() {
+        let want = Value::String {
+            flag: 0,
+            value: "RVS43".to_string(),
+        };
+        // (<payload>) with and without padding after the terminator
+        let testcases = vec![
+            vec![0, b'R', b'V', b'S', b'4', b'3', 0],
+            vec![0, b'R', b'V', b'S', b'4', b'3', 0, 0x20, 0xff],
+        ];
+        for payload in testcases {
+            let testcase = Value::decode(&payload, Datatype::String).unwrap();
+            assert_eq!(testcase, want);
+        }
+        assert_eq!(want.encode(), [0, b'R', b'V', b'S', b'4', b'3', 0]);
+        assert_eq!(want.to_string(), "RVS43");
+        assert_eq!(Value::from_str("RVS43", Datatype::String).unwrap(), want);
+        assert_eq!(
+            Value::decode(&[0, 0xc4, 0], Datatype::String),
+            Err(BsbError::InvalidFieldValue)
+        );
+        assert_eq!(
+            Value::from_str("Ä", Datatype::String),
+            Err(BsbError::InvalidFieldValue)
+        );
+        assert_eq!(
+            Value::from_str(&"x".repeat(60), Datatype::String),
+            Err(BsbError::InvalidFieldValue)
+        );
+    }
+
+    #[test]
+    fn test_value_decode_unset() {
+        // (<packet type>, <payload>, <datatype>, <value>)
+        let testcases = vec![
+            (
+                Some(PacketType::Ret),
+                vec![1, 0, 0],
+                Datatype::Float(64),
+                Value::Unset {
+                    flag: 1,
+                    datatype: Datatype::Float(64),
+                },
+            ),
+            // `Info` broadcasts carry no flag
+            (
+                Some(PacketType::Info),
+                vec![5, 0x60],
+                Datatype::Float(64),
+                Value::Float {
+                    flag: 0,
+                    value: 21.5,
+                    factor: 64,
+                },
+            ),
+            // the flag 1 enables the value of a `Set`
+            (
+                Some(PacketType::Set),
+                vec![1, 0, 15],
+                Datatype::Float(10),
+                Value::Float {
+                    flag: 1,
+                    value: 1.5,
+                    factor: 10,
+                },
+            ),
+            // without the packet type the flag is not interpreted
+            (
+                None,
+                vec![1, 0, 15],
+                Datatype::Number,
+                Value::Number { flag: 1, value: 15 },
+            ),
+            // settings cannot be unset
+            (
+                Some(PacketType::Ret),
+                vec![1, 1],
+                Datatype::Setting(1),
+                Value::Setting {
+                    flag: 1,
+                    setting: 1,
+                    max: 1,
+                },
+            ),
+        ];
+        for (packet_type, payload, datatype, want) in testcases {
+            let options = packet_type.map_or_else(DecodeOptions::default, |packet_type| {
+                DecodeOptions::default().with_packet_type(packet_type)
+            });
+            let testcase = Value::decode_with(&payload, datatype, &options).unwrap();
+            assert_eq!(testcase, want);
+        }
+        let unset = Value::Unset {
+            flag: 1,
+            datatype: Datatype::Float(64),
+        };
+        assert_eq!(unset.to_string(), "---");
+        assert_eq!(unset.encode(), [1, 0, 0]);
+        assert_eq!(unset.as_f64(), None);
+        assert_eq!(unset.datatype(), Datatype::Float(64));
+    }
+
+    #[test]
+    fn test_value_from_str_unset() {
+        // (<datatype>, <value>)
+        let testcases = vec![
+            (
+                Datatype::Float(64),
+                Value::Invalid {
+                    flag: 0,
+                    factor: 64,
+                },
+            ),
+            (
+                Datatype::Number,
+                Value::Unset {
+                    flag: 0,
+                    datatype: Datatype::Number,
+                },
+            ),
+            (
+                Datatype::SignedNumber,
+                Value::Unset {
+                    flag: 0,
+                    datatype: Datatype::SignedNumber,
+                },
+            ),
+        ];
+        for (datatype, want) in testcases {
+            assert_eq!(Value::from_str("---", datatype).unwrap(), want);
+        }
+        // writable fields can be unset
+        let field = Field::by_name("heating_circuit_1_set_point_comfort").unwrap();
+        let testcase = Value::from_str_for("---", field).unwrap();
+        assert_eq!(
+            testcase,
+            Value::Unset {
+                flag: 0,
+                datatype: Datatype::Float(64)
+            }
+        );
+        assert_eq!(testcase.encode_for(PacketType::Set).unwrap(), [5, 0, 0]);
+        assert_eq!(
+            Value::from_str_for(&testcase.to_string_for(field), field).unwrap(),
+            testcase
+        );
+        // read-only fields report sensor errors
+        let field = Field::by_name("warmwater_temperature").unwrap();
+        assert_eq!(
+            Value::from_str_for("---", field).unwrap(),
+            Value::Invalid {
+                flag: 0,
+                factor: 64
+            }
+        );
+    }
+
     #[test]
     fn test_value_decode_errors() {
         // a set of error testcases to test the decoder (<datatype>, <encoded>, <error>)
         let error_testcases = vec![
             (Datatype::Setting(2), vec![0, 3], BsbError::InvalidSetting),
             (Datatype::Number, vec![0, 0], BsbError::InvalidPayloadLength),
+            (
+                Datatype::SignedNumber,
+                vec![0, 0],
+                BsbError::InvalidPayloadLength,
+            ),
             (
                 Datatype::Float(10),
                 vec![0, 0],
@@ -588,6 +1746,21 @@ mod tests {
                 vec![6, 50, 7, 10, 18, 30, 18, 60, 24 ^ 0x80, 0, 24, 0],
                 BsbError::InvalidSchedule,
             ),
+            (
+                Datatype::Trigger(1),
+                vec![0, 2],
+                BsbError::InvalidFieldValue,
+            ),
+            (
+                Datatype::ErrorRecord,
+                vec![0, 0, 110, 124, 11, 11, 1, 9, 36],
+                BsbError::InvalidPayloadLength,
+            ),
+            (
+                Datatype::ErrorRecord,
+                vec![0, 0, 110, 124, 13, 11, 1, 9, 36, 57],
+                BsbError::InvalidDateTime,
+            ),
         ];
         for (datatype, bytes, error) in error_testcases.into_iter() {
             let testcase = Value::decode(&bytes, datatype).expect_err("not an error");